@@ -0,0 +1,91 @@
+//! Movement benchmark
+//!
+//! Measures the cost of a single game tick, movement together with eating and the
+//! collision checks, for a range of snake lengths and arena sizes. Run it with
+//! `cargo bench`.
+//!
+//! Each configuration runs the snake plugins headlessly, the same way the tests do.
+//! The snake starts as a straight line up the first column of the arena and the top
+//! and bottom edges wrap, so it can move for as long as needed without dying. The
+//! last column is filled with food the snake never reaches, which keeps the eater
+//! busy checking every piece on each tick.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use gametime::{
+    arena::{ArenaConfig, Direction, Position},
+    food::Food,
+    score::ScorePlugin,
+    snake::{EdgeWrap, MovementTimer, SnakePlugin, SnakeStart},
+};
+
+const ARENA_SIZES: [i32; 3] = [16, 64, 256];
+const SNAKE_LENGTHS: [usize; 4] = [4, 16, 64, 250];
+const WARMUP_TICKS: u32 = 100;
+const MEASURED_TICKS: u32 = 2_000;
+
+/// A headless game on a `size` by `size` arena with a snake `length` tiles long
+fn bench_app(size: i32, length: usize) -> App {
+    let mut app = App::new();
+    app.insert_resource(Time::<Virtual>::default());
+    app.insert_resource(ArenaConfig {
+        width: size,
+        height: size,
+        ..Default::default()
+    });
+    app.insert_resource(SnakeStart {
+        head: Position {
+            x: 0,
+            y: length as i32 - 1,
+        },
+        direction: Direction::Up,
+        length,
+    });
+    app.insert_resource(EdgeWrap {
+        wrap_x: false,
+        wrap_y: true,
+    });
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.add_plugins((SnakePlugin, ScorePlugin));
+    for y in 0..size {
+        app.world_mut().spawn((Food, Position { x: size - 1, y }));
+    }
+    app.update();
+    app
+}
+
+/// Advances the game by one movement tick
+fn tick(app: &mut App) {
+    let interval = app.world().resource::<MovementTimer>().interval();
+    app.world_mut()
+        .resource_mut::<Time<Virtual>>()
+        .advance_by(interval);
+    app.update();
+}
+
+/// The mean time taken by a tick
+fn measure(app: &mut App) -> Duration {
+    for _ in 0..WARMUP_TICKS {
+        tick(app);
+    }
+    let start = Instant::now();
+    for _ in 0..MEASURED_TICKS {
+        tick(app);
+    }
+    start.elapsed() / MEASURED_TICKS
+}
+
+fn main() {
+    println!("{:>10} {:>8} {:>12}", "arena", "length", "per tick");
+    for size in ARENA_SIZES {
+        for length in SNAKE_LENGTHS
+            .into_iter()
+            .filter(|length| *length < size as usize)
+        {
+            let per_tick = measure(&mut bench_app(size, length));
+            let arena = format!("{}x{}", size, size);
+            println!("{:>10} {:>8} {:>12?}", arena, length, per_tick);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! AI module
+//!
+//! This module contains the pathfinding used to work out good moves for a snake.
+//!
+//! Paths are found with a breadth-first search over the arena grid, so the first
+//! step returned always lies on a shortest route to the nearest reachable food.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::arena::{self, ArenaConfig, Direction, Position};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+/// Finds the tile a snake at `head` travelling in `direction` should move to next to
+/// reach the closest of `food` as quickly as possible.
+///
+/// Tiles in `blocked` and outside the arena are avoided, and the first step never
+/// reverses the snake. Returns `None` when no food can be reached.
+pub fn next_step(
+    head: Position,
+    direction: Direction,
+    arena: &ArenaConfig,
+    blocked: &[Position],
+    food: &[Position],
+) -> Option<Position> {
+    let open = |tile: Position| arena::in_bounds(tile, arena) && !blocked.contains(&tile);
+
+    // Maps each visited tile to the first step taken on the way to it
+    let mut first_steps: HashMap<Position, Position> = HashMap::new();
+    let mut frontier = VecDeque::new();
+    for dir in DIRECTIONS {
+        let tile = head.neighbor(dir);
+        if dir != direction.opposite() && open(tile) && !first_steps.contains_key(&tile) {
+            first_steps.insert(tile, tile);
+            frontier.push_back(tile);
+        }
+    }
+
+    while let Some(tile) = frontier.pop_front() {
+        let first = first_steps[&tile];
+        if food.contains(&tile) {
+            return Some(first);
+        }
+        for dir in DIRECTIONS {
+            let next = tile.neighbor(dir);
+            if next != head && open(next) && !first_steps.contains_key(&next) {
+                first_steps.insert(next, first);
+                frontier.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_step_heads_toward_food() {
+        let arena = ArenaConfig::default();
+        let head = Position { x: 3, y: 3 };
+        let food = [Position { x: 7, y: 3 }];
+
+        assert_eq!(
+            next_step(head, Direction::Up, &arena, &[], &food),
+            Some(Position { x: 4, y: 3 })
+        );
+    }
+
+    #[test]
+    fn test_next_step_never_reverses() {
+        let arena = ArenaConfig::default();
+        let head = Position { x: 3, y: 3 };
+        let food = [Position { x: 3, y: 1 }];
+
+        let step = next_step(head, Direction::Up, &arena, &[], &food).unwrap();
+        assert_ne!(step, Position { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn test_next_step_routes_around_body() {
+        let arena = ArenaConfig::default();
+        let head = Position { x: 0, y: 0 };
+        let blocked = [Position { x: 1, y: 0 }, Position { x: 1, y: 1 }];
+        let food = [Position { x: 2, y: 0 }];
+
+        assert_eq!(
+            next_step(head, Direction::Right, &arena, &blocked, &food),
+            Some(Position { x: 0, y: 1 })
+        );
+    }
+
+    #[test]
+    fn test_next_step_without_reachable_food() {
+        let arena = ArenaConfig::default();
+        let head = Position { x: 0, y: 0 };
+        let blocked = [Position { x: 1, y: 0 }, Position { x: 0, y: 1 }];
+
+        assert_eq!(
+            next_step(
+                head,
+                Direction::Up,
+                &arena,
+                &blocked,
+                &[Position { x: 5, y: 5 }]
+            ),
+            None
+        );
+    }
+}
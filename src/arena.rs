@@ -8,20 +8,244 @@
 //! - Handling collision detection with arena walls
 //! - Managing the coordinate system for game entities
 //! - Providing utilities for position validation
+//! - Shaping non-rectangular arenas with an `ArenaMask`
+//! - Cutting outer rings off the arena with `ArenaConfig::inset`
+//! - Insetting the board from the window edges with `ArenaConfig::margin`
+//! - Snapping sprites to whole pixels with `PixelSnap`
+
+use std::ops::{Add, Sub};
+
+use serde::{Deserialize, Serialize};
 
 use bevy::{
-    ecs::{component::Component, query::With, system::Query}, math::Vec3, transform::components::Transform, window::{PrimaryWindow, Window}
+    color::Color,
+    ecs::{
+        change_detection::DetectChanges,
+        component::Component,
+        entity::Entity,
+        query::With,
+        system::{Commands, Query, Res, Resource},
+    },
+    math::Vec2,
+    sprite::Sprite,
+    transform::components::Transform,
+    window::{PrimaryWindow, Window},
 };
 
 pub const WIDTH: f32 = 10.;
 pub const HEIGHT: f32 = 10.;
 
-#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+/// The dimensions of the arena in tiles and where it is placed in the world
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct ArenaConfig {
+    pub width: i32,
+    pub height: i32,
+    /// World-space offset of the center of the board. The board is centered on the
+    /// world origin by default.
+    pub origin: Vec2,
+    /// The playable tiles of a non-rectangular arena. Every tile is playable when unset.
+    pub mask: Option<ArenaMask>,
+    /// How many outer rings of tiles are cut off the arena, as the shrinking arena mode
+    /// does over time. Tiles in these rings are not playable.
+    pub inset: i32,
+    /// The fraction of the window left empty on each side of the board, leaving room
+    /// for the HUD around it. The board fills the window when zero.
+    pub margin: f32,
+}
+
+impl ArenaConfig {
+    /// An arena shaped by `mask`, sized to fit it
+    pub fn from_mask(mask: ArenaMask) -> Self {
+        Self {
+            width: mask.width,
+            height: mask.height,
+            mask: Some(mask),
+            ..Default::default()
+        }
+    }
+
+    /// The middle tile of the arena, rounding toward the bottom left on even sizes
+    pub fn center_tile(&self) -> Position {
+        Position {
+            x: (self.width - 1) / 2,
+            y: (self.height - 1) / 2,
+        }
+    }
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        Self {
+            width: WIDTH as i32,
+            height: HEIGHT as i32,
+            origin: Vec2::ZERO,
+            mask: None,
+            inset: 0,
+            margin: 0.0,
+        }
+    }
+}
+
+/// Marks which tiles of an arena can be played on, allowing shapes such as a plus
+/// or a donut
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ArenaMask {
+    width: i32,
+    height: i32,
+    /// Row-major from the bottom left corner, matching `free_tiles`
+    playable: Vec<bool>,
+}
+
+impl ArenaMask {
+    /// Builds a mask from rows of text as they would appear on screen, top row first.
+    ///
+    /// `#` marks a tile that is not part of the arena and any other character marks a
+    /// playable tile. Short rows are padded with unplayable tiles.
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let playable = rows
+            .iter()
+            .rev()
+            .flat_map(|row| {
+                let mut tiles: Vec<bool> = row.chars().map(|tile| tile != '#').collect();
+                tiles.resize(width, false);
+                tiles
+            })
+            .collect();
+        Self {
+            width: width as i32,
+            height: rows.len() as i32,
+            playable,
+        }
+    }
+
+    /// Builds a `width` by `height` mask where every tile is playable except `walls`
+    pub fn from_walls(width: i32, height: i32, walls: &[Position]) -> Self {
+        let playable = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Position { x, y }))
+            .map(|tile| !walls.contains(&tile))
+            .collect();
+        Self {
+            width,
+            height,
+            playable,
+        }
+    }
+
+    /// Returns true if `pos` is a playable tile of the mask
+    pub fn is_playable(&self, pos: Position) -> bool {
+        (0..self.width).contains(&pos.x)
+            && (0..self.height).contains(&pos.y)
+            && self.playable[(pos.y * self.width + pos.x) as usize]
+    }
+
+    /// Makes `pos` playable or cuts it out of the mask. Tiles outside the mask are
+    /// left alone.
+    pub fn set_playable(&mut self, pos: Position, playable: bool) {
+        if (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y) {
+            self.playable[(pos.y * self.width + pos.x) as usize] = playable;
+        }
+    }
+
+    /// The tiles cut out of the mask, in row-major order from the bottom left corner
+    pub fn walls(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| Position { x, y }))
+            .filter(|tile| !self.is_playable(*tile))
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    Left,
+    Up,
+    Right,
+    Down,
+}
+
+impl Direction {
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Left => Self::Right,
+            Direction::Up => Self::Down,
+            Direction::Right => Self::Left,
+            Direction::Down => Self::Up,
+        }
+    }
+
+    pub fn clockwise(self) -> Self {
+        match self {
+            Direction::Left => Self::Up,
+            Direction::Up => Self::Right,
+            Direction::Right => Self::Down,
+            Direction::Down => Self::Left,
+        }
+    }
+
+    pub fn counter_clockwise(self) -> Self {
+        self.clockwise().opposite()
+    }
+
+    /// The change in position from taking a single step in this direction
+    pub fn offset(self) -> Position {
+        match self {
+            Direction::Left => Position { x: -1, y: 0 },
+            Direction::Up => Position { x: 0, y: 1 },
+            Direction::Right => Position { x: 1, y: 0 },
+            Direction::Down => Position { x: 0, y: -1 },
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
 
+impl Position {
+    /// The adjacent tile in `dir`. The result may lie outside the arena.
+    pub fn neighbor(self, dir: Direction) -> Position {
+        self + dir.offset()
+    }
+
+    /// The adjacent tile in `dir`, wrapping around to the opposite edge when the
+    /// step would leave the arena
+    pub fn wrapped(self, dir: Direction, arena: &ArenaConfig) -> Position {
+        let next = self.neighbor(dir);
+        Position {
+            x: next.x.rem_euclid(arena.width),
+            y: next.y.rem_euclid(arena.height),
+        }
+    }
+}
+
+impl Add for Position {
+    type Output = Position;
+
+    fn add(self, rhs: Position) -> Position {
+        Position {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl Sub for Position {
+    type Output = Position;
+
+    fn sub(self, rhs: Position) -> Position {
+        Position {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Size {
     width: f32,
@@ -37,6 +261,118 @@ impl Size {
     }
 }
 
+/// Scales an entity's `Size` when it is drawn, for example to taper the snake toward
+/// its tail. Entities without one are drawn at their `Size`.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct SizeModifier(pub f32);
+
+/// The block of tiles occupied by an entity larger than a tile, such as big food or
+/// a big snake head.
+///
+/// The footprint is anchored at the entity's `Position` (its bottom left tile) and
+/// extends `width` tiles to the right and `height` tiles up. Entities without one
+/// cover a single tile.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FootprintSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl FootprintSize {
+    pub fn square(size: i32) -> Self {
+        Self {
+            width: size,
+            height: size,
+        }
+    }
+
+    /// Returns true if `tile` falls within this footprint when anchored at `origin`
+    pub fn covers(&self, origin: &Position, tile: &Position) -> bool {
+        (origin.x..origin.x + self.width).contains(&tile.x)
+            && (origin.y..origin.y + self.height).contains(&tile.y)
+    }
+
+    /// Returns every tile covered by this footprint when anchored at `origin`
+    pub fn tiles(self, origin: Position) -> impl Iterator<Item = Position> {
+        (0..self.height).flat_map(move |dy| {
+            (0..self.width).map(move |dx| Position {
+                x: origin.x + dx,
+                y: origin.y + dy,
+            })
+        })
+    }
+}
+
+impl Default for FootprintSize {
+    fn default() -> Self {
+        Self::square(1)
+    }
+}
+
+/// Returns true if `pos` lies on a playable tile inside the arena
+pub fn in_bounds(pos: Position, arena: &ArenaConfig) -> bool {
+    (arena.inset..arena.width - arena.inset).contains(&pos.x)
+        && (arena.inset..arena.height - arena.inset).contains(&pos.y)
+        && arena.mask.as_ref().is_none_or(|mask| mask.is_playable(pos))
+}
+
+/// Returns true if any entry in `occupied` sits on `tile`
+pub fn is_occupied(tile: Position, occupied: &[Position]) -> bool {
+    occupied.contains(&tile)
+}
+
+/// Returns every playable arena tile that is not in `occupied`, in row-major order starting
+/// from the bottom left corner.
+///
+/// The ordering is stable so that picking from the result is deterministic for a
+/// given index.
+pub fn free_tiles(arena: &ArenaConfig, occupied: &[Position]) -> Vec<Position> {
+    (0..arena.height)
+        .flat_map(|y| (0..arena.width).map(move |x| Position { x, y }))
+        .filter(|tile| in_bounds(*tile, arena) && !is_occupied(*tile, occupied))
+        .collect()
+}
+
+/// Marks a tile that is cut out of a masked arena
+#[derive(Component)]
+pub struct Hole;
+
+const HOLE_COLOR: Color = Color::srgb(0.02, 0.02, 0.02);
+
+/// Covers every unplayable tile of a masked or shrunk arena so the shape of the
+/// playfield is visible, redrawing whenever the arena changes
+pub fn draw_holes(
+    mut commands: Commands,
+    arena: Res<ArenaConfig>,
+    holes: Query<Entity, With<Hole>>,
+) {
+    if !arena.is_changed() {
+        return;
+    }
+    for hole in holes.iter() {
+        commands.entity(hole).despawn();
+    }
+    if arena.mask.is_none() && arena.inset == 0 {
+        return;
+    }
+    for y in 0..arena.height {
+        for x in 0..arena.width {
+            let tile = Position { x, y };
+            if !in_bounds(tile, &arena) {
+                commands.spawn((
+                    Sprite {
+                        color: HOLE_COLOR,
+                        ..Default::default()
+                    },
+                    Hole,
+                    tile,
+                    Size::square(1.0),
+                ));
+            }
+        }
+    }
+}
+
 /// Scales the size of game entities based on the window dimensions.
 ///
 /// This system adjusts the scale of entities with `Size` and `Transform` components to maintain
@@ -45,27 +381,76 @@ impl Size {
 ///
 /// # Arguments
 /// * `window` - Query for the primary window to get current dimensions
-/// * `size_transform` - Query for entities with both Size and Transform components
+/// * `arena` - The arena dimensions in tiles
+/// * `size_transform` - Query for entities with both Size and Transform components, and
+///   an optional `SizeModifier`
 ///
 /// The scaling is calculated by:
 /// 1. Getting the current window dimensions
 /// 2. For each entity, computing scale factors based on:
-///    - The entity's defined size (width/height)
-///    - The game arena dimensions (WIDTH/HEIGHT constants)
+///    - The entity's defined size (width/height), multiplied by its `SizeModifier`
+///    - The game arena dimensions (`ArenaConfig`)
 ///    - The current window dimensions
 ///
-/// This maintains consistent relative sizes as the window is resized.
-pub fn scale_size(window: Query<&Window, With<PrimaryWindow>>, mut size_transform: Query<(&Size, &mut Transform)>) {
+/// This maintains consistent relative sizes as the window is resized. With `PixelSnap`
+/// enabled the edges of entities on the board are rounded to whole pixels, and the size
+/// of anything else is rounded.
+#[allow(clippy::type_complexity)]
+pub fn scale_size(
+    window: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<ArenaConfig>,
+    snap: Option<Res<PixelSnap>>,
+    mut size_transform: Query<(
+        &Size,
+        Option<&SizeModifier>,
+        Option<&Position>,
+        Option<&FootprintSize>,
+        &mut Transform,
+    )>,
+) {
     let window = window.single();
-    for (size, mut transform) in size_transform.iter_mut() {
-        transform.scale = Vec3::new(
-            size.width / WIDTH * window.width(),
-            size.height / WIDTH * window.height(),
-            1.0
-        )
+    let tile = tile_size(window.size(), &arena);
+    let snap = snap.is_some_and(|snap| snap.enabled);
+    for (size, modifier, pos, footprint, mut transform) in size_transform.iter_mut() {
+        let modifier = modifier.map_or(1.0, |modifier| modifier.0);
+        let size = Vec2::new(size.width, size.height) * modifier;
+        let scale = match pos {
+            Some(pos) if snap => {
+                let (min, max) = tile_rect(*pos, footprint, size, window.size(), &arena);
+                snap_to_pixels(max) - snap_to_pixels(min)
+            }
+            _ if snap => snap_to_pixels(size * tile),
+            _ => size * tile,
+        };
+        transform.scale = scale.extend(1.0);
     }
 }
 
+/// Rounds the edges of sprites to whole pixels, keeping the seams between neighbouring
+/// tiles crisp on window sizes that do not divide evenly into tiles. Snapping the edges
+/// rather than the center and size on their own keeps tiles that share an edge meeting
+/// exactly, with no gap or overlap. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PixelSnap {
+    pub enabled: bool,
+}
+
+/// Rounds `value` to the nearest whole pixel on each axis
+pub fn snap_to_pixels(value: Vec2) -> Vec2 {
+    value.round()
+}
+
+/// The size of the board within `window`, after leaving the arena `margin` empty on
+/// each side
+pub fn board_size(window: Vec2, arena: &ArenaConfig) -> Vec2 {
+    window * (1. - 2. * arena.margin.clamp(0., 0.5))
+}
+
+/// The size of a single tile of the board within `window`
+pub fn tile_size(window: Vec2, arena: &ArenaConfig) -> Vec2 {
+    board_size(window, arena) / Vec2::new(arena.width as f32, arena.height as f32)
+}
+
 /// Converts a position from the game arena dimensions to the window dimensions.
 ///
 /// This function converts a position from the game arena's coordinate system to the window's
@@ -77,6 +462,43 @@ fn convert(pos: f32, window_bounds: f32, game_bounds: f32) -> f32 {
     pos / game_bounds * window_bounds - (window_bounds / 2.) + (tile_size / 2.)
 }
 
+/// The world-space corners, bottom left then top right, of a rectangle `size` tiles
+/// across centered on the block of tiles `footprint` covers from `pos`.
+///
+/// Tiles are measured from the corner of the board, so rectangles that share an edge
+/// on the board compute it identically.
+fn tile_rect(
+    pos: Position,
+    footprint: Option<&FootprintSize>,
+    size: Vec2,
+    window: Vec2,
+    arena: &ArenaConfig,
+) -> (Vec2, Vec2) {
+    let board = board_size(window, arena);
+    let tile = tile_size(window, arena);
+    let corner = arena.origin - board / 2.;
+    let footprint = footprint.map_or(Vec2::ONE, |footprint| {
+        Vec2::new(footprint.width as f32, footprint.height as f32)
+    });
+    let center = Vec2::new(pos.x as f32, pos.y as f32) + footprint / 2.;
+    (
+        corner + (center - size / 2.) * tile,
+        corner + (center + size / 2.) * tile,
+    )
+}
+
+/// Maps a tile to the world-space location of its center.
+///
+/// The board is stretched to fill `window`, less the arena `margin`, and then shifted
+/// by the arena `origin`.
+pub fn grid_to_world(pos: Position, window: Vec2, arena: &ArenaConfig) -> Vec2 {
+    let board = board_size(window, arena);
+    Vec2::new(
+        convert(pos.x as f32, board.x, arena.width as f32),
+        convert(pos.y as f32, board.y, arena.height as f32),
+    ) + arena.origin
+}
+
 /// Translates the position of game entities based on the window dimensions.
 ///
 /// This system adjusts the position of entities with `Position` and `Transform` components
@@ -85,19 +507,308 @@ fn convert(pos: f32, window_bounds: f32, game_bounds: f32) -> f32 {
 ///
 /// # Arguments
 /// * `window` - Query for the primary window to get current dimensions
+/// * `arena` - The arena dimensions in tiles
 /// * `position_transform` - Query for entities with both Position and Transform components
 ///
 /// The translation is calculated by:
 /// 1. Getting the current window dimensions
 /// 2. For each entity, converting the position to the correct location based on:
 ///    - The entity's position (x/y)
-///    - The game arena dimensions (WIDTH/HEIGHT constants) 
-pub fn position_translation(window: Query<&Window, With<PrimaryWindow>>, mut position_transform: Query<(&Position, &mut Transform)>) {
+///    - The game arena dimensions and origin (`ArenaConfig`)
+///
+/// Entities with a `FootprintSize` are centered on the whole block of tiles they cover.
+/// With `PixelSnap` enabled the entity is centered between its edges rounded to whole
+/// pixels, matching the size `scale_size` gives it.
+#[allow(clippy::type_complexity)]
+pub fn position_translation(
+    window: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<ArenaConfig>,
+    snap: Option<Res<PixelSnap>>,
+    mut position_transform: Query<(
+        &Position,
+        Option<&FootprintSize>,
+        Option<&Size>,
+        Option<&SizeModifier>,
+        &mut Transform,
+    )>,
+) {
     let window = window.single();
-    for (pos, mut transform) in position_transform.iter_mut() {
-        let x = convert(pos.x as f32, window.width(), WIDTH);
-        let y = convert(pos.y as f32, window.height(), HEIGHT);
-        transform.translation = Vec3::new(x, y, 0.0);
+    let snap = snap.is_some_and(|snap| snap.enabled);
+    for (pos, footprint, size, modifier, mut transform) in position_transform.iter_mut() {
+        let size = size.map_or(Vec2::ONE, |size| Vec2::new(size.width, size.height))
+            * modifier.map_or(1.0, |modifier| modifier.0);
+        let (min, max) = tile_rect(*pos, footprint, size, window.size(), &arena);
+        let world = if snap {
+            (snap_to_pixels(min) + snap_to_pixels(max)) / 2.
+        } else {
+            (min + max) / 2.
+        };
+        transform.translation = world.extend(0.0);
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::Vec3;
+
+    #[test]
+    fn test_in_bounds() {
+        let arena = ArenaConfig {
+            width: 10,
+            height: 8,
+            ..Default::default()
+        };
+        let cases = [
+            (Position { x: 0, y: 0 }, true),
+            (Position { x: 9, y: 0 }, true),
+            (Position { x: 0, y: 7 }, true),
+            (Position { x: 9, y: 7 }, true),
+            (Position { x: 5, y: 0 }, true),
+            (Position { x: 9, y: 4 }, true),
+            (Position { x: -1, y: 0 }, false),
+            (Position { x: 0, y: -1 }, false),
+            (Position { x: 10, y: 7 }, false),
+            (Position { x: 9, y: 8 }, false),
+            (Position { x: 10, y: 8 }, false),
+        ];
+        for (pos, expected) in cases {
+            assert_eq!(in_bounds(pos, &arena), expected, "in_bounds({:?})", pos);
+        }
+    }
+
+    #[test]
+    fn test_free_tiles_skips_occupied() {
+        let arena = ArenaConfig {
+            width: 2,
+            height: 2,
+            ..Default::default()
+        };
+        let occupied = [Position { x: 1, y: 0 }, Position { x: 0, y: 1 }];
+        assert_eq!(
+            free_tiles(&arena, &occupied),
+            vec![Position { x: 0, y: 0 }, Position { x: 1, y: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_neighbor() {
+        let pos = Position { x: 3, y: 3 };
+        assert_eq!(pos.neighbor(Direction::Up), Position { x: 3, y: 4 });
+        assert_eq!(pos.neighbor(Direction::Down), Position { x: 3, y: 2 });
+        assert_eq!(pos.neighbor(Direction::Left), Position { x: 2, y: 3 });
+        assert_eq!(pos.neighbor(Direction::Right), Position { x: 4, y: 3 });
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let arena = ArenaConfig {
+            width: 10,
+            height: 8,
+            ..Default::default()
+        };
+        let cases = [
+            (
+                Position { x: 0, y: 4 },
+                Direction::Left,
+                Position { x: 9, y: 4 },
+            ),
+            (
+                Position { x: 9, y: 4 },
+                Direction::Right,
+                Position { x: 0, y: 4 },
+            ),
+            (
+                Position { x: 4, y: 0 },
+                Direction::Down,
+                Position { x: 4, y: 7 },
+            ),
+            (
+                Position { x: 4, y: 7 },
+                Direction::Up,
+                Position { x: 4, y: 0 },
+            ),
+            (
+                Position { x: 4, y: 4 },
+                Direction::Up,
+                Position { x: 4, y: 5 },
+            ),
+        ];
+        for (pos, dir, expected) in cases {
+            assert_eq!(
+                pos.wrapped(dir, &arena),
+                expected,
+                "{:?} from {:?}",
+                dir,
+                pos
+            );
+        }
+    }
+
+    #[test]
+    fn test_position_arithmetic() {
+        let a = Position { x: 3, y: -2 };
+        let b = Position { x: 1, y: 5 };
+        assert_eq!(a + b, Position { x: 4, y: 3 });
+        assert_eq!(a - b, Position { x: 2, y: -7 });
+    }
+
+    #[test]
+    fn test_grid_to_world_applies_origin() {
+        let window = Vec2::new(500., 500.);
+        let centered = ArenaConfig::default();
+        assert_eq!(
+            grid_to_world(Position { x: 0, y: 0 }, window, &centered),
+            Vec2::new(-225., -225.)
+        );
+        assert_eq!(
+            grid_to_world(Position { x: 9, y: 9 }, window, &centered),
+            Vec2::new(225., 225.)
+        );
+
+        let offset = ArenaConfig {
+            origin: Vec2::new(300., -50.),
+            ..Default::default()
+        };
+        assert_eq!(
+            grid_to_world(Position { x: 0, y: 0 }, window, &offset),
+            Vec2::new(75., -275.)
+        );
+        assert_eq!(
+            grid_to_world(Position { x: 4, y: 7 }, window, &offset),
+            Vec2::new(275., 75.)
+        );
+    }
+
+    #[test]
+    fn test_margin_insets_board() {
+        let window = Vec2::new(500., 400.);
+        let arena = ArenaConfig {
+            margin: 0.1,
+            ..Default::default()
+        };
+        assert_eq!(board_size(window, &arena), Vec2::new(400., 320.));
+        assert_eq!(tile_size(window, &arena), Vec2::new(40., 32.));
+        assert_eq!(
+            grid_to_world(Position { x: 0, y: 0 }, window, &arena),
+            Vec2::new(-180., -144.)
+        );
+        assert_eq!(
+            grid_to_world(Position { x: 9, y: 9 }, window, &arena),
+            Vec2::new(180., 144.)
+        );
+    }
+
+    #[test]
+    fn test_pixel_snap_joins_neighbouring_tiles() {
+        let mut app = bevy::app::App::new();
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(PixelSnap { enabled: true });
+        app.add_systems(bevy::app::Update, (position_translation, scale_size));
+        // 487 and 503 do not divide evenly into ten tiles
+        let mut window = Window::default();
+        window.resolution.set(487., 503.);
+        app.world_mut().spawn((window, PrimaryWindow));
+        let mut tiles = Vec::new();
+        for x in 0..10 {
+            for y in 0..2 {
+                let tile = Position { x, y };
+                let entity = app
+                    .world_mut()
+                    .spawn((tile, Size::square(1.0), Transform::default()))
+                    .id();
+                tiles.push((tile, entity));
+            }
+        }
+
+        app.update();
+
+        let corners = |entity| {
+            let transform = app.world().get::<Transform>(entity).unwrap();
+            let half = transform.scale.truncate() / 2.;
+            let center = transform.translation.truncate();
+            (center - half, center + half)
+        };
+        for (tile, entity) in tiles.iter().copied() {
+            let (min, max) = corners(entity);
+            assert_eq!(min.fract(), Vec2::ZERO, "{:?}", tile);
+            assert_eq!(max.fract(), Vec2::ZERO, "{:?}", tile);
+            for (other, neighbor) in tiles.iter().copied() {
+                if other == tile.neighbor(Direction::Right) {
+                    assert_eq!(corners(neighbor).0.x, max.x, "{:?} to {:?}", tile, other);
+                }
+                if other == tile.neighbor(Direction::Up) {
+                    assert_eq!(corners(neighbor).0.y, max.y, "{:?} to {:?}", tile, other);
+                }
+            }
+        }
+        // The tiles take up whole pixels, so their widths differ to make up the fraction
+        let widths: Vec<f32> = tiles
+            .iter()
+            .map(|(_, entity)| app.world().get::<Transform>(*entity).unwrap().scale.x)
+            .collect();
+        assert!(
+            widths.iter().any(|width| *width != widths[0]),
+            "{:?}",
+            widths
+        );
+    }
+
+    fn donut() -> ArenaConfig {
+        ArenaConfig::from_mask(ArenaMask::from_rows(&[
+            ".....", ".....", "..#..", ".....", ".....",
+        ]))
+    }
+
+    #[test]
+    fn test_in_bounds_follows_mask() {
+        let arena = donut();
+        assert_eq!((arena.width, arena.height), (5, 5));
+        for y in -1..=5 {
+            for x in -1..=5 {
+                let tile = Position { x, y };
+                let expected =
+                    (0..5).contains(&x) && (0..5).contains(&y) && tile != Position { x: 2, y: 2 };
+                assert_eq!(in_bounds(tile, &arena), expected, "{:?}", tile);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mask_rows_are_read_top_down() {
+        let arena = ArenaConfig::from_mask(ArenaMask::from_rows(&["#..", "..."]));
+        assert!(!in_bounds(Position { x: 0, y: 1 }, &arena));
+        assert!(in_bounds(Position { x: 0, y: 0 }, &arena));
+    }
+
+    #[test]
+    fn test_free_tiles_skip_masked_tiles() {
+        let arena = donut();
+        let free = free_tiles(&arena, &[]);
+        assert_eq!(free.len(), 24);
+        assert!(!free.contains(&Position { x: 2, y: 2 }));
+    }
+
+    #[test]
+    fn test_scale_size_applies_modifier() {
+        let mut app = bevy::app::App::new();
+        app.insert_resource(ArenaConfig::default());
+        app.add_systems(bevy::app::Update, scale_size);
+        app.world_mut().spawn((Window::default(), PrimaryWindow));
+        let plain = app
+            .world_mut()
+            .spawn((Size::square(0.8), Transform::default()))
+            .id();
+        let tapered = app
+            .world_mut()
+            .spawn((Size::square(0.8), SizeModifier(0.5), Transform::default()))
+            .id();
+
+        app.update();
+
+        let scale = |entity| app.world().get::<Transform>(entity).unwrap().scale;
+        // A default window is 1280x720 over a 10x10 arena
+        assert!(scale(plain).abs_diff_eq(Vec3::new(0.8 * 128., 0.8 * 72., 1.0), 1e-4));
+        assert!(scale(tapered).abs_diff_eq(Vec3::new(0.4 * 128., 0.4 * 72., 1.0), 1e-4));
+    }
+}
@@ -0,0 +1,82 @@
+//! Audio module
+//!
+//! This module loads the sound effects played during the game.
+//!
+//! Sounds are looked up from the `assets/sounds` directory at startup. A missing
+//! file only results in a logged warning from the asset server and the matching
+//! cue stays silent, so the game is playable without any audio assets.
+
+use bevy::prelude::*;
+
+use crate::food::FoodKind;
+
+/// Handles to the sounds played when each kind of food is eaten
+#[derive(Resource, Default, Clone, Debug)]
+pub struct AudioAssets {
+    pub normal: Option<Handle<AudioSource>>,
+    pub golden: Option<Handle<AudioSource>>,
+    pub bonus: Option<Handle<AudioSource>>,
+    pub poison: Option<Handle<AudioSource>>,
+    /// The click played when the menu selection moves
+    pub menu_navigate: Option<Handle<AudioSource>>,
+}
+
+impl AudioAssets {
+    /// The sound to play when food of the given kind is eaten
+    pub fn for_kind(&self, kind: FoodKind) -> Option<Handle<AudioSource>> {
+        match kind {
+            FoodKind::Normal | FoodKind::Freeze | FoodKind::Dash { .. } => self.normal.clone(),
+            FoodKind::Golden => self.golden.clone(),
+            FoodKind::Bonus => self.bonus.clone(),
+            FoodKind::Poison => self.poison.clone(),
+        }
+    }
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AudioAssets {
+        normal: Some(asset_server.load("sounds/eat.ogg")),
+        golden: Some(asset_server.load("sounds/eat_golden.ogg")),
+        bonus: Some(asset_server.load("sounds/eat_bonus.ogg")),
+        poison: Some(asset_server.load("sounds/eat_poison.ogg")),
+        menu_navigate: Some(asset_server.load("sounds/menu_click.ogg")),
+    });
+}
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_sounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_kind_maps_to_its_sound() {
+        let assets = AudioAssets {
+            normal: Some(Handle::weak_from_u128(1)),
+            golden: Some(Handle::weak_from_u128(2)),
+            bonus: Some(Handle::weak_from_u128(3)),
+            poison: None,
+            menu_navigate: None,
+        };
+
+        assert_eq!(
+            assets.for_kind(FoodKind::Normal),
+            Some(Handle::weak_from_u128(1))
+        );
+        assert_eq!(
+            assets.for_kind(FoodKind::Golden),
+            Some(Handle::weak_from_u128(2))
+        );
+        assert_eq!(
+            assets.for_kind(FoodKind::Bonus),
+            Some(Handle::weak_from_u128(3))
+        );
+        assert_eq!(assets.for_kind(FoodKind::Poison), None);
+    }
+}
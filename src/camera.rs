@@ -0,0 +1,68 @@
+//! Camera module
+//!
+//! This module spawns the 2D camera the game is viewed through.
+//!
+//! The camera is tagged with `GameCamera` so other systems can find it. Apps that
+//! manage their own camera can turn off the built-in one with `CameraSettings` and
+//! tag theirs with `GameCamera` instead.
+
+use bevy::prelude::*;
+
+/// Marks the camera the game is drawn with
+#[derive(Component)]
+pub struct GameCamera;
+
+/// Whether the game spawns its own camera. On by default.
+#[derive(Resource)]
+pub struct CameraSettings {
+    pub spawn: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self { spawn: true }
+    }
+}
+
+fn setup_camera(mut commands: Commands, settings: Res<CameraSettings>) {
+    if settings.spawn {
+        commands.spawn((Camera2d, GameCamera));
+    }
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraSettings>();
+        app.add_systems(Startup, setup_camera);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera_count(settings: CameraSettings) -> usize {
+        let mut app = App::new();
+        app.insert_resource(settings);
+        app.add_plugins(CameraPlugin);
+        app.update();
+
+        let world = app.world_mut();
+        world
+            .query_filtered::<&Camera2d, With<GameCamera>>()
+            .iter(world)
+            .count()
+    }
+
+    #[test]
+    fn test_camera_is_tagged() {
+        assert_eq!(camera_count(CameraSettings::default()), 1);
+    }
+
+    #[test]
+    fn test_camera_can_be_disabled() {
+        assert_eq!(camera_count(CameraSettings { spawn: false }), 0);
+    }
+}
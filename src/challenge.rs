@@ -0,0 +1,144 @@
+//! Challenge module
+//!
+//! This module sets up the daily challenge, where every player gets the same food
+//! sequence on the same day so their scores can be compared.
+//!
+//! The food seed is derived from the current date in UTC by `daily_seed`, which is a
+//! pure function of the date and never changes between builds or machines. Every
+//! attempt during the day replays the same board. Pass `--daily` on the command line
+//! to play the challenge.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::food::{BoardRestart, FoodRng};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Whether the run is a free game or a shared challenge
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ChallengeMode {
+    /// Every board gets a fresh random seed
+    #[default]
+    Free,
+    /// The board is seeded from today's date
+    Daily,
+}
+
+/// A calendar date
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// The current date in UTC
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        Self::from_days_since_epoch((secs / SECS_PER_DAY) as i64)
+    }
+
+    /// The date `days` days after 1970-01-01, in the proleptic Gregorian calendar
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        // Counts from 0000-03-01 so that the leap day falls at the end of each year
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+}
+
+/// The food seed shared by every player on `date`.
+///
+/// The date is written as the number `YYYYMMDD` and scrambled with SplitMix64, so
+/// neighbouring days get unrelated boards.
+pub fn daily_seed(date: Date) -> u64 {
+    let stamp = date.year as i64 * 10_000 + date.month as i64 * 100 + date.day as i64;
+    let mut z = (stamp as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Seeds the food for today's challenge whenever the daily mode is chosen
+fn start_challenge(
+    mode: Res<ChallengeMode>,
+    mut rng: ResMut<FoodRng>,
+    mut restart: ResMut<BoardRestart>,
+) {
+    if mode.is_changed() && *mode == ChallengeMode::Daily {
+        *rng = FoodRng::from_seed(daily_seed(Date::today()));
+        *restart = BoardRestart::SameBoard;
+    }
+}
+
+pub struct ChallengePlugin;
+
+impl Plugin for ChallengePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChallengeMode>();
+        app.init_resource::<FoodRng>();
+        app.init_resource::<BoardRestart>();
+        app.add_systems(PreUpdate, start_challenge);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_since_epoch_to_date() {
+        let date = |year, month, day| Date { year, month, day };
+        assert_eq!(Date::from_days_since_epoch(0), date(1970, 1, 1));
+        assert_eq!(Date::from_days_since_epoch(-1), date(1969, 12, 31));
+        assert_eq!(Date::from_days_since_epoch(19_723), date(2024, 1, 1));
+        assert_eq!(Date::from_days_since_epoch(19_782), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn test_daily_seed_is_stable_per_date() {
+        let today = Date {
+            year: 2026,
+            month: 10,
+            day: 17,
+        };
+        let tomorrow = Date { day: 18, ..today };
+        // Pinned so that a change to the derivation, which would split players on
+        // the same day across different boards, is caught
+        assert_eq!(daily_seed(today), 8_099_358_280_037_599_703);
+        assert_eq!(daily_seed(today), daily_seed(today));
+        assert_ne!(daily_seed(today), daily_seed(tomorrow));
+    }
+
+    #[test]
+    fn test_daily_mode_seeds_food_from_today() {
+        let mut app = App::new();
+        app.insert_resource(ChallengeMode::Daily);
+        app.add_plugins(ChallengePlugin);
+        app.update();
+
+        let world = app.world();
+        assert_eq!(
+            world.resource::<FoodRng>().seed(),
+            daily_seed(Date::today())
+        );
+        assert_eq!(*world.resource::<BoardRestart>(), BoardRestart::SameBoard);
+    }
+}
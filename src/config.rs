@@ -0,0 +1,121 @@
+//! Config module
+//!
+//! This module gathers the settings of a game into a single `GameConfig`.
+//!
+//! Each setting is still its own resource, read and changed individually while the
+//! game runs. `GameConfig` is only a convenient way to set all of them up front:
+//! `GameConfig::insert_into` puts them in the app before any plugin is added, so
+//! every plugin finds them no matter the order the plugins are added in.
+
+use bevy::prelude::*;
+
+use crate::{
+    arena::ArenaConfig,
+    food::FoodSettings,
+    snake::{EdgeWrap, GameMode, KeyBindings, Lives, SnakeStart, SpeedRamp, TurnStaleness},
+    theme::ThemeSetting,
+};
+
+/// Every setting needed to set up a game, each inserted as its own resource
+#[derive(Clone, Debug, Default)]
+pub struct GameConfig {
+    pub arena: ArenaConfig,
+    pub start: SnakeStart,
+    pub wrap: EdgeWrap,
+    pub speed: SpeedRamp,
+    pub food: FoodSettings,
+    pub theme: ThemeSetting,
+    pub key_bindings: KeyBindings,
+    pub turn_staleness: TurnStaleness,
+    pub lives: Lives,
+    pub mode: GameMode,
+}
+
+impl GameConfig {
+    /// Inserts every setting as a resource, replacing any already in `world`
+    pub fn insert_into(self, world: &mut World) {
+        world.insert_resource(self.arena);
+        world.insert_resource(self.start);
+        world.insert_resource(self.wrap);
+        world.insert_resource(self.speed);
+        world.insert_resource(self.food);
+        world.insert_resource(self.theme);
+        world.insert_resource(self.key_bindings);
+        world.insert_resource(self.turn_staleness);
+        world.insert_resource(self.lives);
+        world.insert_resource(self.mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::{Direction, Position},
+        food::{FoodPlugin, SpawnMode},
+        snake::{MovementTimer, SnakePlugin, SpeedCurve},
+        theme::{Theme, ThemePlugin},
+    };
+
+    #[test]
+    fn test_config_inserts_every_setting() {
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.bind(Direction::Up, &[KeyCode::KeyI]).unwrap();
+        let config = GameConfig {
+            arena: ArenaConfig {
+                width: 24,
+                height: 16,
+                ..Default::default()
+            },
+            start: SnakeStart {
+                head: Position { x: 5, y: 8 },
+                direction: Direction::Right,
+                length: 4,
+            },
+            wrap: EdgeWrap {
+                wrap_x: true,
+                wrap_y: false,
+            },
+            speed: SpeedRamp {
+                base: 0.3,
+                min: 0.1,
+                curve: SpeedCurve::Constant,
+                over_time: None,
+            },
+            food: FoodSettings {
+                mode: SpawnMode::EveryNTicks(6),
+                per_spawn: 2,
+                ..Default::default()
+            },
+            theme: ThemeSetting::Fixed(Theme::Light),
+            key_bindings,
+            turn_staleness: TurnStaleness { ticks: Some(2) },
+            lives: Lives::new(3),
+            mode: GameMode::ReachLength(30),
+        };
+
+        let mut app = App::new();
+        config.clone().insert_into(app.world_mut());
+        app.add_plugins((FoodPlugin::default(), SnakePlugin, ThemePlugin));
+
+        let world = app.world();
+        assert_eq!(*world.resource::<ArenaConfig>(), config.arena);
+        assert_eq!(*world.resource::<SnakeStart>(), config.start);
+        assert_eq!(*world.resource::<EdgeWrap>(), config.wrap);
+        assert_eq!(world.resource::<SpeedRamp>().base, 0.3);
+        assert_eq!(
+            world.resource::<MovementTimer>().interval().as_secs_f32(),
+            0.3
+        );
+        let food = world.resource::<FoodSettings>();
+        assert_eq!(food.mode, SpawnMode::EveryNTicks(6));
+        assert_eq!(food.per_spawn, 2);
+        assert_eq!(*world.resource::<ThemeSetting>(), config.theme);
+        assert_eq!(
+            world.resource::<KeyBindings>().direction(KeyCode::KeyI),
+            Some(Direction::Up)
+        );
+        assert_eq!(world.resource::<Lives>().remaining(), 3);
+        assert_eq!(*world.resource::<GameMode>(), config.mode);
+    }
+}
@@ -0,0 +1,165 @@
+//! Countdown module
+//!
+//! This module holds the snake still for a short countdown at the start of every
+//! run, so the player is not dropped straight into a moving snake.
+//!
+//! The snake is spawned and drawn as usual, but `movement` does not run and the
+//! movement timer does not tick until the countdown is over. A large number in the
+//! middle of the screen counts down the seconds left. The countdown restarts after
+//! every game over and reset, and runs on virtual time so it waits while paused.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::snake::{GameOverEvent, ResetGameEvent, SnakeSet};
+
+const COUNTDOWN_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+/// How many seconds to count down before each run. Zero starts runs straight away.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Countdown {
+    pub secs: u32,
+}
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self { secs: 3 }
+    }
+}
+
+/// The countdown for the run in progress
+#[derive(Resource, Debug)]
+pub(crate) struct CountdownClock(Timer);
+
+impl CountdownClock {
+    fn new(countdown: Countdown) -> Self {
+        Self(Timer::new(
+            Duration::from_secs(countdown.secs.into()),
+            TimerMode::Once,
+        ))
+    }
+
+    /// The whole seconds left to count, rounded up so the last second shows as 1
+    fn secs_left(&self) -> u32 {
+        self.0.remaining().as_secs_f32().ceil() as u32
+    }
+}
+
+/// Run condition that holds back the snake until the countdown is over. Always true
+/// when no countdown is in use.
+pub(crate) fn countdown_finished(clock: Option<Res<CountdownClock>>) -> bool {
+    clock.is_none_or(|clock| clock.0.finished())
+}
+
+fn tick_countdown(time: Res<Time<Virtual>>, mut clock: ResMut<CountdownClock>) {
+    clock.0.tick(time.delta());
+}
+
+/// Starts the countdown over for the next run
+fn restart_countdown(
+    countdown: Res<Countdown>,
+    mut clock: ResMut<CountdownClock>,
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if died || reset {
+        *clock = CountdownClock::new(*countdown);
+    }
+}
+
+/// Marks the number shown during the countdown
+#[derive(Component)]
+struct CountdownText;
+
+fn spawn_countdown_text(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 96.,
+            ..Default::default()
+        },
+        TextColor(COUNTDOWN_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.),
+            width: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            ..Default::default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        CountdownText,
+    ));
+}
+
+fn show_countdown(
+    clock: Res<CountdownClock>,
+    mut texts: Query<(&mut Text, &mut Visibility), With<CountdownText>>,
+) {
+    for (mut text, mut visibility) in texts.iter_mut() {
+        if clock.0.finished() {
+            *visibility = Visibility::Hidden;
+        } else {
+            *visibility = Visibility::Inherited;
+            text.0 = clock.secs_left().to_string();
+        }
+    }
+}
+
+pub struct CountdownPlugin;
+
+impl Plugin for CountdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Countdown>();
+        let countdown = *app.world().resource::<Countdown>();
+        app.insert_resource(CountdownClock::new(countdown));
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_systems(Startup, spawn_countdown_text);
+        app.add_systems(Update, tick_countdown.before(SnakeSet::Movement));
+        app.add_systems(
+            Update,
+            (restart_countdown, show_countdown)
+                .chain()
+                .after(SnakeSet::Collision),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestGame;
+
+    #[test]
+    fn test_snake_waits_for_countdown() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(Countdown { secs: 1 });
+        game.app.add_plugins(CountdownPlugin);
+        let start = game.head();
+
+        // Each tick is well under a second, so the first few fall inside the countdown
+        game.advance(3);
+        assert_eq!(game.head(), start);
+        assert_eq!(game.app.world().resource::<CountdownClock>().secs_left(), 1);
+
+        // A few more ticks finish the countdown without running into the far wall
+        game.advance(5);
+        assert_ne!(game.head(), start);
+    }
+
+    #[test]
+    fn test_secs_left_rounds_up() {
+        let mut clock = CountdownClock::new(Countdown { secs: 3 });
+        assert_eq!(clock.secs_left(), 3);
+        clock.0.tick(Duration::from_millis(1_500));
+        assert_eq!(clock.secs_left(), 2);
+        clock.0.tick(Duration::from_millis(1_499));
+        assert_eq!(clock.secs_left(), 1);
+        clock.0.tick(Duration::from_millis(1));
+        assert_eq!(clock.secs_left(), 0);
+        assert!(clock.0.finished());
+    }
+}
@@ -0,0 +1,482 @@
+//! Debug module
+//!
+//! This module draws developer overlays over the arena.
+//!
+//! Collision is decided per tile, while the snake and food sprites are drawn smaller
+//! than a tile. The collision overlay outlines the full tile behind each entity that
+//! takes part in collisions, making the grid the game actually plays on visible. In
+//! dev builds it is toggled with F3.
+//!
+//! Dev builds also check the integrity of the game after every movement tick: the
+//! snake's entities must match `SnakeSegments`, every part of the snake must sit on
+//! its own tile inside the arena, and the food on the board must stay within
+//! `FoodSettings::max_food`. A violation panics, so a bug that slowly corrupts state
+//! over a long session is caught on the tick it happens. While the snake can pass
+//! through itself, in `GameMode::Peaceful` or the grace period after a respawn, its
+//! parts are allowed to share tiles and leave the arena until it is untangled again.
+//!
+//! For screenshots, dev builds also have a showcase key, F4 by default. Each press
+//! switches to the next theme preset and lays out one piece of every kind of food on
+//! the first free tiles of the board, replacing the pieces placed by the last press.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    arena::{self, ArenaConfig, Position},
+    food::{self, Food, FoodKind, FoodSettings, FootprintSize},
+    skin::SpriteAssets,
+    snake::{GameMode, Lives, SnakeHead, SnakeSegment, SnakeSegments, SnakeSet, TickEvent},
+    theme::{self, PreferredColorScheme, ThemeSetting},
+};
+
+const OUTLINE_COLOR: Color = Color::srgb(0.0, 1.0, 0.4);
+
+/// Whether the collision tile outlines are drawn. Off by default.
+#[derive(Resource, Default)]
+pub struct CollisionOverlay {
+    pub enabled: bool,
+}
+
+/// Every tile covered by the given bodies, one entry per tile
+fn collision_tiles<'a>(
+    bodies: impl Iterator<Item = (&'a Position, Option<&'a FootprintSize>)>,
+) -> Vec<Position> {
+    bodies
+        .flat_map(|(pos, footprint)| footprint.copied().unwrap_or_default().tiles(*pos))
+        .collect()
+}
+
+#[cfg(debug_assertions)]
+fn toggle_overlay(input: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<CollisionOverlay>) {
+    if input.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+/// The key that cycles the theme and lays out sample food for screenshots
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ShowcaseKey(pub KeyCode);
+
+impl Default for ShowcaseKey {
+    fn default() -> Self {
+        Self(KeyCode::F4)
+    }
+}
+
+/// Marks food placed by the showcase key
+#[derive(Component)]
+struct SampleFood;
+
+/// One piece of every kind of food, on the first free tiles of the arena in row-major
+/// order. Fewer kinds are placed when the board is too full for all of them.
+fn sample_food(arena: &ArenaConfig, occupied: &[Position]) -> Vec<(Position, FoodKind)> {
+    arena::free_tiles(arena, occupied)
+        .into_iter()
+        .zip(FoodKind::ALL)
+        .collect()
+}
+
+#[cfg(debug_assertions)]
+#[allow(clippy::too_many_arguments)]
+fn showcase(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    key: Res<ShowcaseKey>,
+    arena: Res<ArenaConfig>,
+    assets: Option<Res<SpriteAssets>>,
+    mut setting: ResMut<ThemeSetting>,
+    preferred: Res<PreferredColorScheme>,
+    samples: Query<Entity, With<SampleFood>>,
+    occupied: Query<(&Position, Option<&FootprintSize>), Without<SampleFood>>,
+) {
+    if !input.just_pressed(key.0) {
+        return;
+    }
+    *setting = ThemeSetting::Fixed(theme::active_theme(*setting, &preferred).next());
+    for sample in samples.iter() {
+        commands.entity(sample).despawn();
+    }
+    let occupied = collision_tiles(occupied.iter());
+    for (position, kind) in sample_food(&arena, &occupied) {
+        let entity = food::spawn_food_at(&mut commands, position, kind, assets.as_deref());
+        commands.entity(entity).insert(SampleFood);
+    }
+}
+
+fn overlay_enabled(overlay: Res<CollisionOverlay>) -> bool {
+    overlay.enabled
+}
+
+#[allow(clippy::type_complexity)]
+fn draw_collision_tiles(
+    arena: Res<ArenaConfig>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    bodies: Query<
+        (&Position, Option<&FootprintSize>),
+        Or<(With<SnakeHead>, With<SnakeSegment>, With<Food>)>,
+    >,
+    mut gizmos: Gizmos,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let tile = arena::tile_size(window.size(), &arena);
+    for pos in collision_tiles(bodies.iter()) {
+        let center = arena::grid_to_world(pos, window.size(), &arena);
+        gizmos.rect_2d(Isometry2d::from_translation(center), tile, OUTLINE_COLOR);
+    }
+}
+
+/// Describes every broken invariant of the snake and food, or nothing when the game
+/// is consistent.
+///
+/// `snake` holds every entity marked as part of a snake along with its position. With
+/// `tangled` set the parts of the snake may share tiles and sit outside the arena.
+fn integrity_violations(
+    segments: &SnakeSegments,
+    snake: &[(Entity, Option<Position>)],
+    arena: &ArenaConfig,
+    food_count: usize,
+    max_food: Option<usize>,
+    tangled: bool,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    if snake.len() != segments.len() {
+        violations.push(format!(
+            "{} snake entities but SnakeSegments has {}",
+            snake.len(),
+            segments.len()
+        ));
+    }
+    let mut seen: Vec<Position> = Vec::new();
+    for entity in segments.iter() {
+        let Some((_, position)) = snake.iter().find(|(other, _)| other == entity) else {
+            violations.push(format!(
+                "SnakeSegments lists {:?}, which is not a segment",
+                entity
+            ));
+            continue;
+        };
+        let Some(position) = *position else {
+            violations.push(format!("segment {:?} has no position", entity));
+            continue;
+        };
+        if tangled {
+            continue;
+        }
+        if !arena::in_bounds(position, arena) {
+            violations.push(format!(
+                "segment {:?} is out of bounds at {:?}",
+                entity, position
+            ));
+        }
+        if seen.contains(&position) {
+            violations.push(format!(
+                "segment {:?} shares {:?} with another",
+                entity, position
+            ));
+        }
+        seen.push(position);
+    }
+    if let Some(max) = max_food.filter(|max| food_count > *max) {
+        violations.push(format!(
+            "{} food on the board, over the cap of {}",
+            food_count, max
+        ));
+    }
+    violations
+}
+
+/// Panics if the game has fallen into an inconsistent state, checked after each
+/// movement tick.
+///
+/// `tangled` is set while the snake can pass through itself and stays set after that
+/// ends until no part of the snake shares a tile or sits outside the arena, since the
+/// body keeps crossing itself until the tail has passed.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn check_integrity(
+    mut ticks: EventReader<TickEvent>,
+    mut tangled: Local<bool>,
+    segments: Res<SnakeSegments>,
+    arena: Res<ArenaConfig>,
+    settings: Option<Res<FoodSettings>>,
+    mode: Option<Res<GameMode>>,
+    lives: Option<Res<Lives>>,
+    snake: Query<(Entity, Option<&Position>), Or<(With<SnakeHead>, With<SnakeSegment>)>>,
+    food: Query<(), With<Food>>,
+) {
+    if ticks.read().count() == 0 {
+        return;
+    }
+    let snake: Vec<(Entity, Option<Position>)> = snake
+        .iter()
+        .map(|(entity, position)| (entity, position.copied()))
+        .collect();
+    let food_count = food.iter().count();
+    let max_food = settings.and_then(|settings| settings.max_food);
+    let violations =
+        |tangled| integrity_violations(&segments, &snake, &arena, food_count, max_food, tangled);
+    let passing = mode.is_some_and(|mode| *mode == GameMode::Peaceful)
+        || lives.is_some_and(|lives| lives.in_grace());
+    if passing {
+        *tangled = true;
+    } else if *tangled && violations(false).is_empty() {
+        *tangled = false;
+    }
+    let violations = violations(*tangled);
+    assert!(
+        violations.is_empty(),
+        "game integrity broken: {}",
+        violations.join("; ")
+    );
+}
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CollisionOverlay>();
+        app.init_resource::<ShowcaseKey>();
+        app.init_resource::<ThemeSetting>();
+        app.init_resource::<PreferredColorScheme>();
+        // Gizmos are only touched while the overlay is on, so the plugin also runs
+        // without a renderer
+        app.add_systems(Update, draw_collision_tiles.run_if(overlay_enabled));
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, showcase);
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, toggle_overlay.before(draw_collision_tiles));
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, check_integrity.after(SnakeSet::Growth));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ai,
+        arena::{ArenaMask, Direction},
+        food::{FoodPlugin, FoodRng, SpawnMode},
+        snake::{FreeReverse, GameOverEvent, LifeLostEvent, SnakeStart},
+        testing::TestGame,
+        theme::Theme,
+    };
+
+    #[test]
+    fn test_outlines_cover_occupied_tiles() {
+        let head = Position { x: 3, y: 3 };
+        let segment = Position { x: 3, y: 2 };
+        let food = Position { x: 6, y: 6 };
+        let large = FootprintSize::square(2);
+        let bodies = [(&head, None), (&segment, None), (&food, Some(&large))];
+
+        let tiles = collision_tiles(bodies.into_iter());
+
+        assert_eq!(
+            tiles,
+            [
+                head,
+                segment,
+                Position { x: 6, y: 6 },
+                Position { x: 7, y: 6 },
+                Position { x: 6, y: 7 },
+                Position { x: 7, y: 7 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_showcase_cycles_themes_and_samples_every_food() {
+        let mut theme = Theme::Dark;
+        let cycle: Vec<Theme> = (0..3)
+            .map(|_| {
+                theme = theme.next();
+                theme
+            })
+            .collect();
+        assert_eq!(cycle, [Theme::Light, Theme::Dark, Theme::Light]);
+
+        let arena = ArenaConfig {
+            width: 3,
+            height: 3,
+            ..Default::default()
+        };
+        let snake = [Position { x: 1, y: 0 }, Position { x: 1, y: 1 }];
+        assert_eq!(
+            sample_food(&arena, &snake),
+            [
+                (Position { x: 0, y: 0 }, FoodKind::Normal),
+                (Position { x: 2, y: 0 }, FoodKind::Golden),
+                (Position { x: 0, y: 1 }, FoodKind::Bonus),
+                (Position { x: 2, y: 1 }, FoodKind::Poison),
+                (Position { x: 0, y: 2 }, FoodKind::Freeze),
+                (Position { x: 1, y: 2 }, FoodKind::Dash { distance: 3 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_violations_are_reported() {
+        let arena = ArenaConfig::default();
+        let mut segments = SnakeSegments::default();
+        let head = Entity::from_raw(1);
+        let tail = Entity::from_raw(2);
+        segments.push(head);
+        segments.push(tail);
+        let snake = [
+            (head, Some(Position { x: 3, y: 3 })),
+            (tail, Some(Position { x: 3, y: 3 })),
+            (Entity::from_raw(3), Some(Position { x: 10, y: 3 })),
+        ];
+
+        let violations = integrity_violations(&segments, &snake, &arena, 6, Some(5), false);
+
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        // A missing tail is reported both as a count mismatch and as a missing segment
+        let missing = integrity_violations(&segments, &snake[..1], &arena, 5, Some(5), false);
+        assert_eq!(missing.len(), 2, "{:?}", missing);
+        // A tangled snake may share tiles, but must still match `SnakeSegments`
+        let tangled = integrity_violations(&segments, &snake, &arena, 5, Some(5), true);
+        assert_eq!(tangled.len(), 1, "{:?}", tangled);
+    }
+
+    /// A game checked for integrity after every tick, as in dev builds
+    fn checked_game(start: SnakeStart) -> TestGame {
+        let mut game = TestGame::with_start(start);
+        game.app.add_plugins(DebugPlugin);
+        game
+    }
+
+    #[test]
+    fn test_peaceful_snake_may_pass_through_itself_and_walls() {
+        let mut game = checked_game(SnakeStart {
+            head: Position { x: 5, y: 3 },
+            direction: Direction::Right,
+            length: 5,
+        });
+        let arena = ArenaConfig::from_mask(ArenaMask::from_rows(&[
+            "..........",
+            "..........",
+            "..........",
+            "..........",
+            "..........",
+            "..........",
+            "..........",
+            "..........",
+            "..#.......",
+            "..........",
+        ]));
+        game.app.insert_resource(arena.clone());
+        game.app.insert_resource(GameMode::Peaceful);
+        game.app.insert_resource(FreeReverse { enabled: true });
+
+        // Straight back onto the neck and through the rest of the body
+        game.turn(Direction::Left).advance(3);
+        // Then into the wall at (2, 1), through it and across the bottom edge
+        game.turn(Direction::Down).advance(2);
+        assert!(!arena::in_bounds(game.head(), &arena));
+        game.advance(5);
+        assert_eq!(game.head(), Position { x: 2, y: 6 });
+    }
+
+    #[test]
+    fn test_respawned_snake_may_stay_tangled_after_grace() {
+        let mut game = checked_game(SnakeStart {
+            head: Position { x: 8, y: 3 },
+            direction: Direction::Right,
+            length: 9,
+        });
+        game.app.insert_resource(Lives::new(2));
+        game.app.world_mut().resource_mut::<Lives>().lose();
+
+        // Up and back down across the body at (6, 3), which stays covered twice until
+        // the old part of the body has moved off it, after the grace period is over
+        for direction in [
+            Direction::Up,
+            Direction::Left,
+            Direction::Left,
+            Direction::Down,
+            Direction::Down,
+        ] {
+            game.turn(direction).advance(1);
+        }
+        assert!(!game.app.world().resource::<Lives>().in_grace());
+        let crossing = Position { x: 6, y: 3 };
+        assert_eq!(
+            game.segments()
+                .iter()
+                .filter(|tile| **tile == crossing)
+                .count(),
+            2
+        );
+        game.turn(Direction::Left).advance(3);
+        assert!(game
+            .app
+            .world()
+            .resource::<Events<LifeLostEvent>>()
+            .is_empty());
+        assert!(game
+            .app
+            .world()
+            .resource::<Events<GameOverEvent>>()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_long_run_keeps_integrity() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(FoodRng::from_seed(11));
+        game.app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(4))
+                .with_food_per_spawn(2)
+                .with_max_food(6),
+        );
+        game.app
+            .add_systems(Update, check_integrity.after(SnakeSet::Growth));
+
+        let arena = ArenaConfig::default();
+        for tick in 0..5_000 {
+            // Follow the pathfinder toward food, wandering when no food is reachable
+            let body = game.segments();
+            let head = body[0];
+            let world = game.app.world_mut();
+            let food: Vec<Position> = world
+                .query_filtered::<&Position, With<Food>>()
+                .iter(world)
+                .copied()
+                .collect();
+            let direction = world.query::<&SnakeHead>().single(world).direction;
+            let next = ai::next_step(head, direction, &arena, &body, &food);
+            let turn = next
+                .and_then(|next| {
+                    [
+                        Direction::Up,
+                        Direction::Right,
+                        Direction::Down,
+                        Direction::Left,
+                    ]
+                    .into_iter()
+                    .find(|dir| head.neighbor(*dir) == next)
+                })
+                .unwrap_or(if tick % 7 == 0 {
+                    direction.clockwise()
+                } else {
+                    direction
+                });
+            game.turn(turn).advance(1);
+
+            let world = game.app.world_mut();
+            let snake: Vec<(Entity, Option<Position>)> = world
+                .query_filtered::<(Entity, Option<&Position>), Or<(With<SnakeHead>, With<SnakeSegment>)>>()
+                .iter(world)
+                .map(|(entity, position)| (entity, position.copied()))
+                .collect();
+            let food = world.query::<&Food>().iter(world).count();
+            let segments = world.resource::<SnakeSegments>();
+            let violations = integrity_violations(segments, &snake, &arena, food, Some(6), false);
+            assert!(violations.is_empty(), "tick {}: {:?}", tick, violations);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Effect module
+//!
+//! This module drives short-lived visual effects such as flashes and banners.
+//!
+//! Every effect carries a `TimedEffect` component, and all of them are ticked by a
+//! single `EffectClock` that follows virtual time. Effects therefore freeze together
+//! while the game is paused and advance in lockstep with the rest of the game logic.
+//! The systems owning each effect only decide what to do once it has finished.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+/// The time that passed for effects this frame, zero while the game is paused
+#[derive(Resource, Default, Debug)]
+pub struct EffectClock {
+    delta: Duration,
+}
+
+impl EffectClock {
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+}
+
+/// A transient effect that lasts for a fixed span of effect time
+#[derive(Component, Debug)]
+pub struct TimedEffect(Timer);
+
+impl TimedEffect {
+    pub fn from_seconds(secs: f32) -> Self {
+        Self(Timer::from_seconds(secs, TimerMode::Once))
+    }
+
+    /// How far through the effect is, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        self.0.fraction()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.0.finished()
+    }
+}
+
+/// Ordering for the effect systems. Effects are ticked before `Update` so the systems
+/// reacting to finished effects see this frame's progress.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EffectTick;
+
+fn tick_effect_clock(time: Res<Time<Virtual>>, mut clock: ResMut<EffectClock>) {
+    clock.delta = if time.is_paused() {
+        Duration::ZERO
+    } else {
+        time.delta()
+    };
+}
+
+fn tick_effects(clock: Res<EffectClock>, mut effects: Query<&mut TimedEffect>) {
+    for mut effect in effects.iter_mut() {
+        effect.0.tick(clock.delta);
+    }
+}
+
+/// Ticks every `TimedEffect`. Plugins that spawn effects add it if it is not already
+/// present.
+pub struct EffectPlugin;
+
+impl Plugin for EffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EffectClock>();
+        app.add_systems(
+            PreUpdate,
+            (tick_effect_clock, tick_effects).chain().in_set(EffectTick),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.add_plugins(EffectPlugin);
+        let effect = app.world_mut().spawn(TimedEffect::from_seconds(0.5)).id();
+        (app, effect)
+    }
+
+    fn advance(app: &mut App, millis: u64) {
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(millis));
+        app.update();
+    }
+
+    #[test]
+    fn test_effect_finishes_after_its_duration() {
+        let (mut app, effect) = effect_app();
+
+        advance(&mut app, 300);
+        let timed = app.world().get::<TimedEffect>(effect).unwrap();
+        assert!(!timed.finished());
+        assert!((timed.fraction() - 0.6).abs() < 1e-6);
+
+        advance(&mut app, 200);
+        assert!(app.world().get::<TimedEffect>(effect).unwrap().finished());
+    }
+
+    #[test]
+    fn test_effects_freeze_while_paused() {
+        let (mut app, effect) = effect_app();
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+
+        advance(&mut app, 1000);
+
+        assert_eq!(
+            app.world().resource::<EffectClock>().delta(),
+            Duration::ZERO
+        );
+        assert_eq!(
+            app.world().get::<TimedEffect>(effect).unwrap().fraction(),
+            0.0
+        );
+    }
+}
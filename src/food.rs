@@ -1,40 +1,145 @@
 //! Food module for the snake game
-//! 
+//!
 //! This module handles the food mechanics in the snake game, including:
 //! - Spawning food at random positions in the arena
 //! - Handling food collection when the snake collides with it
 //! - Managing food appearance and visual representation
-//! 
+//!
 //! Food appears as collectible items that the snake can eat to grow longer.
 //! When collected, new food spawns at a random unoccupied position.
-//! 
+//!
 //! # Components
 //! - `Food` - Marks an entity as food that can be collected by the snake
-//! 
+//! - `FootprintSize` - The block of tiles a piece of food occupies
+//! - `FoodKind` - The variety of a piece of food
+//!
+//! # Placing food
+//! Scripted scenarios, tutorials and tests can place food on a chosen tile with
+//! `spawn_food_at` or by sending a `SpawnFoodEvent`, instead of waiting on the random
+//! spawner.
+//!
+//! # Replacing eaten food
+//! With `FoodSettings::respawn_delay` set, every piece eaten is replaced by a new one
+//! once the delay has passed, timed separately for each piece and on top of the
+//! spawning driven by `SpawnMode`.
+//!
+//! # Spawn timer
+//! `SpawnTimerBar`, or the `--spawn-timer` command line flag, shows a small bar in the
+//! HUD that fills up as the next timed spawn approaches and empties when food spawns.
+//! It is hidden when spawning is driven by ticks, and while the board holds as much
+//! food as `FoodSettings::max_food` allows, as the timer tells the player nothing then.
+//!
+//! # Power-ups
+//! Eating `FoodKind::Freeze` food makes `Powerup::Freeze` the `ActivePowerup` for
+//! `FoodFreeze::secs` seconds of play. While it lasts no new food is spawned and food
+//! stops drifting, giving the player a calm moment. A power-up ends early when the run
+//! does.
+//!
+//! # Dash food
+//! Eating `FoodKind::Dash` food carries the head `distance` tiles further along its
+//! current direction at once, skipping over everything on the tiles in between. The
+//! dashed path is still checked for walls and the body, and the run ends on the first
+//! tile of it that is blocked.
+//!
+//! # Ordered food
+//! Giving food a `FoodOrder` turns it into a puzzle objective: numbered pieces can only
+//! be eaten in order, starting from 1, and the snake passes over a piece whose turn
+//! has not come yet. `OrderedFood` counts how far the run has got. The random spawner
+//! never numbers food, so a puzzle lays out its pieces with `SpawnFoodEvent::order`.
+//!
+//! # Clusters
+//! Food normally spawns one tile at a time. Listing shapes in `FoodSettings::clusters`,
+//! or passing `--clusters` on the command line, spawns whole clusters instead, such as
+//! an L-tromino or a 2x2 block. Each tile of a cluster is its own piece of food, so
+//! the snake eats a cluster one tile at a time.
+//!
 //! # Systems
-//! - `spawn_food` - Spawns initial food and respawns food when collected
-//! - `food_collection` - Detects snake collision with food and handles collection
+//! - `spawn` - Places the starting food of each run and more whenever spawning is due
+//! - `spawn_requested_food` - Places the food asked for with a `SpawnFoodEvent`
+//! - `restart_board` - Resets the food sequence and spawn timing when a run ends
+//! - `drift_food` - Pulls food toward the center of the arena in `GameMode::Gravity`
+//!
+//! Food is eaten by the `eater` system of the snake module, which sends a
+//! `FoodEatenEvent` for every piece.
 //!
 //! Food positions are constrained to the game arena grid to maintain consistent
 //! gameplay mechanics with the snake's movement.
 
 use core::f32;
+use std::{collections::HashSet, time::Duration};
 
 use bevy::prelude::*;
-use rand::random;
+use rand::{random, rngs::StdRng, Rng, SeedableRng};
 
-use crate::arena::{self, Position, Size};
+pub use crate::arena::FootprintSize;
+
+use crate::{
+    arena::{self, ArenaConfig, Position, Size},
+    skin::{self, SpriteAssets},
+    snake::{
+        FoodEatenEvent, GameMode, GameOverEvent, MoveCount, ResetGameEvent, SnakeSet, TickEvent,
+    },
+};
 
 const FOOD_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
+const SPAWN_TIMER_TRACK_COLOR: Color = Color::srgba(0.6, 0.6, 0.6, 0.3);
 
 #[derive(Resource)]
 struct FoodTimer {
-    clock: Timer
+    clock: Timer,
+    /// The `MoveCount` total when food was last spawned in `SpawnMode::EveryNTicks`
+    moves_at_last_spawn: u64,
+    /// Set when a run starts, until the starting food has been placed
+    fresh_board: bool,
 }
 
 impl FoodTimer {
     fn from_seconds(secs: f32) -> Self {
-        Self { clock: Timer::from_seconds(secs, TimerMode::Repeating) }
+        Self {
+            clock: Timer::from_seconds(secs, TimerMode::Repeating),
+            moves_at_last_spawn: 0,
+            fresh_board: true,
+        }
+    }
+
+    /// Starts the spawn timing over for a run beginning at the `moves` total
+    fn restart(&mut self, moves: u64) {
+        self.clock.reset();
+        self.moves_at_last_spawn = moves;
+        self.fresh_board = true;
+    }
+}
+
+/// The replacements for eaten food still waiting out `FoodSettings::respawn_delay`
+#[derive(Resource, Default, Debug)]
+struct RespawnDelays {
+    timers: Vec<Timer>,
+    /// Replacements whose delay has passed, waiting to be placed
+    due: usize,
+}
+
+/// Starts a replacement delay for every piece of food eaten and counts those that
+/// have run out
+fn time_replacements(
+    time: Res<Time<Virtual>>,
+    settings: Res<FoodSettings>,
+    mut delays: ResMut<RespawnDelays>,
+    mut eaten: EventReader<FoodEatenEvent>,
+) {
+    for timer in delays.timers.iter_mut() {
+        timer.tick(time.delta());
+    }
+    let before = delays.timers.len();
+    delays.timers.retain(|timer| !timer.finished());
+    delays.due += before - delays.timers.len();
+    let Some(secs) = settings.respawn_delay else {
+        eaten.clear();
+        return;
+    };
+    for _ in eaten.read() {
+        delays
+            .timers
+            .push(Timer::from_seconds(secs, TimerMode::Once));
     }
 }
 
@@ -42,27 +147,1388 @@ impl FoodTimer {
 #[derive(Component)]
 pub struct Food;
 
+/// The variety of a piece of food
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FoodKind {
+    #[default]
+    Normal,
+    Golden,
+    Bonus,
+    Poison,
+    /// Freezes food spawning and drift for a while when eaten
+    Freeze,
+    /// Dashes the head `distance` tiles straight ahead when eaten
+    Dash {
+        distance: u32,
+    },
+}
+
+impl FoodKind {
+    /// Every kind of food
+    pub const ALL: [FoodKind; 6] = [
+        FoodKind::Normal,
+        FoodKind::Golden,
+        FoodKind::Bonus,
+        FoodKind::Poison,
+        FoodKind::Freeze,
+        FoodKind::Dash { distance: 3 },
+    ];
+
+    /// The points scored for eating food of this kind
+    pub fn points(self) -> u32 {
+        match self {
+            FoodKind::Normal => 1,
+            FoodKind::Golden => 3,
+            FoodKind::Bonus => 2,
+            FoodKind::Poison => 0,
+            FoodKind::Freeze => 1,
+            FoodKind::Dash { .. } => 1,
+        }
+    }
+
+    /// A short lowercase name for the kind, such as `golden`
+    pub fn name(self) -> &'static str {
+        match self {
+            FoodKind::Normal => "normal",
+            FoodKind::Golden => "golden",
+            FoodKind::Bonus => "bonus",
+            FoodKind::Poison => "poison",
+            FoodKind::Freeze => "freeze",
+            FoodKind::Dash { .. } => "dash",
+        }
+    }
+}
+
+/// The place of a piece of food in the order numbered food must be eaten in, from 1
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FoodOrder(pub u32);
+
+/// How many numbered pieces of food have been eaten in order this run
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct OrderedFood {
+    pub eaten: u32,
+}
+
+impl OrderedFood {
+    /// Whether food with the given order can be eaten now. Food without an order can
+    /// always be eaten.
+    pub fn allows(&self, order: Option<&FoodOrder>) -> bool {
+        order.is_none_or(|order| order.0 == self.eaten + 1)
+    }
+
+    /// Counts food with the given order as eaten
+    pub fn advance(&mut self, order: Option<&FoodOrder>) {
+        if order.is_some() {
+            self.eaten += 1;
+        }
+    }
+}
+
+/// Restricts where food may spawn
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FoodSpawnRegion {
+    /// Food may spawn on any tile in the arena
+    #[default]
+    FullArena,
+    /// Food may only spawn within the rectangle spanning `min` to `max` inclusive
+    Within { min: Position, max: Position },
+}
+
+impl FoodSpawnRegion {
+    pub fn contains(&self, tile: Position) -> bool {
+        match self {
+            FoodSpawnRegion::FullArena => true,
+            FoodSpawnRegion::Within { min, max } => {
+                (min.x..=max.x).contains(&tile.x) && (min.y..=max.y).contains(&tile.y)
+            }
+        }
+    }
+}
+
+/// The free tiles within `region` that food could be spawned on
+fn spawn_candidates(
+    arena: &ArenaConfig,
+    region: &FoodSpawnRegion,
+    occupied: &[Position],
+) -> Vec<Position> {
+    arena::free_tiles(arena, occupied)
+        .into_iter()
+        .filter(|tile| region.contains(*tile))
+        .collect()
+}
+
+/// A shape of food tiles spawned together
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FoodCluster {
+    /// The tiles of the cluster as offsets from its anchor tile
+    pub cells: Vec<Position>,
+}
+
+impl FoodCluster {
+    /// A single tile, the same as spawning without clusters
+    pub fn single() -> Self {
+        Self {
+            cells: vec![Position { x: 0, y: 0 }],
+        }
+    }
+
+    /// Three tiles in an L
+    pub fn l_tromino() -> Self {
+        Self {
+            cells: vec![
+                Position { x: 0, y: 0 },
+                Position { x: 1, y: 0 },
+                Position { x: 0, y: 1 },
+            ],
+        }
+    }
+
+    /// A 2x2 block
+    pub fn square() -> Self {
+        Self {
+            cells: vec![
+                Position { x: 0, y: 0 },
+                Position { x: 1, y: 0 },
+                Position { x: 0, y: 1 },
+                Position { x: 1, y: 1 },
+            ],
+        }
+    }
+
+    /// The tiles the cluster covers when anchored at `anchor`
+    pub fn tiles(&self, anchor: Position) -> impl Iterator<Item = Position> + '_ {
+        self.cells.iter().map(move |cell| anchor + *cell)
+    }
+}
+
+/// The anchor tiles a cluster fits on, with every one of its tiles free and within
+/// `region`
+fn cluster_candidates(
+    arena: &ArenaConfig,
+    region: &FoodSpawnRegion,
+    occupied: &[Position],
+    cluster: &FoodCluster,
+) -> Vec<Position> {
+    let free = spawn_candidates(arena, region, occupied);
+    let lookup: HashSet<Position> = free.iter().copied().collect();
+    free.into_iter()
+        .filter(|anchor| cluster.tiles(*anchor).all(|tile| lookup.contains(&tile)))
+        .collect()
+}
+
+/// Chooses the tile new food should be spawned on.
+///
+/// `roll` is expected in the range `[0, 1)` and selects one of the free tiles.
+/// When only one tile is free it is always chosen, and when the board is full
+/// `None` is returned so no food is spawned.
+fn choose_spawn_tile(free: &[Position], roll: f32) -> Option<Position> {
+    match free {
+        [] => None,
+        [only] => Some(*only),
+        tiles => {
+            let index = ((roll * tiles.len() as f32) as usize).min(tiles.len() - 1);
+            Some(tiles[index])
+        }
+    }
+}
+
 /// Spawns initial food and respawns food when collected
-fn spawn(time: Res<Time>, mut timer: ResMut<FoodTimer>, mut commands: Commands) {
-    if timer.clock.tick(time.delta()).just_finished() {
-        let x = (random::<f32>() * arena::WIDTH) as i32;
-        let y = (random::<f32>() * arena::HEIGHT) as i32;
-        commands.spawn(Sprite {
-            color: FOOD_COLOR,
-            ..Default::default()
-        })
+///
+/// Each run starts with `FoodSettings::initial_food` pieces placed straight away,
+/// the same way as the pieces placed when spawning is due.
+///
+/// The timer is driven by virtual time so no food is spawned while the game is
+/// paused and nothing is spawned in a burst on resume.
+///
+/// Food is only ever placed on a tile that is not already taken by the snake or
+/// other food, and only within the configured `FoodSpawnRegion`. If every candidate
+/// tile is occupied spawning is skipped. With `FoodSettings::clusters` set, each
+/// piece is a whole cluster placed only where all of its tiles are free. Nothing is
+/// spawned that would take the board past `FoodSettings::max_food`.
+///
+/// Placement is deterministic for a given `FoodRng` seed: the pieces of a batch are
+/// placed one after another, each drawing a single roll and picking from the free
+/// tiles in the row-major order of `free_tiles`, with earlier pieces of the batch
+/// counted as occupied. The order entities are visited in never affects the result,
+/// so the same seed and the same play always produce the same food layout.
+#[allow(clippy::too_many_arguments)]
+fn spawn(
+    time: Res<Time<Virtual>>,
+    settings: Res<FoodSettings>,
+    moves: Option<Res<MoveCount>>,
+    mut timer: ResMut<FoodTimer>,
+    mut delays: ResMut<RespawnDelays>,
+    mut commands: Commands,
+    arena: Res<ArenaConfig>,
+    region: Res<FoodSpawnRegion>,
+    mut rng: ResMut<FoodRng>,
+    assets: Option<Res<SpriteAssets>>,
+    occupied: Query<(&Position, Option<&FootprintSize>)>,
+    food: Query<(), With<Food>>,
+) {
+    let due = match settings.mode {
+        SpawnMode::RealTime { secs } => {
+            if settings.is_changed() {
+                timer.clock.set_duration(Duration::from_secs_f32(secs));
+            }
+            timer.clock.tick(time.delta()).just_finished()
+        }
+        SpawnMode::EveryNTicks(ticks) => {
+            let moves = moves.map_or(0, |moves| moves.total());
+            let due = moves.saturating_sub(timer.moves_at_last_spawn) >= ticks;
+            if due {
+                timer.moves_at_last_spawn = moves;
+            }
+            due
+        }
+    };
+    let mut count = if due { settings.per_spawn } else { 0 };
+    count += std::mem::take(&mut delays.due);
+    if timer.fresh_board {
+        timer.fresh_board = false;
+        count += settings.initial_food;
+    }
+    if count == 0 {
+        return;
+    }
+    let mut occupied: Vec<Position> = occupied
+        .iter()
+        .flat_map(|(pos, footprint)| footprint.copied().unwrap_or_default().tiles(*pos))
+        .collect();
+    let mut food_count = food.iter().count();
+    let single = FoodCluster::single();
+    for _ in 0..count {
+        let cluster = match settings.clusters.as_slice() {
+            [] => &single,
+            [only] => only,
+            shapes => {
+                let index = (rng.roll() * shapes.len() as f32) as usize;
+                &shapes[index.min(shapes.len() - 1)]
+            }
+        };
+        if settings
+            .max_food
+            .is_some_and(|max| food_count + cluster.cells.len() > max)
+        {
+            return;
+        }
+        let candidates = cluster_candidates(&arena, &region, &occupied, cluster);
+        let Some(anchor) = choose_spawn_tile(&candidates, rng.roll()) else {
+            return;
+        };
+        food_count += cluster.cells.len();
+        for position in cluster.tiles(anchor) {
+            occupied.push(position);
+            spawn_food_at(&mut commands, position, FoodKind::Normal, assets.as_deref());
+        }
+    }
+}
+
+/// Spawns a single tile of food of the given kind at `position`, whether or not the
+/// tile is free. Uses the food sprite from `assets` when one is loaded.
+pub fn spawn_food_at(
+    commands: &mut Commands,
+    position: Position,
+    kind: FoodKind,
+    assets: Option<&SpriteAssets>,
+) -> Entity {
+    let image = assets.and_then(|assets| assets.food.as_ref());
+    commands
+        .spawn(skin::sprite(image, FOOD_COLOR))
         .insert(Food)
-        .insert(Position { x, y })
-        .insert(Size::square(0.8));
+        .insert(kind)
+        .insert(position)
+        .insert(FootprintSize::default())
+        .insert(Size::square(0.8))
+        .id()
+}
+
+/// Places food at a chosen tile rather than a random one, for scripted scenarios and
+/// tutorials
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpawnFoodEvent {
+    pub position: Position,
+    pub kind: FoodKind,
+    /// Numbers the food as a puzzle objective, leaving it free to eat when `None`
+    pub order: Option<FoodOrder>,
+}
+
+fn spawn_requested_food(
+    mut commands: Commands,
+    mut requests: EventReader<SpawnFoodEvent>,
+    assets: Option<Res<SpriteAssets>>,
+) {
+    for request in requests.read() {
+        let food = spawn_food_at(
+            &mut commands,
+            request.position,
+            request.kind,
+            assets.as_deref(),
+        );
+        if let Some(order) = request.order {
+            commands.entity(food).insert(order);
+        }
+    }
+}
+
+/// Shows how close the next timed food spawn is as a bar in the HUD. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SpawnTimerBar {
+    pub enabled: bool,
+}
+
+/// Marks the outline of the spawn timer bar
+#[derive(Component)]
+struct SpawnTimerTrack;
+
+/// Marks the part of the spawn timer bar that fills up
+#[derive(Component)]
+struct SpawnTimerFill;
+
+fn spawn_timer_bar(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.),
+                left: Val::Px(8.),
+                width: Val::Px(60.),
+                height: Val::Px(6.),
+                ..Default::default()
+            },
+            BackgroundColor(SPAWN_TIMER_TRACK_COLOR),
+            Visibility::Hidden,
+            SpawnTimerTrack,
+        ))
+        .with_child((
+            Node {
+                width: Val::Percent(0.),
+                height: Val::Percent(100.),
+                ..Default::default()
+            },
+            BackgroundColor(FOOD_COLOR),
+            SpawnTimerFill,
+        ));
+}
+
+/// Fills the spawn timer bar to match `FoodTimer`, hiding it when the timer does not
+/// decide when food spawns next
+fn update_timer_bar(
+    bar: Res<SpawnTimerBar>,
+    settings: Res<FoodSettings>,
+    timer: Res<FoodTimer>,
+    food: Query<(), With<Food>>,
+    mut tracks: Query<&mut Visibility, With<SpawnTimerTrack>>,
+    mut fills: Query<&mut Node, With<SpawnTimerFill>>,
+) {
+    let capped = settings
+        .max_food
+        .is_some_and(|max| food.iter().count() >= max);
+    let timed = matches!(settings.mode, SpawnMode::RealTime { .. });
+    let visibility = if bar.enabled && timed && !capped {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut track in tracks.iter_mut() {
+        track.set_if_neq(visibility);
+    }
+    for mut fill in fills.iter_mut() {
+        fill.width = Val::Percent(timer.clock.fraction() * 100.);
+    }
+}
+
+/// The tiles one step from `pos` toward `target`, most preferred first.
+///
+/// The axis with the greater distance to cover is preferred, with ties going to the
+/// horizontal axis. Nothing is returned once `pos` has reached `target`.
+fn drift_steps(pos: Position, target: Position) -> Vec<Position> {
+    let dx = target.x - pos.x;
+    let dy = target.y - pos.y;
+    let horizontal = (dx != 0).then(|| Position {
+        x: pos.x + dx.signum(),
+        y: pos.y,
+    });
+    let vertical = (dy != 0).then(|| Position {
+        x: pos.x,
+        y: pos.y + dy.signum(),
+    });
+    let steps = if dx.abs() >= dy.abs() {
+        [horizontal, vertical]
+    } else {
+        [vertical, horizontal]
+    };
+    steps.into_iter().flatten().collect()
+}
+
+/// Moves every piece of food one tile toward the center of the arena on each
+/// `GameMode::Gravity` drift tick.
+///
+/// Food never drifts out of the arena or onto a tile taken by the snake or other
+/// food. When the preferred step is blocked the other axis is tried, and if both are
+/// blocked the food stays put until the next drift.
+fn drift_food(
+    mode: Res<GameMode>,
+    arena: Res<ArenaConfig>,
+    mut ticks: EventReader<TickEvent>,
+    mut entities: Query<(Entity, &mut Position, Option<&FootprintSize>, Has<Food>)>,
+) {
+    let GameMode::Gravity { every_ticks } = *mode else {
+        ticks.clear();
+        return;
+    };
+    let drifts = ticks
+        .read()
+        .filter(|tick| every_ticks > 0 && tick.tick % every_ticks == 0)
+        .count();
+    if drifts == 0 {
+        return;
+    }
+    let center = arena.center_tile();
+    let mut food: Vec<Entity> = entities
+        .iter()
+        .filter(|(_, _, _, is_food)| *is_food)
+        .map(|(entity, ..)| entity)
+        .collect();
+    food.sort();
+    for _ in 0..drifts {
+        for entity in food.iter().copied() {
+            let occupied: Vec<Position> = entities
+                .iter()
+                .filter(|(other, ..)| *other != entity)
+                .flat_map(|(_, pos, footprint, _)| {
+                    footprint.copied().unwrap_or_default().tiles(*pos)
+                })
+                .collect();
+            let Ok((_, mut pos, footprint, _)) = entities.get_mut(entity) else {
+                continue;
+            };
+            let footprint = footprint.copied().unwrap_or_default();
+            let next = drift_steps(*pos, center).into_iter().find(|next| {
+                footprint.tiles(*next).all(|tile| {
+                    arena::in_bounds(tile, &arena) && !arena::is_occupied(tile, &occupied)
+                })
+            });
+            if let Some(next) = next {
+                *pos = next;
+            }
+        }
+    }
+}
+
+/// The seeded random number generator that decides where food spawns.
+///
+/// The same seed always produces the same sequence of spawn tiles for the same
+/// snake, which is what makes a retry of a board fair and a replay reproducible.
+#[derive(Resource, Clone, Debug)]
+pub struct FoodRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl FoodRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// The seed the current board started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Rewinds to the start of the current board so the same food sequence repeats
+    pub fn retry(&mut self) {
+        *self = Self::from_seed(self.seed);
+    }
+
+    /// Moves on to a fresh board, drawing its seed from the current generator
+    pub fn new_board(&mut self) {
+        *self = Self::from_seed(self.rng.random());
+    }
+
+    fn roll(&mut self) -> f32 {
+        self.rng.random()
+    }
+}
+
+impl Default for FoodRng {
+    fn default() -> Self {
+        Self::from_seed(random())
+    }
+}
+
+/// How long eating `FoodKind::Freeze` food freezes the food for
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct FoodFreeze {
+    pub secs: f32,
+}
+
+impl Default for FoodFreeze {
+    fn default() -> Self {
+        Self { secs: 5.0 }
+    }
+}
+
+/// An effect given by eating a power-up food
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Powerup {
+    /// No food spawns and food stops drifting
+    Freeze,
+}
+
+/// The power-up in effect, if any, and how much longer it lasts
+#[derive(Resource, Default, Debug)]
+pub struct ActivePowerup(Option<(Powerup, Timer)>);
+
+impl ActivePowerup {
+    /// The power-up currently in effect
+    pub fn current(&self) -> Option<Powerup> {
+        self.0.as_ref().map(|(powerup, _)| *powerup)
+    }
+
+    fn clear(&mut self) {
+        self.0 = None;
+    }
+}
+
+/// Run condition that holds while food is not frozen by a power-up
+pub fn food_unfrozen(active: Option<Res<ActivePowerup>>) -> bool {
+    active.is_none_or(|active| active.current() != Some(Powerup::Freeze))
+}
+
+/// Counts down the power-up in effect, then starts any power-up just eaten
+fn update_powerup(
+    time: Res<Time<Virtual>>,
+    freeze: Res<FoodFreeze>,
+    mut active: ResMut<ActivePowerup>,
+    mut eaten: EventReader<FoodEatenEvent>,
+) {
+    if let Some((_, timer)) = active.0.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            active.clear();
+        }
+    }
+    for food in eaten.read() {
+        if food.kind == FoodKind::Freeze {
+            let timer = Timer::from_seconds(freeze.secs, TimerMode::Once);
+            active.0 = Some((Powerup::Freeze, timer));
+        }
+    }
+}
+
+/// What the food sequence does when the snake restarts after a game over
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BoardRestart {
+    /// Start a new board with a fresh seed
+    #[default]
+    NewBoard,
+    /// Retry the same board, reproducing the food sequence of the last run
+    SameBoard,
+}
+
+/// Resets the food sequence and spawn timing at the end of a run, or when the game is
+/// reset, according to `BoardRestart`
+#[allow(clippy::too_many_arguments)]
+fn restart_board(
+    mut reader: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+    restart: Res<BoardRestart>,
+    moves: Option<Res<MoveCount>>,
+    mut rng: ResMut<FoodRng>,
+    mut timer: ResMut<FoodTimer>,
+    mut powerup: ResMut<ActivePowerup>,
+    mut ordered: ResMut<OrderedFood>,
+    mut delays: ResMut<RespawnDelays>,
+) {
+    let died = reader.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if !died && !reset {
+        return;
+    }
+    match *restart {
+        BoardRestart::NewBoard => rng.new_board(),
+        BoardRestart::SameBoard => rng.retry(),
+    }
+    timer.restart(moves.map_or(0, |moves| moves.total()));
+    powerup.clear();
+    *ordered = OrderedFood::default();
+    *delays = RespawnDelays::default();
+}
+
+/// Returns the food sequence state to the start of a fresh game, for `despawn_game`.
+/// The food itself is despawned along with everything else on the board.
+pub(crate) fn clear_food(world: &mut World) {
+    if let Some(mut timer) = world.get_resource_mut::<FoodTimer>() {
+        timer.restart(0);
+    }
+    if let Some(mut powerup) = world.get_resource_mut::<ActivePowerup>() {
+        powerup.clear();
     }
+    if let Some(mut ordered) = world.get_resource_mut::<OrderedFood>() {
+        *ordered = OrderedFood::default();
+    }
+    if let Some(mut delays) = world.get_resource_mut::<RespawnDelays>() {
+        *delays = RespawnDelays::default();
+    }
+}
+
+/// What drives food spawning
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpawnMode {
+    /// Food spawns every `secs` seconds of play
+    RealTime { secs: f32 },
+    /// Food spawns after every `n` moves of the snake, independent of speed and frame rate
+    EveryNTicks(u64),
 }
 
-pub struct FoodPlugin;
+impl Default for SpawnMode {
+    fn default() -> Self {
+        SpawnMode::RealTime { secs: 2.0 }
+    }
+}
+
+/// Food spawning settings that can be changed while the game is running.
+///
+/// Insert this resource before adding `FoodPlugin` to use it in place of the
+/// settings the plugin was built with.
+#[derive(Resource, Clone, Debug)]
+pub struct FoodSettings {
+    pub mode: SpawnMode,
+    /// How many pieces of food are placed each time spawning is due
+    pub per_spawn: usize,
+    /// The shapes food spawns in, one picked at random for each piece. When empty
+    /// food spawns as single tiles.
+    pub clusters: Vec<FoodCluster>,
+    /// The most food allowed on the board at once, counting each tile of a cluster.
+    /// Unlimited when unset.
+    pub max_food: Option<usize>,
+    /// How many pieces of food are on the board when a run starts, before spawning
+    /// is first due
+    pub initial_food: usize,
+    /// Seconds after each piece is eaten until a replacement is placed. When unset
+    /// eaten food is not replaced, leaving spawning to `mode` alone.
+    pub respawn_delay: Option<f32>,
+}
+
+impl Default for FoodSettings {
+    fn default() -> Self {
+        Self {
+            mode: SpawnMode::default(),
+            per_spawn: 1,
+            clusters: Vec::new(),
+            max_food: None,
+            initial_food: 1,
+            respawn_delay: None,
+        }
+    }
+}
+
+/// The stages food runs through each frame, in order, after the snake has moved and
+/// eaten.
+///
+/// Anything that changes the board between runs, such as generated obstacles, can run
+/// `.after(FoodSet::Restart).before(FoodSet::Spawn)` to be in place before the
+/// starting food is placed.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FoodSet {
+    /// Resetting the food sequence when a run ends
+    Restart,
+    /// Placing new food
+    Spawn,
+}
+
+#[derive(Default)]
+pub struct FoodPlugin {
+    settings: FoodSettings,
+}
+
+impl FoodPlugin {
+    /// Spawns food every `secs` seconds
+    pub fn with_spawn_interval(self, secs: f32) -> Self {
+        self.with_spawn_mode(SpawnMode::RealTime { secs })
+    }
+
+    /// Sets what initially drives food spawning
+    pub fn with_spawn_mode(mut self, mode: SpawnMode) -> Self {
+        self.settings.mode = mode;
+        self
+    }
+
+    /// Places `count` pieces of food each time spawning is due
+    pub fn with_food_per_spawn(mut self, count: usize) -> Self {
+        self.settings.per_spawn = count;
+        self
+    }
+
+    /// Spawns food in the given cluster shapes rather than as single tiles
+    pub fn with_clusters(mut self, clusters: Vec<FoodCluster>) -> Self {
+        self.settings.clusters = clusters;
+        self
+    }
+
+    /// Stops spawning while `count` pieces of food are on the board
+    pub fn with_max_food(mut self, count: usize) -> Self {
+        self.settings.max_food = Some(count);
+        self
+    }
+
+    /// Starts each run with `count` pieces of food on the board
+    pub fn with_initial_food(mut self, count: usize) -> Self {
+        self.settings.initial_food = count;
+        self
+    }
+
+    /// Replaces each piece of food eaten `secs` seconds after it was eaten
+    pub fn with_respawn_delay(mut self, secs: f32) -> Self {
+        self.settings.respawn_delay = Some(secs);
+        self
+    }
+}
 
 impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
-        let timer = FoodTimer::from_seconds(2.0);
-        app.insert_resource(timer);
-        app.add_systems(Update, spawn);
+        if !app.world().contains_resource::<FoodSettings>() {
+            app.insert_resource(self.settings.clone());
+        }
+        let secs = match app.world().resource::<FoodSettings>().mode {
+            SpawnMode::RealTime { secs } => secs,
+            SpawnMode::EveryNTicks(_) => 2.0,
+        };
+        app.insert_resource(FoodTimer::from_seconds(secs));
+        app.init_resource::<FoodSpawnRegion>();
+        app.init_resource::<FoodRng>();
+        app.init_resource::<BoardRestart>();
+        app.init_resource::<SpawnTimerBar>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_event::<TickEvent>();
+        app.add_event::<SpawnFoodEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.init_resource::<FoodFreeze>();
+        app.init_resource::<ActivePowerup>();
+        app.init_resource::<OrderedFood>();
+        app.init_resource::<RespawnDelays>();
+        app.init_resource::<GameMode>();
+        // Spawning waits for the snake to settle, so food is never placed on a snake
+        // that is about to be respawned or under food that is about to be eaten
+        app.configure_sets(
+            Update,
+            (FoodSet::Restart, FoodSet::Spawn)
+                .chain()
+                .after(SnakeSet::Collision),
+        );
+        app.add_systems(
+            Update,
+            (update_powerup, time_replacements, restart_board)
+                .chain()
+                .in_set(FoodSet::Restart),
+        );
+        app.add_systems(
+            Update,
+            (spawn_requested_food, spawn.run_if(food_unfrozen))
+                .chain()
+                .in_set(FoodSet::Spawn),
+        );
+        app.add_systems(Startup, spawn_timer_bar);
+        app.add_systems(Update, update_timer_bar.after(FoodSet::Spawn));
+        app.add_systems(
+            Update,
+            drift_food
+                .run_if(food_unfrozen)
+                .after(SnakeSet::Movement)
+                .before(SnakeSet::Collision),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::Direction,
+        snake::{despawn_game, PlayerId},
+    };
+    use bevy::state::app::StatesPlugin;
+
+    fn all_tiles_except(free: Position) -> Vec<Position> {
+        let arena = ArenaConfig::default();
+        (0..arena.height)
+            .flat_map(|y| (0..arena.width).map(move |x| Position { x, y }))
+            .filter(|tile| *tile != free)
+            .collect()
+    }
+
+    #[test]
+    fn test_food_spawns_on_last_free_tile() {
+        let last = Position { x: 7, y: 2 };
+        let free = arena::free_tiles(&ArenaConfig::default(), &all_tiles_except(last));
+        assert_eq!(free, vec![last]);
+
+        for roll in [0.0, 0.5, 0.999] {
+            assert_eq!(choose_spawn_tile(&free, roll), Some(last));
+        }
+    }
+
+    #[test]
+    fn test_no_food_spawns_on_full_board() {
+        let mut occupied = all_tiles_except(Position { x: 0, y: 0 });
+        occupied.push(Position { x: 0, y: 0 });
+
+        let free = arena::free_tiles(&ArenaConfig::default(), &occupied);
+        assert!(free.is_empty());
+        assert_eq!(choose_spawn_tile(&free, 0.5), None);
+    }
+
+    #[test]
+    fn test_spawn_tile_selection_is_deterministic() {
+        let free = vec![
+            Position { x: 1, y: 1 },
+            Position { x: 2, y: 1 },
+            Position { x: 3, y: 1 },
+        ];
+        assert_eq!(choose_spawn_tile(&free, 0.0), Some(Position { x: 1, y: 1 }));
+        assert_eq!(choose_spawn_tile(&free, 0.5), Some(Position { x: 2, y: 1 }));
+        assert_eq!(choose_spawn_tile(&free, 1.0), Some(Position { x: 3, y: 1 }));
+    }
+
+    #[test]
+    fn test_spawn_interval_changes_at_runtime() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.add_plugins(FoodPlugin::default().with_spawn_interval(3.0));
+
+        app.update();
+        let timer = app.world().resource::<FoodTimer>();
+        assert_eq!(timer.clock.duration(), Duration::from_secs(3));
+
+        app.world_mut().resource_mut::<FoodSettings>().mode = SpawnMode::RealTime { secs: 0.5 };
+        app.update();
+        let timer = app.world().resource::<FoodTimer>();
+        assert_eq!(timer.clock.duration(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_spawn_timer_bar_follows_food_timer() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(SpawnTimerBar { enabled: true });
+        app.add_plugins(FoodPlugin::default().with_spawn_interval(2.0));
+        let bar = |app: &mut App, secs| {
+            let mut time = app.world_mut().resource_mut::<Time<Virtual>>();
+            time.advance_by(Duration::from_secs_f32(secs));
+            app.update();
+            let world = app.world_mut();
+            let visibility = *world
+                .query_filtered::<&Visibility, With<SpawnTimerTrack>>()
+                .single(world);
+            let width = world
+                .query_filtered::<&Node, With<SpawnTimerFill>>()
+                .single(world)
+                .width;
+            let fraction = world.resource::<FoodTimer>().clock.fraction();
+            assert_eq!(width, Val::Percent(fraction * 100.));
+            (visibility, width)
+        };
+
+        assert_eq!(
+            bar(&mut app, 0.0),
+            (Visibility::Inherited, Val::Percent(0.))
+        );
+        assert_eq!(
+            bar(&mut app, 0.5),
+            (Visibility::Inherited, Val::Percent(25.))
+        );
+        assert_eq!(
+            bar(&mut app, 1.0),
+            (Visibility::Inherited, Val::Percent(75.))
+        );
+        // Empties as food spawns
+        assert_eq!(
+            bar(&mut app, 0.5),
+            (Visibility::Inherited, Val::Percent(0.))
+        );
+
+        app.world_mut().resource_mut::<FoodSettings>().max_food = Some(2);
+        assert_eq!(bar(&mut app, 0.5).0, Visibility::Hidden);
+        app.world_mut().resource_mut::<FoodSettings>().max_food = None;
+        app.world_mut().resource_mut::<FoodSettings>().mode = SpawnMode::EveryNTicks(5);
+        assert_eq!(bar(&mut app, 0.5).0, Visibility::Hidden);
+    }
+
+    #[test]
+    fn test_spawn_candidates_stay_within_region() {
+        let arena = ArenaConfig::default();
+        let region = FoodSpawnRegion::Within {
+            min: Position { x: 2, y: 2 },
+            max: Position { x: 7, y: 7 },
+        };
+
+        let candidates = spawn_candidates(&arena, &region, &[Position { x: 4, y: 4 }]);
+        assert_eq!(candidates.len(), 35);
+        assert!(!candidates.contains(&Position { x: 4, y: 4 }));
+        for tile in &candidates {
+            assert!(
+                (2..=7).contains(&tile.x) && (2..=7).contains(&tile.y),
+                "{:?}",
+                tile
+            );
+        }
+
+        for roll in [0.0, 0.25, 0.5, 0.75, 0.999] {
+            let tile = choose_spawn_tile(&candidates, roll).unwrap();
+            assert!(region.contains(tile));
+        }
+    }
+
+    #[test]
+    fn test_full_arena_region_allows_every_free_tile() {
+        let arena = ArenaConfig::default();
+        let candidates = spawn_candidates(&arena, &FoodSpawnRegion::FullArena, &[]);
+        assert_eq!(candidates.len(), 100);
+    }
+
+    #[test]
+    fn test_food_spawns_every_n_ticks() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(5))
+                .with_initial_food(0),
+        );
+
+        let mut spawned = Vec::new();
+        for _ in 0..10 {
+            app.world_mut().resource_mut::<MoveCount>().increment();
+            app.update();
+            let world = app.world_mut();
+            spawned.push(world.query::<&Food>().iter(world).count());
+        }
+
+        assert_eq!(spawned, [0, 0, 0, 0, 1, 1, 1, 1, 1, 2]);
+    }
+
+    #[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+    enum Screen {
+        #[default]
+        Playing,
+        Title,
+    }
+
+    #[test]
+    fn test_food_spawns_every_n_ticks_after_leaving_and_reentering_the_game() {
+        let mut app = App::new();
+        app.add_plugins(StatesPlugin);
+        app.init_state::<Screen>();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(5))
+                .with_initial_food(1),
+        );
+        app.add_systems(OnExit(Screen::Playing), despawn_game);
+        let food_count = |app: &mut App| {
+            let world = app.world_mut();
+            world.query::<&Food>().iter(world).count()
+        };
+
+        for _ in 0..7 {
+            app.world_mut().resource_mut::<MoveCount>().increment();
+            app.update();
+        }
+        assert_eq!(food_count(&mut app), 2);
+
+        // Leaving the game takes the move count back to zero, and the board starts
+        // over with its initial food as soon as food spawns again
+        app.world_mut()
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Title);
+        app.update();
+        app.world_mut()
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Playing);
+        app.update();
+
+        let mut spawned = vec![food_count(&mut app)];
+        for _ in 0..5 {
+            app.world_mut().resource_mut::<MoveCount>().increment();
+            app.update();
+            spawned.push(food_count(&mut app));
+        }
+
+        assert_eq!(spawned, [1, 1, 1, 1, 1, 2]);
+    }
+
+    /// Spawns one piece of food per move and returns the tiles of the first `count`,
+    /// then ends the run and returns the tiles of the first `count` after the restart
+    fn food_across_restart(restart: BoardRestart, count: usize) -> (Vec<Position>, Vec<Position>) {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.insert_resource(FoodRng::from_seed(42));
+        app.insert_resource(restart);
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(1))
+                .with_initial_food(0),
+        );
+
+        let run = |app: &mut App| {
+            let mut tiles = Vec::new();
+            while tiles.len() < count {
+                app.world_mut().resource_mut::<MoveCount>().increment();
+                app.update();
+                let world = app.world_mut();
+                let food = world
+                    .query_filtered::<(Entity, &Position), With<Food>>()
+                    .iter(world)
+                    .map(|(entity, position)| (entity, *position))
+                    .collect::<Vec<_>>();
+                for (entity, position) in food {
+                    tiles.push(position);
+                    world.despawn(entity);
+                }
+            }
+            tiles
+        };
+
+        let first = run(&mut app);
+        app.world_mut().send_event(GameOverEvent);
+        let second = run(&mut app);
+        (first, second)
+    }
+
+    #[test]
+    fn test_same_board_retry_repeats_food_positions() {
+        let (first, retry) = food_across_restart(BoardRestart::SameBoard, 5);
+        assert_eq!(first, retry);
+    }
+
+    #[test]
+    fn test_new_board_changes_seed() {
+        let mut rng = FoodRng::from_seed(42);
+        rng.new_board();
+        assert_ne!(rng.seed(), 42);
+
+        let (first, next) = food_across_restart(BoardRestart::NewBoard, 5);
+        assert_ne!(first, next);
+    }
+
+    /// Runs `frames` frames spawning a batch of food each move, eating a piece every
+    /// other frame, and returns the food layout after each frame
+    fn food_history(seed: u64, frames: usize) -> Vec<Vec<Position>> {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.insert_resource(FoodRng::from_seed(seed));
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(1))
+                .with_food_per_spawn(3)
+                .with_initial_food(0),
+        );
+
+        let mut history = Vec::new();
+        for frame in 0..frames {
+            app.world_mut().resource_mut::<MoveCount>().increment();
+            app.update();
+            let world = app.world_mut();
+            let mut food = world
+                .query_filtered::<(Entity, &Position), With<Food>>()
+                .iter(world)
+                .map(|(entity, position)| (*position, entity))
+                .collect::<Vec<_>>();
+            food.sort_by_key(|(position, _)| (position.y, position.x));
+            if frame % 2 == 1 {
+                world.despawn(food.remove(0).1);
+            }
+            history.push(food.into_iter().map(|(position, _)| position).collect());
+        }
+        history
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_food_layouts() {
+        let first = food_history(7, 20);
+        let second = food_history(7, 20);
+        assert_eq!(first[0].len(), 3);
+        assert_eq!(first, second);
+        assert_ne!(first, food_history(8, 20));
+    }
+
+    #[test]
+    fn test_cluster_spawns_on_contiguous_free_tiles() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.insert_resource(FoodRng::from_seed(3));
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(1))
+                .with_clusters(vec![FoodCluster::l_tromino()]),
+        );
+        // Leave room for a single L in the bottom left corner
+        let snake: Vec<Position> = all_tiles_except(Position { x: 0, y: 0 })
+            .into_iter()
+            .filter(|tile| *tile != Position { x: 1, y: 0 } && *tile != Position { x: 0, y: 1 })
+            .collect();
+        for tile in snake {
+            app.world_mut().spawn(tile);
+        }
+
+        app.world_mut().resource_mut::<MoveCount>().increment();
+        app.update();
+
+        let world = app.world_mut();
+        let mut food: Vec<Position> = world
+            .query_filtered::<&Position, With<Food>>()
+            .iter(world)
+            .copied()
+            .collect();
+        food.sort_by_key(|tile| (tile.y, tile.x));
+        assert_eq!(
+            food,
+            [
+                Position { x: 0, y: 0 },
+                Position { x: 1, y: 0 },
+                Position { x: 0, y: 1 },
+            ]
+        );
+
+        // With no room left for a whole cluster nothing more is spawned
+        app.world_mut().resource_mut::<MoveCount>().increment();
+        app.update();
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Food>().iter(world).count(), 3);
+    }
+
+    #[test]
+    fn test_food_stops_at_max_food() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(1))
+                .with_food_per_spawn(2)
+                .with_max_food(5)
+                .with_initial_food(0),
+        );
+
+        let mut counts = Vec::new();
+        for _ in 0..4 {
+            app.world_mut().resource_mut::<MoveCount>().increment();
+            app.update();
+            let world = app.world_mut();
+            counts.push(world.query::<&Food>().iter(world).count());
+        }
+
+        assert_eq!(counts, [2, 4, 5, 5]);
+    }
+
+    #[test]
+    fn test_spawn_food_event_places_food() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.add_plugins(FoodPlugin::default().with_initial_food(0));
+
+        let position = Position { x: 5, y: 5 };
+        app.world_mut().send_event(SpawnFoodEvent {
+            position,
+            kind: FoodKind::Golden,
+            order: None,
+        });
+        app.update();
+
+        let world = app.world_mut();
+        let food: Vec<(Position, FoodKind)> = world
+            .query_filtered::<(&Position, &FoodKind), With<Food>>()
+            .iter(world)
+            .map(|(pos, kind)| (*pos, *kind))
+            .collect();
+        assert_eq!(food, [(position, FoodKind::Golden)]);
+    }
+
+    #[test]
+    fn test_spawn_food_event_numbers_food() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.add_plugins(FoodPlugin::default().with_initial_food(0));
+
+        for (x, order) in [(2, Some(FoodOrder(1))), (3, Some(FoodOrder(2))), (4, None)] {
+            app.world_mut().send_event(SpawnFoodEvent {
+                position: Position { x, y: 5 },
+                kind: FoodKind::Normal,
+                order,
+            });
+        }
+        app.update();
+
+        let world = app.world_mut();
+        let mut food: Vec<(i32, Option<FoodOrder>)> = world
+            .query_filtered::<(&Position, Option<&FoodOrder>), With<Food>>()
+            .iter(world)
+            .map(|(pos, order)| (pos.x, order.copied()))
+            .collect();
+        food.sort_by_key(|(x, _)| *x);
+        assert_eq!(
+            food,
+            [(2, Some(FoodOrder(1))), (3, Some(FoodOrder(2))), (4, None)]
+        );
+    }
+
+    #[test]
+    fn test_run_starts_with_initial_food() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(5))
+                .with_initial_food(5),
+        );
+        let food_count = |app: &mut App| {
+            let world = app.world_mut();
+            world.query::<&Food>().iter(world).count()
+        };
+
+        app.update();
+        assert_eq!(food_count(&mut app), 5);
+
+        // The starting food is placed again once the next run starts
+        let food: Vec<Entity> = {
+            let world = app.world_mut();
+            world
+                .query_filtered::<Entity, With<Food>>()
+                .iter(world)
+                .collect()
+        };
+        for entity in food {
+            app.world_mut().despawn(entity);
+        }
+        app.world_mut().send_event(GameOverEvent);
+        app.update();
+        assert_eq!(food_count(&mut app), 5);
+    }
+
+    #[test]
+    fn test_freeze_powerup_suppresses_spawning_until_it_ends() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(FoodFreeze { secs: 1.0 });
+        app.init_resource::<MoveCount>();
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(1))
+                .with_initial_food(0),
+        );
+        app.world_mut().send_event(FoodEatenEvent {
+            position: Position { x: 0, y: 0 },
+            kind: FoodKind::Freeze,
+            player: PlayerId::default(),
+        });
+
+        let mut spawned = Vec::new();
+        let mut frozen = Vec::new();
+        for _ in 0..6 {
+            app.world_mut().resource_mut::<MoveCount>().increment();
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(250));
+            app.update();
+            frozen.push(app.world().resource::<ActivePowerup>().current().is_some());
+            let world = app.world_mut();
+            spawned.push(world.query::<&Food>().iter(world).count());
+        }
+
+        assert_eq!(frozen, [true, true, true, true, false, false]);
+        assert_eq!(spawned, [0, 0, 0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_eaten_food_is_replaced_after_delay() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<MoveCount>();
+        app.add_plugins(
+            FoodPlugin::default()
+                .with_spawn_mode(SpawnMode::EveryNTicks(1000))
+                .with_initial_food(0)
+                .with_respawn_delay(0.5),
+        );
+        app.world_mut().send_event(FoodEatenEvent {
+            position: Position { x: 2, y: 2 },
+            kind: FoodKind::Normal,
+            player: PlayerId::default(),
+        });
+
+        let mut spawned = Vec::new();
+        for _ in 0..4 {
+            app.update();
+            let world = app.world_mut();
+            spawned.push(world.query::<&Food>().iter(world).count());
+            world
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(250));
+        }
+
+        assert_eq!(spawned, [0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_gravity_pulls_food_to_center() {
+        let mut app = App::new();
+        app.insert_resource(GameMode::Gravity { every_ticks: 2 });
+        app.init_resource::<ArenaConfig>();
+        app.add_event::<TickEvent>();
+        app.add_systems(Update, drift_food);
+
+        let center = ArenaConfig::default().center_tile();
+        let food = app
+            .world_mut()
+            .spawn((Food, Position { x: 0, y: center.y }))
+            .id();
+        let stuck = app.world_mut().spawn((Food, Position { x: 8, y: 0 })).id();
+        // A wall of snake between the second piece and the center
+        for y in 0..10 {
+            app.world_mut().spawn(Position { x: 7, y });
+        }
+
+        let mut path = Vec::new();
+        for tick in 1..=10 {
+            app.world_mut().send_event(TickEvent {
+                tick,
+                direction: Direction::Up,
+            });
+            app.update();
+            path.push(app.world().get::<Position>(food).unwrap().x);
+        }
+
+        // Drifts on every second tick and then rests on the center tile
+        assert_eq!(path, [0, 1, 1, 2, 2, 3, 3, 4, 4, 4]);
+        assert_eq!(*app.world().get::<Position>(food).unwrap(), center);
+        // Blocked along x, the other piece falls back to climbing toward the center row
+        assert_eq!(
+            *app.world().get::<Position>(stuck).unwrap(),
+            Position { x: 8, y: center.y }
+        );
     }
 }
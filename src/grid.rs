@@ -0,0 +1,169 @@
+//! Grid module
+//!
+//! This module draws a faint checkerboard behind the arena so the tiles the snake
+//! moves between are easy to count.
+//!
+//! The grid can be hidden at any time with the G key or `BackgroundGrid::visible`.
+//! Toggling only changes the `Visibility` of the grid tiles, which are kept around
+//! rather than despawned. The tiles are rebuilt only when the arena changes shape.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::arena::{self, ArenaConfig, Position, Size};
+
+/// A mid grey, faint enough to sit behind the snake on both the light and dark themes
+const GRID_COLOR: Color = Color::srgba(0.5, 0.5, 0.5, 0.08);
+
+/// Draws the grid below everything else on the board
+const GRID_DEPTH: f32 = -1.0;
+
+/// Whether the background grid is shown. On by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BackgroundGrid {
+    pub visible: bool,
+}
+
+impl Default for BackgroundGrid {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+impl BackgroundGrid {
+    fn visibility(self) -> Visibility {
+        if self.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    }
+}
+
+/// A shaded tile of the checkerboard.
+///
+/// The tile is stored here rather than as a `Position` so the grid is never mistaken
+/// for something occupying the board.
+#[derive(Component)]
+struct GridTile(Position);
+
+/// Builds the checkerboard over every playable tile, shading every other one
+fn spawn_grid(
+    mut commands: Commands,
+    arena: Res<ArenaConfig>,
+    grid: Res<BackgroundGrid>,
+    tiles: Query<Entity, With<GridTile>>,
+) {
+    if !arena.is_changed() {
+        return;
+    }
+    for tile in tiles.iter() {
+        commands.entity(tile).despawn();
+    }
+    for tile in arena::free_tiles(&arena, &[]) {
+        if (tile.x + tile.y) % 2 == 0 {
+            commands.spawn((
+                Sprite {
+                    color: GRID_COLOR,
+                    ..Default::default()
+                },
+                GridTile(tile),
+                Size::square(1.0),
+                Transform::from_xyz(0., 0., GRID_DEPTH),
+                grid.visibility(),
+            ));
+        }
+    }
+}
+
+fn toggle_grid(input: Res<ButtonInput<KeyCode>>, mut grid: ResMut<BackgroundGrid>) {
+    if input.just_pressed(KeyCode::KeyG) {
+        grid.visible = !grid.visible;
+    }
+}
+
+fn show_grid(grid: Res<BackgroundGrid>, mut tiles: Query<&mut Visibility, With<GridTile>>) {
+    if !grid.is_changed() {
+        return;
+    }
+    for mut visibility in tiles.iter_mut() {
+        *visibility = grid.visibility();
+    }
+}
+
+/// Places the grid tiles on the window, like `arena::position_translation` does for
+/// entities with a `Position`
+fn place_grid(
+    window: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<ArenaConfig>,
+    mut tiles: Query<(&GridTile, &mut Transform)>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    for (GridTile(tile), mut transform) in tiles.iter_mut() {
+        let world = arena::grid_to_world(*tile, window.size(), &arena);
+        transform.translation = world.extend(GRID_DEPTH);
+    }
+}
+
+pub struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackgroundGrid>();
+        app.init_resource::<ArenaConfig>();
+        app.add_systems(Update, (toggle_grid, spawn_grid, show_grid).chain());
+        app.add_systems(PostUpdate, place_grid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_visibility(app: &mut App) -> Vec<(Entity, Visibility)> {
+        let world = app.world_mut();
+        let mut tiles: Vec<(Entity, Visibility)> = world
+            .query_filtered::<(Entity, &Visibility), With<GridTile>>()
+            .iter(world)
+            .map(|(entity, visibility)| (entity, *visibility))
+            .collect();
+        tiles.sort_by_key(|(entity, _)| *entity);
+        tiles
+    }
+
+    #[test]
+    fn test_toggle_hides_grid_without_despawning() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.add_plugins(GridPlugin);
+        app.update();
+
+        let shown = grid_visibility(&mut app);
+        assert_eq!(shown.len(), 50, "half of a 10x10 board is shaded");
+        assert!(shown
+            .iter()
+            .all(|(_, visibility)| *visibility == Visibility::Inherited));
+
+        let press_g = |app: &mut App| {
+            let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            input.clear();
+            input.release(KeyCode::KeyG);
+            input.press(KeyCode::KeyG);
+            app.update();
+        };
+
+        press_g(&mut app);
+        let hidden = grid_visibility(&mut app);
+        assert!(hidden
+            .iter()
+            .all(|(_, visibility)| *visibility == Visibility::Hidden));
+        assert!(hidden
+            .iter()
+            .map(|(entity, _)| entity)
+            .eq(shown.iter().map(|(entity, _)| entity)));
+
+        press_g(&mut app);
+        assert_eq!(grid_visibility(&mut app), shown);
+    }
+}
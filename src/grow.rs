@@ -0,0 +1,206 @@
+//! Growing arena module
+//!
+//! This module adds a mode where the arena opens up as the player scores, the
+//! opposite of the shrinking arena. Every `GrowingArena::every_points` points the
+//! board gains `grow_by` tiles in each dimension, until a side reaches `max_size`.
+//!
+//! New tiles are added along the right and top edges, so every tile the snake and
+//! food are on stays in bounds and nothing has to move. The board is drawn centered,
+//! and tiles get smaller to fit the window as it grows. Arenas shaped by a mask keep
+//! their shape and never grow. The arena returns to its starting size at the start of
+//! every run. An arena replaced during a run, such as by switching levels, is taken as
+//! the new starting size rather than being shrunk back.
+//!
+//! The mode is off by default. Enable it with `GrowingArena::enabled` or the `--grow`
+//! command line flag.
+
+use bevy::prelude::*;
+
+use crate::{
+    arena::ArenaConfig,
+    food::FoodSet,
+    score::Score,
+    snake::{GameOverEvent, ResetGameEvent, SnakeSet},
+};
+
+/// Settings for the growing arena mode
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GrowingArena {
+    pub enabled: bool,
+    /// Points scored between each time the arena grows
+    pub every_points: u32,
+    /// Tiles added to the width and height each time the arena grows
+    pub grow_by: i32,
+    /// The arena stops growing along a side once it is this many tiles long
+    pub max_size: i32,
+}
+
+impl Default for GrowingArena {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            every_points: 10,
+            grow_by: 2,
+            max_size: 30,
+        }
+    }
+}
+
+impl GrowingArena {
+    /// How many tiles a side starting `base` tiles long grows by once `score` points
+    /// have been scored
+    pub fn growth(&self, base: i32, score: u32) -> i32 {
+        let milestones = score.checked_div(self.every_points).unwrap_or(0);
+        let wanted = milestones.min(i32::MAX as u32) as i32 * self.grow_by.max(0);
+        wanted.min(self.max_size - base).max(0)
+    }
+}
+
+/// The tiles added to the width and height of the arena during the current run
+#[derive(Resource, Default, Debug)]
+struct ArenaGrowth {
+    width: i32,
+    height: i32,
+    /// The size the arena was grown to, to tell when it has been replaced since
+    size: (i32, i32),
+}
+
+impl ArenaGrowth {
+    /// Forgets the growth once the arena no longer has the size it was grown to, as
+    /// the growth belongs to an arena that has been replaced
+    fn forget_if_replaced(&mut self, arena: &ArenaConfig) {
+        let grown = self.width != 0 || self.height != 0;
+        if grown && (arena.mask.is_some() || (arena.width, arena.height) != self.size) {
+            *self = ArenaGrowth::default();
+        }
+    }
+}
+
+/// Grows the arena to match the milestones reached by the score. The arena never
+/// shrinks back during a run, even if the score drops.
+fn grow_arena(
+    settings: Res<GrowingArena>,
+    score: Res<Score>,
+    mut growth: ResMut<ArenaGrowth>,
+    mut arena: ResMut<ArenaConfig>,
+) {
+    growth.forget_if_replaced(&arena);
+    if !settings.enabled || arena.mask.is_some() {
+        return;
+    }
+    let width = settings.growth(arena.width - growth.width, score.current);
+    let height = settings.growth(arena.height - growth.height, score.current);
+    if width > growth.width {
+        arena.width += width - growth.width;
+        growth.width = width;
+    }
+    if height > growth.height {
+        arena.height += height - growth.height;
+        growth.height = height;
+    }
+    growth.size = (arena.width, arena.height);
+}
+
+/// Returns the arena to its starting size for the next run
+fn restore_arena(
+    mut growth: ResMut<ArenaGrowth>,
+    mut arena: ResMut<ArenaConfig>,
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    growth.forget_if_replaced(&arena);
+    if !died && !reset || growth.width == 0 && growth.height == 0 {
+        return;
+    }
+    arena.width -= growth.width;
+    arena.height -= growth.height;
+    *growth = ArenaGrowth::default();
+}
+
+pub struct GrowPlugin;
+
+impl Plugin for GrowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GrowingArena>();
+        app.init_resource::<ArenaGrowth>();
+        app.init_resource::<ArenaConfig>();
+        app.init_resource::<Score>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        // The arena is back to its starting size before the food of the next run is
+        // placed
+        app.add_systems(
+            Update,
+            (restore_arena, grow_arena)
+                .chain()
+                .after(SnakeSet::Collision)
+                .before(FoodSet::Spawn),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena_grows_at_milestones_up_to_cap() {
+        let mut app = App::new();
+        app.insert_resource(GrowingArena {
+            enabled: true,
+            every_points: 10,
+            grow_by: 2,
+            max_size: 15,
+        });
+        app.add_plugins(GrowPlugin);
+        let size_at = |app: &mut App, score| {
+            app.world_mut().resource_mut::<Score>().current = score;
+            app.update();
+            let arena = app.world().resource::<ArenaConfig>();
+            (arena.width, arena.height)
+        };
+
+        let sizes: Vec<(i32, i32)> = [0, 9, 10, 25, 30, 60]
+            .into_iter()
+            .map(|score| size_at(&mut app, score))
+            .collect();
+        assert_eq!(
+            sizes,
+            [(10, 10), (10, 10), (12, 12), (14, 14), (15, 15), (15, 15)]
+        );
+
+        // The next run starts on the original board
+        app.world_mut().send_event(GameOverEvent);
+        assert_eq!(size_at(&mut app, 0), (10, 10));
+    }
+
+    #[test]
+    fn test_replaced_arena_is_not_shrunk_back() {
+        let mut app = App::new();
+        app.insert_resource(GrowingArena {
+            enabled: true,
+            every_points: 10,
+            grow_by: 2,
+            max_size: 30,
+        });
+        app.add_plugins(GrowPlugin);
+        app.world_mut().resource_mut::<Score>().current = 10;
+        app.update();
+        assert_eq!(app.world().resource::<ArenaConfig>().width, 12);
+
+        // Switching levels mid-run replaces the grown arena and starts over
+        app.insert_resource(ArenaConfig {
+            width: 16,
+            height: 8,
+            ..Default::default()
+        });
+        app.world_mut().resource_mut::<Score>().current = 0;
+        app.world_mut().send_event(ResetGameEvent);
+        app.update();
+
+        let arena = app.world().resource::<ArenaConfig>();
+        assert_eq!((arena.width, arena.height), (16, 8));
+    }
+}
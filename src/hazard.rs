@@ -0,0 +1,217 @@
+//! Hazard module
+//!
+//! This module defines tiles that are dangerous to the snake.
+//!
+//! Spikes alternate between a lethal and a safe phase on a cycle counted in movement
+//! ticks, so crossing them is a matter of timing. The phase is derived from
+//! `MoveCount`, which keeps it in step with the snake regardless of frame rate or
+//! speed. Spike tiles and their cycle are set by the level being played, and are
+//! registered as `Spikes` tile effects so the snake meets them as it moves.
+
+use std::{collections::HashSet, sync::Arc};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    arena::{Position, Size},
+    snake::MoveCount,
+    tile::{TileEffect, TileEffects, TileOutcome},
+};
+
+const SPIKES_LETHAL_COLOR: Color = Color::srgb(0.85, 0.2, 0.2);
+const SPIKES_SAFE_COLOR: Color = Color::srgb(0.35, 0.25, 0.25);
+
+/// How long spikes stay lethal and then safe, in movement ticks
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpikeCycle {
+    pub lethal_ticks: u64,
+    pub safe_ticks: u64,
+}
+
+impl SpikeCycle {
+    /// Returns true if spikes are lethal on the given tick. Each cycle starts with
+    /// its lethal phase.
+    pub fn is_lethal(&self, tick: u64) -> bool {
+        let period = self.lethal_ticks + self.safe_ticks;
+        period > 0 && tick % period < self.lethal_ticks
+    }
+}
+
+impl Default for SpikeCycle {
+    fn default() -> Self {
+        Self {
+            lethal_ticks: 2,
+            safe_ticks: 2,
+        }
+    }
+}
+
+/// The spike tiles of the arena and the cycle they share
+#[derive(Resource, Clone, Default, Debug)]
+pub struct SpikeField {
+    pub tiles: HashSet<Position>,
+    pub cycle: SpikeCycle,
+}
+
+impl SpikeField {
+    /// Returns true if a snake head on `tile` is killed by spikes on `tick`
+    pub fn is_lethal(&self, tile: Position, tick: u64) -> bool {
+        self.tiles.contains(&tile) && self.cycle.is_lethal(tick)
+    }
+}
+
+/// The tile effect of a spike tile, killing the snake while the cycle is lethal
+#[derive(Clone, Copy, Debug)]
+pub struct Spikes {
+    pub cycle: SpikeCycle,
+}
+
+impl TileEffect for Spikes {
+    fn on_enter(&self, _tile: Position, tick: u64, _commands: &mut Commands) -> TileOutcome {
+        if self.cycle.is_lethal(tick) {
+            TileOutcome::Kill
+        } else {
+            TileOutcome::Continue
+        }
+    }
+}
+
+/// Keeps the spike effects registered in `TileEffects` in line with the field
+fn register_spikes(
+    field: Res<SpikeField>,
+    mut effects: ResMut<TileEffects>,
+    mut registered: Local<HashSet<Position>>,
+) {
+    if !field.is_changed() {
+        return;
+    }
+    for tile in registered.drain() {
+        effects.remove(tile);
+    }
+    let spikes = Arc::new(Spikes { cycle: field.cycle });
+    effects.insert_shared(field.tiles.iter().copied(), spikes);
+    registered.extend(field.tiles.iter().copied());
+}
+
+/// Marks the sprite drawn for a spike tile
+#[derive(Component)]
+struct SpikeTile;
+
+/// Spawns a sprite for every spike tile whenever the field changes
+fn draw_spikes(
+    mut commands: Commands,
+    field: Res<SpikeField>,
+    tiles: Query<Entity, With<SpikeTile>>,
+) {
+    if !field.is_changed() {
+        return;
+    }
+    for tile in tiles.iter() {
+        commands.entity(tile).despawn();
+    }
+    for tile in field.tiles.iter() {
+        commands.spawn((
+            Sprite {
+                color: SPIKES_SAFE_COLOR,
+                ..Default::default()
+            },
+            SpikeTile,
+            *tile,
+            Size::square(0.9),
+        ));
+    }
+}
+
+/// Colors spikes by whether they will be lethal on the next tick
+fn show_spike_phase(
+    field: Res<SpikeField>,
+    moves: Res<MoveCount>,
+    mut tiles: Query<&mut Sprite, With<SpikeTile>>,
+) {
+    let color = if field.cycle.is_lethal(moves.total() + 1) {
+        SPIKES_LETHAL_COLOR
+    } else {
+        SPIKES_SAFE_COLOR
+    };
+    for mut sprite in tiles.iter_mut() {
+        sprite.color = color;
+    }
+}
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpikeField>();
+        app.init_resource::<TileEffects>();
+        app.add_systems(
+            Update,
+            (register_spikes, draw_spikes, show_spike_phase).chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spikes_are_lethal_only_during_active_phase() {
+        let cycle = SpikeCycle {
+            lethal_ticks: 2,
+            safe_ticks: 3,
+        };
+        let lethal: Vec<bool> = (0..10).map(|tick| cycle.is_lethal(tick)).collect();
+        assert_eq!(
+            lethal,
+            [true, true, false, false, false, true, true, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_spikes_only_hurt_on_their_tiles() {
+        let field = SpikeField {
+            tiles: HashSet::from([Position { x: 2, y: 2 }]),
+            cycle: SpikeCycle::default(),
+        };
+        assert!(field.is_lethal(Position { x: 2, y: 2 }, 0));
+        assert!(!field.is_lethal(Position { x: 2, y: 2 }, 2));
+        assert!(!field.is_lethal(Position { x: 3, y: 2 }, 0));
+    }
+
+    #[test]
+    fn test_spike_field_is_registered_as_tile_effects() {
+        let mut app = App::new();
+        app.init_resource::<MoveCount>();
+        app.add_plugins(HazardPlugin);
+        app.insert_resource(SpikeField {
+            tiles: HashSet::from([Position { x: 2, y: 2 }]),
+            cycle: SpikeCycle::default(),
+        });
+        app.update();
+        assert!(app
+            .world()
+            .resource::<TileEffects>()
+            .get(Position { x: 2, y: 2 })
+            .is_some());
+
+        app.insert_resource(SpikeField {
+            tiles: HashSet::from([Position { x: 4, y: 1 }]),
+            cycle: SpikeCycle::default(),
+        });
+        app.update();
+        let effects = app.world().resource::<TileEffects>();
+        assert!(effects.get(Position { x: 2, y: 2 }).is_none());
+        assert!(effects.get(Position { x: 4, y: 1 }).is_some());
+    }
+
+    #[test]
+    fn test_empty_cycle_is_never_lethal() {
+        let cycle = SpikeCycle {
+            lethal_ticks: 0,
+            safe_ticks: 0,
+        };
+        assert!(!cycle.is_lethal(0));
+    }
+}
@@ -0,0 +1,302 @@
+//! HUD module
+//!
+//! This module draws the heads-up display shown on top of the arena.
+//!
+//! The HUD currently consists of a badge in the top right corner showing the
+//! difficulty preset and the live movement tick interval, which shrinks as the
+//! snake speeds up. The speed is shown in milliseconds per tick by default, or in
+//! cells per second with `SpeedUnit::CellsPerSecond`.
+//!
+//! In the top left corner, below the food spawn timer bar, each player with a snake on
+//! the board gets a line with their score, drawn in the head color of their snake.
+//!
+//! `HudScale` enlarges or shrinks all of the UI at once, HUD text included, for
+//! players on very large or very small displays. It can also be set with the
+//! `SLITHER_UI_SCALE` environment variable, e.g. `SLITHER_UI_SCALE=1.5`.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, ui::UiScale};
+
+use crate::{
+    score::PlayerScores,
+    snake::{Difficulty, MovementTimer, PlayerId, SnakeHead},
+    theme::PlayerColors,
+};
+
+const HUD_TEXT_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+/// Keeps the score lines clear of the food spawn timer bar in the same corner
+const PLAYER_SCORES_TOP: f32 = 20.;
+const PLAYER_SCORE_LINE_HEIGHT: f32 = 18.;
+
+/// Marks the text entity showing the difficulty and speed
+#[derive(Component)]
+struct DifficultyBadge;
+
+/// Marks the text entity showing the score of a player
+#[derive(Component)]
+struct PlayerScoreReadout(PlayerId);
+
+/// The unit the HUD shows the snake's speed in
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SpeedUnit {
+    /// The movement tick interval, rounded to the nearest millisecond
+    #[default]
+    Millis,
+    /// The tiles moved per second, to one decimal place
+    CellsPerSecond,
+}
+
+/// How much larger than normal all UI is drawn, applied through Bevy's `UiScale`
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct HudScale(pub f32);
+
+impl Default for HudScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl HudScale {
+    /// Parses a scale factor, which must be a positive number
+    pub fn parse(value: &str) -> Option<Self> {
+        let factor: f32 = value.trim().parse().ok()?;
+        (factor.is_finite() && factor > 0.).then_some(Self(factor))
+    }
+}
+
+/// Formats the speed of a snake moving one tile every `interval` in `unit`
+pub fn format_speed(interval: Duration, unit: SpeedUnit) -> String {
+    match unit {
+        SpeedUnit::Millis => format!("{} ms", (interval.as_secs_f64() * 1000.).round()),
+        SpeedUnit::CellsPerSecond => {
+            let secs = interval.as_secs_f64();
+            if secs == 0. {
+                return "- cells/s".to_string();
+            }
+            format!("{:.1} cells/s", 1. / secs)
+        }
+    }
+}
+
+/// Formats the badge text
+fn badge_text(difficulty: Difficulty, interval: Duration, unit: SpeedUnit) -> String {
+    format!("{} · {}", difficulty.name(), format_speed(interval, unit))
+}
+
+fn spawn_badge(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 14.,
+            ..Default::default()
+        },
+        TextColor(HUD_TEXT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.),
+            right: Val::Px(8.),
+            ..Default::default()
+        },
+        DifficultyBadge,
+    ));
+}
+
+fn update_badge(
+    difficulty: Res<Difficulty>,
+    timer: Res<MovementTimer>,
+    unit: Res<SpeedUnit>,
+    mut badges: Query<&mut Text, With<DifficultyBadge>>,
+) {
+    for mut text in badges.iter_mut() {
+        text.0 = badge_text(*difficulty, timer.interval(), *unit);
+    }
+}
+
+/// Formats the score line of `player`, counting players from one
+fn player_score_text(player: PlayerId, score: u32) -> String {
+    format!("P{}: {}", u32::from(player.0) + 1, score)
+}
+
+/// Gives every player with a snake a score line and keeps the lines up to date
+fn update_player_scores(
+    mut commands: Commands,
+    scores: Option<Res<PlayerScores>>,
+    colors: Option<Res<PlayerColors>>,
+    heads: Query<Option<&PlayerId>, With<SnakeHead>>,
+    mut readouts: Query<(&PlayerScoreReadout, &mut Text, &mut TextColor)>,
+) {
+    let score_of = |player| scores.as_ref().map_or(0, |scores| scores.of(player));
+    let color_of = |player| {
+        colors
+            .as_ref()
+            .map_or_else(
+                || PlayerColors::default().of(player),
+                |colors| colors.of(player),
+            )
+            .head
+    };
+    let mut shown: Vec<PlayerId> = readouts.iter().map(|(readout, ..)| readout.0).collect();
+    for player in heads.iter() {
+        let player = player.copied().unwrap_or_default();
+        if shown.contains(&player) {
+            continue;
+        }
+        commands.spawn((
+            Text::new(player_score_text(player, score_of(player))),
+            TextFont {
+                font_size: 14.,
+                ..Default::default()
+            },
+            TextColor(color_of(player)),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(PLAYER_SCORES_TOP + PLAYER_SCORE_LINE_HEIGHT * f32::from(player.0)),
+                left: Val::Px(8.),
+                ..Default::default()
+            },
+            PlayerScoreReadout(player),
+        ));
+        shown.push(player);
+    }
+    for (readout, mut text, mut color) in readouts.iter_mut() {
+        let line = player_score_text(readout.0, score_of(readout.0));
+        if text.0 != line {
+            text.0 = line;
+        }
+        color.0 = color_of(readout.0);
+    }
+}
+
+fn apply_hud_scale(scale: Res<HudScale>, mut ui_scale: ResMut<UiScale>) {
+    if scale.is_changed() {
+        ui_scale.0 = scale.0;
+    }
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeedUnit>();
+        app.init_resource::<HudScale>();
+        app.init_resource::<UiScale>();
+        app.add_systems(Startup, spawn_badge);
+        app.add_systems(
+            Update,
+            (apply_hud_scale, update_badge, update_player_scores),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Direction;
+
+    #[test]
+    fn test_badge_shows_rounded_interval() {
+        assert_eq!(
+            badge_text(
+                Difficulty::Hard,
+                Duration::from_millis(110),
+                SpeedUnit::Millis
+            ),
+            "Hard · 110 ms"
+        );
+        assert_eq!(
+            badge_text(
+                Difficulty::Normal,
+                Duration::from_secs_f32(0.1216),
+                SpeedUnit::Millis
+            ),
+            "Normal · 122 ms"
+        );
+        assert_eq!(
+            badge_text(
+                Difficulty::Easy,
+                Duration::from_micros(99_400),
+                SpeedUnit::Millis
+            ),
+            "Easy · 99 ms"
+        );
+    }
+
+    #[test]
+    fn test_each_player_gets_a_score_line_in_their_color() {
+        let mut app = App::new();
+        app.init_resource::<PlayerScores>();
+        app.init_resource::<PlayerColors>();
+        app.add_systems(Update, update_player_scores);
+        app.world_mut()
+            .spawn((SnakeHead::facing(Direction::Up), PlayerId(0)));
+        app.world_mut()
+            .spawn((SnakeHead::facing(Direction::Up), PlayerId(1)));
+        app.update();
+
+        let world = app.world_mut();
+        let mut lines = world
+            .query::<(&PlayerScoreReadout, &Text, &TextColor)>()
+            .iter(world)
+            .map(|(readout, text, color)| (readout.0, text.0.clone(), color.0))
+            .collect::<Vec<_>>();
+        lines.sort_by_key(|(player, ..)| player.0);
+        let colors = PlayerColors::default();
+        assert_eq!(
+            lines,
+            [
+                (
+                    PlayerId(0),
+                    "P1: 0".to_string(),
+                    colors.of(PlayerId(0)).head
+                ),
+                (
+                    PlayerId(1),
+                    "P2: 0".to_string(),
+                    colors.of(PlayerId(1)).head
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hud_scale_is_applied_to_ui() {
+        assert_eq!(HudScale::parse(" 1.5 "), Some(HudScale(1.5)));
+        assert_eq!(HudScale::parse("0"), None);
+        assert_eq!(HudScale::parse("big"), None);
+
+        let mut app = App::new();
+        app.init_resource::<HudScale>();
+        app.init_resource::<UiScale>();
+        app.add_systems(Update, apply_hud_scale);
+        app.update();
+        assert_eq!(app.world().resource::<UiScale>().0, 1.0);
+
+        app.insert_resource(HudScale(1.5));
+        app.update();
+        assert_eq!(app.world().resource::<UiScale>().0, 1.5);
+    }
+
+    #[test]
+    fn test_format_speed_in_both_units() {
+        let cases = [
+            (150, "150 ms", "6.7 cells/s"),
+            (100, "100 ms", "10.0 cells/s"),
+            (250, "250 ms", "4.0 cells/s"),
+            (1000, "1000 ms", "1.0 cells/s"),
+        ];
+        for (millis, ms, cells) in cases {
+            let interval = Duration::from_millis(millis);
+            assert_eq!(format_speed(interval, SpeedUnit::Millis), ms);
+            assert_eq!(format_speed(interval, SpeedUnit::CellsPerSecond), cells);
+        }
+        assert_eq!(
+            badge_text(
+                Difficulty::Normal,
+                Duration::from_millis(150),
+                SpeedUnit::CellsPerSecond
+            ),
+            "Normal · 6.7 cells/s"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Level module
+//!
+//! This module loads custom levels from the `levels` directory.
+//!
+//! Each level is a RON file describing the shape of the arena as rows of text, top
+//! row first, where `#` cuts a tile out of the playfield and `^` places spikes. The
+//! spike cycle is optional and defaults to 2 lethal ticks followed by 2 safe ones:
+//!
+//! ```ron
+//! (
+//!     name: "Donut",
+//!     rows: [
+//!         "..^..",
+//!         "..#..",
+//!         ".....",
+//!     ],
+//!     spike_cycle: (lethal_ticks: 1, safe_ticks: 3),
+//! )
+//! ```
+//!
+//! Every `.ron` file in the directory is loaded at startup into the `LevelLibrary`.
+//! Files that cannot be read or parsed are skipped with a warning, and a missing
+//! directory simply leaves the library empty.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    arena::{ArenaConfig, ArenaMask, Position},
+    hazard::{SpikeCycle, SpikeField},
+};
+
+/// The directory custom levels are loaded from, relative to the working directory
+pub const LEVELS_DIR: &str = "levels";
+
+/// A custom arena layout
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name: String,
+    /// The tiles of the arena, top row first. `#` marks a tile that is not playable
+    /// and `^` a tile with spikes.
+    pub rows: Vec<String>,
+    #[serde(default)]
+    pub spike_cycle: SpikeCycle,
+}
+
+impl Level {
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// The arena this level is played on
+    pub fn arena(&self) -> ArenaConfig {
+        let rows: Vec<&str> = self.rows.iter().map(String::as_str).collect();
+        ArenaConfig::from_mask(ArenaMask::from_rows(&rows))
+    }
+
+    /// The spikes placed in this level
+    pub fn spikes(&self) -> SpikeField {
+        let height = self.rows.len() as i32;
+        let tiles = self
+            .rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, tiles)| {
+                tiles.chars().enumerate().filter_map(move |(x, tile)| {
+                    (tile == '^').then_some(Position {
+                        x: x as i32,
+                        y: height - 1 - row as i32,
+                    })
+                })
+            })
+            .collect();
+        SpikeField {
+            tiles,
+            cycle: self.spike_cycle,
+        }
+    }
+}
+
+/// The custom levels found at startup and the one being played
+#[derive(Resource, Default, Debug)]
+pub struct LevelLibrary {
+    pub levels: Vec<Level>,
+    /// Index into `levels` of the level being played, or `None` for the default arena
+    pub selected: Option<usize>,
+}
+
+impl LevelLibrary {
+    /// Loads every level file in `dir`, skipping any that fail to load
+    pub fn scan(dir: &Path) -> Self {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("could not read levels from {}: {}", dir.display(), err);
+                }
+                return Self::default();
+            }
+        };
+        let paths = entries.filter_map(|entry| entry.ok().map(|entry| entry.path()));
+        let levels = level_files(paths)
+            .into_iter()
+            .filter_map(|path| match load_level(&path) {
+                Ok(level) => Some(level),
+                Err(err) => {
+                    warn!("skipping level {}: {}", path.display(), err);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            levels,
+            selected: None,
+        }
+    }
+
+    /// Moves on to the next level, cycling back to the default arena after the last.
+    ///
+    /// Returns the arena to play on and its spikes.
+    pub fn cycle(&mut self) -> (ArenaConfig, SpikeField) {
+        self.selected = match self.selected {
+            None if !self.levels.is_empty() => Some(0),
+            Some(index) if index + 1 < self.levels.len() => Some(index + 1),
+            _ => None,
+        };
+        match self.current() {
+            Some(level) => (level.arena(), level.spikes()),
+            None => Default::default(),
+        }
+    }
+
+    pub fn current(&self) -> Option<&Level> {
+        self.selected.and_then(|index| self.levels.get(index))
+    }
+
+    /// The name of the level being played
+    pub fn current_name(&self) -> &str {
+        self.current()
+            .map_or("Classic", |level| level.name.as_str())
+    }
+}
+
+/// The level files among `paths`, sorted so the listing is stable
+fn level_files(paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn load_level(path: &Path) -> Result<Level, String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    Level::from_ron(&text).map_err(|err| err.to_string())
+}
+
+fn scan_levels(mut commands: Commands) {
+    commands.insert_resource(LevelLibrary::scan(Path::new(LEVELS_DIR)));
+}
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelLibrary>();
+        app.add_systems(PreStartup, scan_levels);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena;
+
+    #[test]
+    fn test_only_ron_files_are_listed() {
+        let paths = [
+            "levels/spiral.ron",
+            "levels/README.md",
+            "levels/donut.ron",
+            "levels/backup.ron.bak",
+            "levels/notes",
+        ]
+        .map(PathBuf::from);
+
+        assert_eq!(
+            level_files(paths),
+            [
+                PathBuf::from("levels/donut.ron"),
+                PathBuf::from("levels/spiral.ron")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_level_parses_into_masked_arena() {
+        let level = Level::from_ron(r#"(name: "Hole", rows: ["...", ".#.", "..."])"#).unwrap();
+        assert_eq!(level.name, "Hole");
+
+        let arena = level.arena();
+        assert_eq!((arena.width, arena.height), (3, 3));
+        assert!(!arena::in_bounds(Position { x: 1, y: 1 }, &arena));
+    }
+
+    #[test]
+    fn test_level_places_spikes_with_its_cycle() {
+        let level = Level::from_ron(
+            r#"(name: "Spiky", rows: ["^..", "..^"], spike_cycle: (lethal_ticks: 1, safe_ticks: 3))"#,
+        )
+        .unwrap();
+
+        let spikes = level.spikes();
+        assert_eq!(
+            spikes.tiles,
+            [Position { x: 0, y: 1 }, Position { x: 2, y: 0 }].into()
+        );
+        assert_eq!(spikes.cycle.lethal_ticks, 1);
+        assert!(arena::in_bounds(Position { x: 0, y: 1 }, &level.arena()));
+    }
+
+    #[test]
+    fn test_cycle_returns_to_default_arena() {
+        let level = Level {
+            name: "Tiny".into(),
+            rows: vec!["..".into()],
+            spike_cycle: SpikeCycle::default(),
+        };
+        let mut library = LevelLibrary {
+            levels: vec![level],
+            selected: None,
+        };
+
+        assert_eq!(library.cycle().0.width, 2);
+        assert_eq!(library.current_name(), "Tiny");
+        assert_eq!(library.cycle().0, ArenaConfig::default());
+        assert_eq!(library.current_name(), "Classic");
+    }
+
+    #[test]
+    fn test_missing_directory_gives_empty_library() {
+        let library = LevelLibrary::scan(Path::new("does/not/exist"));
+        assert!(library.levels.is_empty());
+    }
+}
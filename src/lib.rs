@@ -0,0 +1,59 @@
+//! A simple snake game built with Bevy
+//!
+//! The game is split into plugins that can be added to a Bevy `App`:
+//! - `snake` - The playable snake, its movement, growth and collisions
+//! - `food` - Spawning the food the snake eats
+//! - `ai` - Pathfinding toward food
+//! - `arena` - The playing field, its bounds and the mapping onto the window
+//! - `audio` - Sound effects
+//! - `camera` - The camera the game is viewed through
+//! - `challenge` - The daily challenge, seeded from the date
+//! - `config` - All of the game settings gathered in one place
+//! - `countdown` - The countdown held before each run starts
+//! - `debug` - Developer overlays
+//! - `effect` - Timing shared by transient visual effects
+//! - `grid` - A checkerboard behind the arena that can be toggled with G
+//! - `grow` - A mode where the arena grows as the player scores
+//! - `hazard` - Dangerous tiles such as spikes
+//! - `hud` - On-screen information drawn over the arena
+//! - `level` - Custom levels loaded from the `levels` directory
+//! - `menu` - The pause menu
+//! - `obstacle` - Procedurally generated walls
+//! - `replay` - The file format for recorded runs and saving them when a run ends
+//! - `shrink` - A survival mode where the arena closes in over time
+//! - `skin` - Optional textures for the snake and food
+//! - `score` - Scoring and the best score
+//! - `stats` - Statistics on each run, summed up when it ends
+//! - `telemetry` - A single event stream of everything that happens during play
+//! - `theme` - The color palette, following the system light/dark preference
+//! - `tile` - Custom behaviors for floor tiles the snake moves onto
+
+pub mod ai;
+pub mod arena;
+pub mod audio;
+pub mod camera;
+pub mod challenge;
+pub mod config;
+pub mod countdown;
+pub mod debug;
+pub mod effect;
+pub mod food;
+pub mod grid;
+pub mod grow;
+pub mod hazard;
+pub mod hud;
+pub mod level;
+pub mod menu;
+pub mod obstacle;
+pub mod replay;
+pub mod score;
+pub mod shrink;
+pub mod skin;
+pub mod snake;
+pub mod stats;
+pub mod telemetry;
+pub mod theme;
+pub mod tile;
+
+#[cfg(test)]
+mod testing;
@@ -1,20 +1,57 @@
+use std::env;
+
 use bevy::{
     prelude::*,
     window::{Window, WindowPlugin},
 };
 
-use food::FoodPlugin;
-use snake::SnakePlugin;
-
-mod snake;
-mod arena;
-mod food;
+use gametime::{
+    arena,
+    audio::SoundPlugin,
+    camera::CameraPlugin,
+    challenge::{ChallengeMode, ChallengePlugin},
+    countdown::CountdownPlugin,
+    debug::DebugPlugin,
+    food::{FoodCluster, FoodPlugin, FoodSettings, SpawnTimerBar},
+    grid::GridPlugin,
+    grow::{GrowPlugin, GrowingArena},
+    hazard::HazardPlugin,
+    hud::{HudPlugin, HudScale},
+    level::LevelPlugin,
+    menu::MenuPlugin,
+    obstacle::{self, ObstaclePlugin, WallEating},
+    replay::{ReplayAutoSave, ReplayPlugin},
+    score::ScorePlugin,
+    shrink::{ShrinkPlugin, ShrinkingArena},
+    skin::SkinPlugin,
+    snake::{BodyStyle, BodyTaper, FreeReverse, GameMode, MirrorControls, SnakePlugin},
+    stats::StatsPlugin,
+    theme::{BackgroundGradient, ColorScheme, PreferredColorScheme, ThemePlugin},
+};
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::srgb(0.04, 0.04, 0.04))) // Dark gray background
-        .add_systems(Startup, setup_camera)
-        .add_plugins((SnakePlugin, FoodPlugin))
+    let mut app = App::new();
+    let args: Vec<String> = env::args().collect();
+    insert_options(&mut app, &args);
+    app.init_resource::<arena::ArenaConfig>()
+        .add_systems(Update, (toggle_pause, arena::draw_holes))
+        .add_plugins((
+            CameraPlugin,
+            SnakePlugin,
+            FoodPlugin::default(),
+            (ScorePlugin, StatsPlugin),
+            SoundPlugin,
+            HudPlugin,
+            DebugPlugin,
+            MenuPlugin,
+            LevelPlugin,
+            HazardPlugin,
+            SkinPlugin,
+            (ThemePlugin, GridPlugin),
+            ChallengePlugin,
+            CountdownPlugin,
+            (ShrinkPlugin, GrowPlugin, ObstaclePlugin, ReplayPlugin),
+        ))
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Snake Game".into(),
@@ -27,6 +64,103 @@ fn main() {
         .run();
 }
 
-fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2d);
+/// Inserts the settings picked on the command line and through the environment. The
+/// plugins are added afterwards and leave these in place, only filling in defaults
+/// for the rest.
+fn insert_options(app: &mut App, args: &[String]) {
+    let scheme = args
+        .iter()
+        .find_map(|arg| match arg.as_str() {
+            "--light" => Some(ColorScheme::Light),
+            "--dark" => Some(ColorScheme::Dark),
+            _ => None,
+        })
+        .or_else(|| {
+            env::var("SLITHER_COLOR_SCHEME")
+                .ok()
+                .and_then(|value| ColorScheme::parse(&value))
+        });
+    app.insert_resource(PreferredColorScheme(scheme));
+    if let Some(scale) = env::var("SLITHER_UI_SCALE")
+        .ok()
+        .and_then(|value| HudScale::parse(&value))
+    {
+        app.insert_resource(scale);
+    }
+
+    let flag = |name: &str| args.iter().any(|arg| arg == name);
+    if flag("--daily") {
+        app.insert_resource(ChallengeMode::Daily);
+    }
+    if flag("--clusters") {
+        app.insert_resource(FoodSettings {
+            clusters: vec![FoodCluster::l_tromino(), FoodCluster::square()],
+            ..Default::default()
+        });
+    }
+    if flag("--shrink") {
+        app.insert_resource(ShrinkingArena {
+            enabled: true,
+            ..Default::default()
+        });
+    }
+    if flag("--taper") {
+        app.insert_resource(BodyTaper {
+            enabled: true,
+            ..Default::default()
+        });
+    }
+    // Procedural levels take the place of peaceful mode when both are asked for
+    if flag("--peaceful") {
+        app.insert_resource(GameMode::Peaceful);
+    }
+    if flag("--procedural") {
+        app.insert_resource(GameMode::Procedural {
+            density: obstacle::DEFAULT_DENSITY,
+        });
+    }
+    if flag("--eat-walls") {
+        app.insert_resource(WallEating { enabled: true });
+    }
+    if flag("--gradient") {
+        app.insert_resource(BackgroundGradient { enabled: true });
+    }
+    if flag("--dotted") {
+        app.insert_resource(BodyStyle::Dotted { every: 2 });
+    }
+    if flag("--free-reverse") {
+        app.insert_resource(FreeReverse { enabled: true });
+    }
+    if flag("--save-replays") {
+        app.insert_resource(ReplayAutoSave {
+            enabled: true,
+            ..Default::default()
+        });
+    }
+    if flag("--grow") {
+        app.insert_resource(GrowingArena {
+            enabled: true,
+            ..Default::default()
+        });
+    }
+    if flag("--spawn-timer") {
+        app.insert_resource(SpawnTimerBar { enabled: true });
+    }
+    if flag("--mirror") {
+        app.insert_resource(MirrorControls { enabled: true });
+    }
+}
+
+/// Pauses and resumes the game.
+///
+/// Gameplay timers run on virtual time, so pausing the virtual clock halts
+/// movement and food spawning together without them catching up on resume.
+fn toggle_pause(input: Res<ButtonInput<KeyCode>>, mut time: ResMut<Time<Virtual>>) {
+    if input.any_just_pressed([KeyCode::KeyP, KeyCode::Escape]) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
 }
@@ -0,0 +1,292 @@
+//! Menu module
+//!
+//! This module draws the pause menu shown while the game is paused.
+//!
+//! The options are listed top to bottom and navigated with the up and down keys,
+//! wrapping around at either end. The selected option is highlighted and a click
+//! plays each time the selection moves. Enter activates the selected option.
+//!
+//! The level option lists the custom level being played; activating it moves on to
+//! the next level in the `LevelLibrary` and starts a new run there.
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    arena::ArenaConfig, audio::AudioAssets, hazard::SpikeField, level::LevelLibrary,
+    snake::ResetGameEvent,
+};
+
+const OPTION_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
+const SELECTED_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+const OPTION_FONT_SIZE: f32 = 24.;
+const SELECTED_FONT_SIZE: f32 = 30.;
+
+/// The options of the pause menu, in the order they are listed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuOption {
+    Resume,
+    /// Switches to the next custom level and starts a new run on it
+    Level,
+    Quit,
+}
+
+impl MenuOption {
+    pub const ALL: [MenuOption; 3] = [MenuOption::Resume, MenuOption::Level, MenuOption::Quit];
+
+    pub fn label(self, levels: &LevelLibrary) -> String {
+        match self {
+            MenuOption::Resume => "Resume".to_string(),
+            MenuOption::Level => format!("Level: {}", levels.current_name()),
+            MenuOption::Quit => "Quit".to_string(),
+        }
+    }
+}
+
+/// The index of the highlighted option in `MenuOption::ALL`
+#[derive(Resource, Default, Debug)]
+pub struct PauseMenu {
+    pub selected: usize,
+}
+
+impl PauseMenu {
+    /// Moves the selection by `offset` options, wrapping around at either end
+    pub fn move_selection(&mut self, offset: isize) {
+        let count = MenuOption::ALL.len() as isize;
+        self.selected = (self.selected as isize + offset).rem_euclid(count) as usize;
+    }
+
+    pub fn option(&self) -> MenuOption {
+        MenuOption::ALL[self.selected]
+    }
+}
+
+/// Marks the root node of the pause menu
+#[derive(Component)]
+struct PauseMenuRoot;
+
+/// Marks the text of an option, holding its index in `MenuOption::ALL`
+#[derive(Component)]
+struct MenuItem(usize);
+
+/// Opens the menu when the game is paused and closes it when the game resumes
+fn show_menu(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    levels: Res<LevelLibrary>,
+    mut menu: ResMut<PauseMenu>,
+    roots: Query<Entity, With<PauseMenuRoot>>,
+) {
+    match (time.is_paused(), roots.get_single()) {
+        (true, Err(_)) => {
+            menu.selected = 0;
+            commands
+                .spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(8.),
+                        ..Default::default()
+                    },
+                    PauseMenuRoot,
+                ))
+                .with_children(|parent| {
+                    for (index, option) in MenuOption::ALL.iter().enumerate() {
+                        parent.spawn((
+                            Text::new(option.label(&levels)),
+                            TextFont {
+                                font_size: OPTION_FONT_SIZE,
+                                ..Default::default()
+                            },
+                            TextColor(OPTION_COLOR),
+                            MenuItem(index),
+                        ));
+                    }
+                });
+        }
+        (false, Ok(root)) => {
+            commands.entity(root).despawn_recursive();
+        }
+        _ => {}
+    }
+}
+
+/// Moves the selection with the up and down keys while paused, playing a click on
+/// every move
+fn navigate_menu(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time<Virtual>>,
+    audio: Option<Res<AudioAssets>>,
+    mut menu: ResMut<PauseMenu>,
+) {
+    if !time.is_paused() {
+        return;
+    }
+    let mut offset = 0;
+    if input.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        offset -= 1;
+    }
+    if input.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        offset += 1;
+    }
+    if offset == 0 {
+        return;
+    }
+    menu.move_selection(offset);
+    if let Some(sound) = audio.as_ref().and_then(|audio| audio.menu_navigate.clone()) {
+        commands.spawn((AudioPlayer::new(sound), PlaybackSettings::DESPAWN));
+    }
+}
+
+/// Recolors and enlarges the selected option, and keeps every label up to date
+fn highlight_selection(
+    menu: Res<PauseMenu>,
+    levels: Res<LevelLibrary>,
+    mut items: Query<(&MenuItem, &mut Text, &mut TextColor, &mut TextFont)>,
+) {
+    for (item, mut text, mut color, mut font) in items.iter_mut() {
+        let label = MenuOption::ALL[item.0].label(&levels);
+        if text.0 != label {
+            text.0 = label;
+        }
+        let selected = item.0 == menu.selected;
+        color.0 = if selected {
+            SELECTED_COLOR
+        } else {
+            OPTION_COLOR
+        };
+        font.font_size = if selected {
+            SELECTED_FONT_SIZE
+        } else {
+            OPTION_FONT_SIZE
+        };
+    }
+}
+
+/// Carries out the selected option when Enter is pressed while paused
+#[allow(clippy::too_many_arguments)]
+fn activate_selection(
+    input: Res<ButtonInput<KeyCode>>,
+    menu: Res<PauseMenu>,
+    mut time: ResMut<Time<Virtual>>,
+    mut levels: ResMut<LevelLibrary>,
+    mut arena: ResMut<ArenaConfig>,
+    mut spikes: ResMut<SpikeField>,
+    mut reset: EventWriter<ResetGameEvent>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if !time.is_paused() || !input.just_pressed(KeyCode::Enter) {
+        return;
+    }
+    match menu.option() {
+        MenuOption::Resume => time.unpause(),
+        MenuOption::Level => {
+            (*arena, *spikes) = levels.cycle();
+            // Starting over clears the board and respawns the snake on the new arena,
+            // without the abandoned run counting as a death
+            reset.send(ResetGameEvent);
+            time.unpause();
+        }
+        MenuOption::Quit => {
+            exit.send(AppExit::Success);
+        }
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenu>();
+        app.init_resource::<LevelLibrary>();
+        app.add_systems(
+            Update,
+            (
+                show_menu,
+                navigate_menu,
+                activate_selection,
+                highlight_selection,
+            )
+                .chain(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snake::GameOverEvent;
+
+    #[test]
+    fn test_navigation_wraps_around() {
+        let mut app = App::new();
+        let mut time = Time::<Virtual>::default();
+        time.pause();
+        app.insert_resource(time);
+        app.init_resource::<PauseMenu>();
+        app.init_resource::<LevelLibrary>();
+        app.add_systems(Update, navigate_menu);
+
+        let mut selections = Vec::new();
+        for key in [
+            KeyCode::ArrowUp,
+            KeyCode::ArrowDown,
+            KeyCode::ArrowDown,
+            KeyCode::KeyW,
+        ] {
+            let mut input = ButtonInput::<KeyCode>::default();
+            input.press(key);
+            app.insert_resource(input);
+            app.update();
+            selections.push(app.world().resource::<PauseMenu>().selected);
+        }
+
+        assert_eq!(selections, [2, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_navigation_ignored_while_playing() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.init_resource::<PauseMenu>();
+        app.init_resource::<LevelLibrary>();
+        let mut input = ButtonInput::<KeyCode>::default();
+        input.press(KeyCode::ArrowDown);
+        app.insert_resource(input);
+        app.add_systems(Update, navigate_menu);
+
+        app.update();
+
+        assert_eq!(app.world().resource::<PauseMenu>().selected, 0);
+    }
+
+    #[test]
+    fn test_switching_level_resets_without_a_game_over() {
+        let mut app = App::new();
+        let mut time = Time::<Virtual>::default();
+        time.pause();
+        app.insert_resource(time);
+        app.insert_resource(PauseMenu { selected: 1 });
+        app.init_resource::<LevelLibrary>();
+        app.init_resource::<ArenaConfig>();
+        app.init_resource::<SpikeField>();
+        app.add_event::<ResetGameEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<AppExit>();
+        let mut input = ButtonInput::<KeyCode>::default();
+        input.press(KeyCode::Enter);
+        app.insert_resource(input);
+        app.add_systems(Update, activate_selection);
+
+        app.update();
+
+        let world = app.world();
+        assert_eq!(world.resource::<Events<ResetGameEvent>>().len(), 1);
+        assert!(world.resource::<Events<GameOverEvent>>().is_empty());
+        assert!(!world.resource::<Time<Virtual>>().is_paused());
+    }
+}
@@ -0,0 +1,367 @@
+//! Obstacle module
+//!
+//! This module generates procedural levels for `GameMode::Procedural`, scattering
+//! walls across the arena so every board plays differently.
+//!
+//! Walls are placed at random until the requested density is reached, skipping any
+//! wall that would cut part of the board off from the snake. Every open tile can
+//! always be reached from where the snake starts, and a `StartCorridor` around the
+//! snake's starting tiles and the run in front of its head is always left open, so the
+//! snake has room for its first few moves.
+//!
+//! The layout is seeded from the food seed of the board, so retrying a board with
+//! `BoardRestart::SameBoard` or playing the daily challenge gives the same walls, and
+//! a new board gives new ones. Pass `--procedural` on the command line to play it.
+//!
+//! With `WallEating` enabled, or the `--eat-walls` flag, every piece of food eaten
+//! also opens the wall tile nearest to it, gradually opening up a walled board. This
+//! works on any walled arena, procedural or loaded from a level, and the walls are put
+//! back at the start of the next run.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    arena::{self, ArenaConfig, ArenaMask, Position},
+    food::{FoodRng, FoodSet},
+    snake::{FoodEatenEvent, GameMode, GameOverEvent, ResetGameEvent, SnakeSet, SnakeStart},
+};
+
+/// The wall density used by `--procedural`, in percent of the board
+pub const DEFAULT_DENSITY: u32 = 15;
+
+/// The most of the board that is ever walled off, in percent
+const MAX_DENSITY: u32 = 50;
+
+/// The open corridor kept around the snake's starting tiles on a procedural board
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StartCorridor {
+    /// The width of the corridor in tiles, centered on the snake where it can be
+    pub width: i32,
+    /// How many tiles the corridor runs on ahead of the starting head
+    pub length: i32,
+}
+
+impl Default for StartCorridor {
+    fn default() -> Self {
+        Self {
+            width: 3,
+            length: 3,
+        }
+    }
+}
+
+/// The tiles of the arena that can be reached from `from` without leaving it
+pub fn reachable_tiles(arena: &ArenaConfig, from: Position) -> HashSet<Position> {
+    let mut reached = HashSet::new();
+    if !arena::in_bounds(from, arena) {
+        return reached;
+    }
+    let mut frontier = vec![from];
+    reached.insert(from);
+    while let Some(tile) = frontier.pop() {
+        for offset in [
+            Position { x: 1, y: 0 },
+            Position { x: -1, y: 0 },
+            Position { x: 0, y: 1 },
+            Position { x: 0, y: -1 },
+        ] {
+            let next = tile + offset;
+            if arena::in_bounds(next, arena) && reached.insert(next) {
+                frontier.push(next);
+            }
+        }
+    }
+    reached
+}
+
+/// Scatters walls over a `width` by `height` board, walling off `density` percent of
+/// it, capped at `MAX_DENSITY`.
+///
+/// The tiles in `keep_open` are never walled and the first of them is where every
+/// open tile stays reachable from. A wall that would cut tiles off is skipped, so a
+/// crowded board can end up with fewer walls than asked for.
+pub fn generate_obstacles(
+    width: i32,
+    height: i32,
+    keep_open: &[Position],
+    density: u32,
+    rng: &mut impl Rng,
+) -> ArenaMask {
+    let mut candidates: Vec<Position> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| Position { x, y }))
+        .filter(|tile| !keep_open.contains(tile))
+        .collect();
+    let target = (width * height).max(0) as usize * density.min(MAX_DENSITY) as usize / 100;
+    let Some(&from) = keep_open.first() else {
+        return ArenaMask::from_walls(width, height, &[]);
+    };
+    candidates.shuffle(rng);
+
+    let mut walls = Vec::new();
+    for tile in candidates {
+        if walls.len() >= target {
+            break;
+        }
+        walls.push(tile);
+        let arena = ArenaConfig::from_mask(ArenaMask::from_walls(width, height, &walls));
+        let open = (width * height) as usize - walls.len();
+        if reachable_tiles(&arena, from).len() != open {
+            walls.pop();
+        }
+    }
+    ArenaMask::from_walls(width, height, &walls)
+}
+
+/// The tiles the snake starts on and the run ahead of its head, widened into
+/// `corridor`. The snake's own line comes first, starting from the tail.
+fn start_tiles(start: &SnakeStart, corridor: &StartCorridor) -> Vec<Position> {
+    let ahead = start.direction.offset();
+    let side = Position {
+        x: ahead.y,
+        y: ahead.x,
+    };
+    let width = corridor.width.max(1);
+    let mut lanes: Vec<i32> = (-(width - 1) / 2..=width / 2).collect();
+    lanes.sort_by_key(|lane| lane.abs());
+    lanes
+        .into_iter()
+        .flat_map(|lane| {
+            (1 - start.length.max(1) as i32..=corridor.length.max(0)).map(move |i| {
+                start.head
+                    + Position {
+                        x: ahead.x * i + side.x * lane,
+                        y: ahead.y * i + side.y * lane,
+                    }
+            })
+        })
+        .collect()
+}
+
+/// Whether eating food opens the nearest wall tile. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct WallEating {
+    pub enabled: bool,
+}
+
+/// The wall tiles opened by eating during the current run, to be put back when it ends
+#[derive(Resource, Default, Debug)]
+struct OpenedWalls(Vec<Position>);
+
+/// The wall tile of `mask` closest to `tile`, ties going to the first in row-major
+/// order
+fn nearest_wall(mask: &ArenaMask, tile: Position) -> Option<Position> {
+    mask.walls()
+        .min_by_key(|wall| (wall.x - tile.x).abs() + (wall.y - tile.y).abs())
+}
+
+/// Opens the wall nearest to each piece of food eaten
+fn eat_walls(
+    settings: Res<WallEating>,
+    mut eaten: EventReader<FoodEatenEvent>,
+    mut arena: ResMut<ArenaConfig>,
+    mut opened: ResMut<OpenedWalls>,
+) {
+    if !settings.enabled {
+        eaten.clear();
+        return;
+    }
+    for food in eaten.read() {
+        let Some(mask) = arena.mask.as_mut() else {
+            return;
+        };
+        if let Some(wall) = nearest_wall(mask, food.position) {
+            mask.set_playable(wall, true);
+            opened.0.push(wall);
+        }
+    }
+}
+
+/// Puts back the walls opened during a run once it ends
+fn restore_walls(
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+    mut arena: ResMut<ArenaConfig>,
+    mut opened: ResMut<OpenedWalls>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if !died && !reset || opened.0.is_empty() {
+        return;
+    }
+    let walls: Vec<Position> = opened.0.drain(..).collect();
+    if let Some(mask) = arena.mask.as_mut() {
+        for wall in walls {
+            mask.set_playable(wall, false);
+        }
+    }
+}
+
+/// Lays out new walls whenever a procedural board starts on a new food seed
+fn generate_level(
+    mode: Res<GameMode>,
+    rng: Res<FoodRng>,
+    start: Res<SnakeStart>,
+    corridor: Res<StartCorridor>,
+    mut arena: ResMut<ArenaConfig>,
+    mut generated_for: Local<Option<u64>>,
+) {
+    let GameMode::Procedural { density } = *mode else {
+        return;
+    };
+    if *generated_for == Some(rng.seed()) && !mode.is_changed() {
+        return;
+    }
+    *generated_for = Some(rng.seed());
+    let mut layout = StdRng::seed_from_u64(rng.seed());
+    let mask = generate_obstacles(
+        arena.width,
+        arena.height,
+        &start_tiles(&start, &corridor),
+        density,
+        &mut layout,
+    );
+    arena.mask = Some(mask);
+}
+
+pub struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StartCorridor>();
+        app.init_resource::<WallEating>();
+        app.init_resource::<OpenedWalls>();
+        app.init_resource::<GameMode>();
+        app.init_resource::<FoodRng>();
+        app.init_resource::<SnakeStart>();
+        app.init_resource::<ArenaConfig>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        // The walls are in place before the starting food of the board is placed. The
+        // opened walls are put back before a new layout can replace them.
+        app.add_systems(
+            Update,
+            (eat_walls, restore_walls, generate_level)
+                .chain()
+                .after(SnakeSet::Collision)
+                .after(FoodSet::Restart)
+                .before(FoodSet::Spawn),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{arena::Direction, food::FoodKind, snake::PlayerId};
+
+    #[test]
+    fn test_generated_level_stays_connected() {
+        let start = SnakeStart::default();
+        let keep_open = start_tiles(&start, &StartCorridor::default());
+        for seed in 0..20 {
+            let mask = generate_obstacles(10, 10, &keep_open, 30, &mut StdRng::seed_from_u64(seed));
+            let arena = ArenaConfig::from_mask(mask);
+            let open = arena::free_tiles(&arena, &[]);
+            let reachable = reachable_tiles(&arena, start.head);
+
+            assert_eq!(reachable.len(), open.len(), "seed {} cut tiles off", seed);
+            assert!(open.len() >= 70, "seed {} left too little room", seed);
+            assert!(keep_open.iter().all(|tile| reachable.contains(tile)));
+        }
+    }
+
+    #[test]
+    fn test_start_corridor_is_never_walled() {
+        let start = SnakeStart {
+            head: Position { x: 5, y: 2 },
+            direction: Direction::Right,
+            length: 3,
+        };
+        let corridor = StartCorridor {
+            width: 3,
+            length: 2,
+        };
+        let keep_open = start_tiles(&start, &corridor);
+        assert_eq!(keep_open.len(), 15);
+        assert_eq!(keep_open[0], Position { x: 3, y: 2 });
+
+        for seed in 0..50 {
+            let mask = generate_obstacles(12, 12, &keep_open, 50, &mut StdRng::seed_from_u64(seed));
+            let arena = ArenaConfig::from_mask(mask);
+            let reachable = reachable_tiles(&arena, start.head);
+
+            for x in 3..=7 {
+                for y in 1..=3 {
+                    let tile = Position { x, y };
+                    assert!(reachable.contains(&tile), "seed {} walled {:?}", seed, tile);
+                }
+            }
+            assert!(reachable.len() >= 72, "seed {} left too little room", seed);
+        }
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_level() {
+        let keep_open = start_tiles(&SnakeStart::default(), &StartCorridor::default());
+        let level =
+            |seed| generate_obstacles(10, 10, &keep_open, 20, &mut StdRng::seed_from_u64(seed));
+
+        assert_eq!(level(3), level(3));
+        assert_ne!(level(3), level(4));
+    }
+
+    #[test]
+    fn test_each_eat_opens_a_wall() {
+        let mut app = App::new();
+        app.insert_resource(WallEating { enabled: true });
+        app.insert_resource(ArenaConfig::from_mask(ArenaMask::from_rows(&[
+            "#....", ".....", "..#..", ".....", "....#",
+        ])));
+        app.add_plugins(ObstaclePlugin);
+        let walls = |app: &App| -> Vec<Position> {
+            let arena = app.world().resource::<ArenaConfig>();
+            arena.mask.as_ref().unwrap().walls().collect()
+        };
+        let eat = |app: &mut App, x, y| {
+            app.world_mut().send_event(FoodEatenEvent {
+                position: Position { x, y },
+                kind: FoodKind::Normal,
+                player: PlayerId::default(),
+            });
+            app.update();
+        };
+
+        let mut remaining = Vec::new();
+        for (x, y) in [(1, 2), (3, 0), (0, 3), (0, 0)] {
+            eat(&mut app, x, y);
+            remaining.push(walls(&app).len());
+        }
+        assert_eq!(remaining, [2, 1, 0, 0]);
+
+        // The walls are back for the next run
+        app.world_mut().send_event(GameOverEvent);
+        app.update();
+        assert_eq!(walls(&app).len(), 3);
+    }
+
+    #[test]
+    fn test_procedural_mode_walls_off_arena() {
+        let mut app = App::new();
+        app.insert_resource(GameMode::Procedural { density: 20 });
+        app.insert_resource(FoodRng::from_seed(9));
+        app.add_plugins(ObstaclePlugin);
+        app.update();
+
+        let arena = app.world().resource::<ArenaConfig>().clone();
+        assert_eq!(arena::free_tiles(&arena, &[]).len(), 80);
+        assert!(
+            start_tiles(&SnakeStart::default(), &StartCorridor::default())
+                .iter()
+                .all(|tile| arena::in_bounds(*tile, &arena))
+        );
+    }
+}
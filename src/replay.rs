@@ -0,0 +1,493 @@
+//! Replay module
+//!
+//! This module defines the on-disk format used to store and share replays.
+//!
+//! A replay file is plain text. The first line is a header made of the magic string
+//! `slither-replay` and the format version, for example `slither-replay 1`. The rest
+//! of the file is the replay itself encoded as RON.
+//!
+//! Files written by older versions of the format are accepted, while files from a
+//! newer, unknown version are rejected with `ReplayError::UnsupportedVersion` rather
+//! than being misread.
+//!
+//! Version 2 added the shape of the arena, its walls and its shrunken rings, to the
+//! arena dimensions version 1 recorded.
+//!
+//! `ReplayPlugin` records the turns of every run. With `ReplayAutoSave` enabled, or
+//! the `--save-replays` command line flag, the replay of each run that ends in a death
+//! is written to the `replays` directory, named after the time of death and its cause,
+//! for example `1718000000123-body.replay`.
+
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::{ArenaConfig, ArenaMask, Direction, Position},
+    food::FoodRng,
+    snake::{
+        GameOverEvent, LifeLostEvent, MoveCount, ResetGameEvent, SnakeSet, SnakeStart, TickEvent,
+    },
+    telemetry::{DeathCause, GameEvent},
+};
+
+/// The directory replays are saved to, relative to the working directory
+pub const REPLAYS_DIR: &str = "replays";
+
+/// Identifies a file as a replay
+pub const REPLAY_MAGIC: &str = "slither-replay";
+
+/// The newest replay format version this build can read and the one it writes
+pub const REPLAY_VERSION: u32 = 2;
+
+/// Everything needed to play back a run
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Replay {
+    /// The seed used for the random number generator during the run
+    pub seed: u64,
+    pub arena: ReplayArena,
+    /// Turns made during the run, in order
+    pub inputs: Vec<ReplayInput>,
+    /// What ended the run, if it ended in a death
+    #[serde(default)]
+    pub death: Option<DeathCause>,
+}
+
+/// The arena the run was played on
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReplayArena {
+    pub width: i32,
+    pub height: i32,
+    /// The tiles cut out of a shaped arena, such as the walls of a custom level
+    #[serde(default)]
+    pub walls: Vec<Position>,
+    /// The outer rings of tiles cut off the arena
+    #[serde(default)]
+    pub inset: i32,
+}
+
+impl From<&ArenaConfig> for ReplayArena {
+    fn from(arena: &ArenaConfig) -> Self {
+        Self {
+            width: arena.width,
+            height: arena.height,
+            walls: arena
+                .mask
+                .as_ref()
+                .map_or(Vec::new(), |mask| mask.walls().collect()),
+            inset: arena.inset,
+        }
+    }
+}
+
+impl From<&ReplayArena> for ArenaConfig {
+    fn from(arena: &ReplayArena) -> Self {
+        let mask = (!arena.walls.is_empty())
+            .then(|| ArenaMask::from_walls(arena.width, arena.height, &arena.walls));
+        Self {
+            width: arena.width,
+            height: arena.height,
+            mask,
+            inset: arena.inset,
+            ..Default::default()
+        }
+    }
+}
+
+/// A direction change applied on the given movement tick
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub direction: Direction,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The text does not start with a replay header
+    MissingHeader,
+    /// The replay was written in a format version this build does not understand
+    UnsupportedVersion(u32),
+    /// The replay body could not be encoded or decoded
+    Malformed(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::MissingHeader => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "replay format version {} is not supported, the newest supported version is {}",
+                version, REPLAY_VERSION
+            ),
+            ReplayError::Malformed(reason) => write!(f, "malformed replay: {}", reason),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+/// Whether the replay of every run that ends in a death is saved, and where. Off by
+/// default.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct ReplayAutoSave {
+    pub enabled: bool,
+    pub dir: PathBuf,
+}
+
+impl Default for ReplayAutoSave {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from(REPLAYS_DIR),
+        }
+    }
+}
+
+/// The turns of the run in progress
+#[derive(Resource, Default, Debug)]
+struct ReplayRecorder {
+    /// The `MoveCount` total when the run started, as replay ticks count from the
+    /// start of the run
+    start_tick: u64,
+    /// The direction the snake last moved in, or `None` before its first move
+    heading: Option<Direction>,
+    inputs: Vec<ReplayInput>,
+    death: Option<DeathCause>,
+}
+
+/// Notes each change of direction the snake moved in, along with what killed it. A
+/// snake that loses a life starts over facing its start direction.
+///
+/// Every movement step is checked on its own, so turns taken on steps that share a
+/// frame are all recorded.
+fn record_inputs(
+    start: Res<SnakeStart>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut ticks: EventReader<TickEvent>,
+    mut events: EventReader<GameEvent>,
+    mut life_lost: EventReader<LifeLostEvent>,
+) {
+    if life_lost.read().count() > 0 {
+        recorder.heading = None;
+    }
+    for event in events.read() {
+        if let GameEvent::Died { cause } = event {
+            recorder.death = Some(*cause);
+        }
+    }
+    for &TickEvent { tick, direction } in ticks.read() {
+        if direction != recorder.heading.unwrap_or(start.direction) {
+            let tick = tick - recorder.start_tick;
+            recorder.inputs.push(ReplayInput { tick, direction });
+        }
+        recorder.heading = Some(direction);
+    }
+}
+
+/// The name of the file a replay is saved to, from the time it was saved in
+/// milliseconds since the Unix epoch and what ended the run
+fn replay_file_name(millis: u128, death: Option<DeathCause>) -> String {
+    let cause = death.map_or("end", DeathCause::name);
+    format!("{}-{}.replay", millis, cause)
+}
+
+/// Writes `replay` to a new timestamped file in `dir`, returning its path
+pub fn save_replay(replay: &Replay, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    fs::create_dir_all(dir)?;
+    let path = dir.join(replay_file_name(millis, replay.death));
+    fs::write(&path, replay.to_text()?)?;
+    Ok(path)
+}
+
+/// Saves the replay of a run that ended in a death, then starts recording the next
+fn finish_recording(
+    settings: Res<ReplayAutoSave>,
+    arena: Res<ArenaConfig>,
+    rng: Res<FoodRng>,
+    moves: Res<MoveCount>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if !died && !reset {
+        return;
+    }
+    let recorded = std::mem::take(&mut *recorder);
+    recorder.start_tick = moves.total();
+    if !settings.enabled || recorded.death.is_none() {
+        return;
+    }
+    let replay = Replay {
+        seed: rng.seed(),
+        arena: ReplayArena::from(&*arena),
+        inputs: recorded.inputs,
+        death: recorded.death,
+    };
+    if let Err(err) = save_replay(&replay, &settings.dir) {
+        warn!("could not save the replay: {}", err);
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayAutoSave>();
+        app.init_resource::<ReplayRecorder>();
+        app.init_resource::<ArenaConfig>();
+        app.init_resource::<FoodRng>();
+        app.init_resource::<MoveCount>();
+        app.init_resource::<SnakeStart>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_event::<TickEvent>();
+        app.add_event::<LifeLostEvent>();
+        app.add_event::<GameEvent>();
+        // Saved before the board moves on to the seed of the next run
+        app.add_systems(
+            Update,
+            (record_inputs, finish_recording)
+                .chain()
+                .after(SnakeSet::Movement)
+                .before(SnakeSet::Collision),
+        );
+    }
+}
+
+impl Replay {
+    /// Encodes the replay in the current format version
+    pub fn to_text(&self) -> Result<String, ReplayError> {
+        let body = ron::to_string(self).map_err(|err| ReplayError::Malformed(err.to_string()))?;
+        Ok(format!("{} {}\n{}\n", REPLAY_MAGIC, REPLAY_VERSION, body))
+    }
+
+    /// Decodes a replay, checking the header before reading the body
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let (header, body) = text.split_once('\n').unwrap_or((text, ""));
+        let version = header
+            .strip_prefix(REPLAY_MAGIC)
+            .and_then(|version| version.trim().parse::<u32>().ok())
+            .ok_or(ReplayError::MissingHeader)?;
+        if version == 0 || version > REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        ron::from_str(body).map_err(|err| ReplayError::Malformed(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        snake::{Lives, MovementTimer, TurnTiming},
+        testing::TestGame,
+    };
+
+    fn sample() -> Replay {
+        Replay {
+            seed: 42,
+            arena: ReplayArena::from(&ArenaConfig::default()),
+            inputs: vec![
+                ReplayInput {
+                    tick: 3,
+                    direction: Direction::Left,
+                },
+                ReplayInput {
+                    tick: 7,
+                    direction: Direction::Down,
+                },
+            ],
+            death: None,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = sample().to_text().unwrap();
+        assert!(text.starts_with("slither-replay 2\n"));
+        assert_eq!(Replay::from_text(&text).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let text = sample()
+            .to_text()
+            .unwrap()
+            .replacen("slither-replay 2", "slither-replay 3", 1);
+        assert!(matches!(
+            Replay::from_text(&text),
+            Err(ReplayError::UnsupportedVersion(3))
+        ));
+    }
+
+    #[test]
+    fn test_missing_header_is_rejected() {
+        assert!(matches!(
+            Replay::from_text("(seed: 1)"),
+            Err(ReplayError::MissingHeader)
+        ));
+        assert!(matches!(
+            Replay::from_text("slither-replay one\n(seed: 1)"),
+            Err(ReplayError::MissingHeader)
+        ));
+    }
+
+    #[test]
+    fn test_replay_without_death_still_loads() {
+        let text = "slither-replay 1\n(seed: 1, arena: (width: 5, height: 5), inputs: [])";
+        assert_eq!(Replay::from_text(text).unwrap().death, None);
+    }
+
+    #[test]
+    fn test_shaped_arena_round_trips() {
+        let arena = ArenaConfig {
+            inset: 1,
+            ..ArenaConfig::from_mask(ArenaMask::from_rows(&["...", ".#.", "..."]))
+        };
+        let replay = Replay {
+            arena: ReplayArena::from(&arena),
+            ..sample()
+        };
+        let loaded = Replay::from_text(&replay.to_text().unwrap()).unwrap();
+        assert_eq!(loaded.arena.walls, [Position { x: 1, y: 1 }]);
+        assert_eq!(ArenaConfig::from(&loaded.arena), arena);
+    }
+
+    /// Plays a game that saves its replay, returning the replay of the run that ended
+    fn saved_replay(game: &mut TestGame, seed: u64, play: impl FnOnce(&mut TestGame)) -> Replay {
+        let dir = env::temp_dir().join(format!("slither-replays-{}-{}", std::process::id(), seed));
+        let _ = fs::remove_dir_all(&dir);
+        game.app.insert_resource(ReplayAutoSave {
+            enabled: true,
+            dir: dir.clone(),
+        });
+        game.app.insert_resource(FoodRng::from_seed(seed));
+        game.app.add_plugins(ReplayPlugin);
+
+        play(game);
+
+        let files: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        let replay = Replay::from_text(&fs::read_to_string(&files[0]).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        replay
+    }
+
+    #[test]
+    fn test_turn_after_losing_a_life_is_recorded() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(Lives::new(2));
+        let replay = saved_replay(&mut game, 12, |game| {
+            // Right into the wall, then right again as soon as the snake is back
+            game.turn(Direction::Right).advance(7);
+            game.turn(Direction::Right).advance(7);
+        });
+
+        assert_eq!(replay.death, Some(DeathCause::Wall));
+        let turns: Vec<Direction> = replay.inputs.iter().map(|input| input.direction).collect();
+        assert_eq!(turns, [Direction::Right, Direction::Right]);
+    }
+
+    #[test]
+    fn test_turns_on_steps_sharing_a_frame_are_recorded() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(TurnTiming::Queued);
+        let replay = saved_replay(&mut game, 13, |game| {
+            // Right then up, queued on frames that take no step
+            for key in [KeyCode::ArrowRight, KeyCode::ArrowUp] {
+                let mut input = ButtonInput::<KeyCode>::default();
+                input.press(key);
+                game.app.insert_resource(input);
+                game.app.update();
+            }
+            game.app.insert_resource(ButtonInput::<KeyCode>::default());
+            // A long frame that takes both steps at once
+            let interval = game.app.world().resource::<MovementTimer>().interval();
+            game.app
+                .world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(interval * 2);
+            game.app.update();
+            game.turn(Direction::Right).advance(7);
+        });
+
+        assert_eq!(
+            replay.inputs,
+            [
+                ReplayInput {
+                    tick: 1,
+                    direction: Direction::Right
+                },
+                ReplayInput {
+                    tick: 2,
+                    direction: Direction::Up
+                },
+                ReplayInput {
+                    tick: 3,
+                    direction: Direction::Right
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_death_saves_loadable_replay() {
+        let dir = env::temp_dir().join(format!("slither-replays-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut game = TestGame::new();
+        game.app.insert_resource(ReplayAutoSave {
+            enabled: true,
+            dir: dir.clone(),
+        });
+        game.app.insert_resource(FoodRng::from_seed(11));
+        game.app.add_plugins(ReplayPlugin);
+
+        // Two ticks up, then right into the wall
+        game.advance(2);
+        game.turn(Direction::Right).advance(7);
+
+        let files: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].to_string_lossy().ends_with("-wall.replay"));
+        let replay = Replay::from_text(&fs::read_to_string(&files[0]).unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(replay.seed, 11);
+        assert_eq!(replay.death, Some(DeathCause::Wall));
+        assert_eq!(
+            replay.inputs,
+            [ReplayInput {
+                tick: 3,
+                direction: Direction::Right
+            }]
+        );
+    }
+
+    #[test]
+    fn test_malformed_body_is_rejected() {
+        assert!(matches!(
+            Replay::from_text("slither-replay 1\n(seed: \"x\")"),
+            Err(ReplayError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,666 @@
+//! Score module
+//!
+//! This module keeps track of the score for the current run and the best score
+//! seen so far.
+//!
+//! Each piece of food eaten is worth the points given by the `ScoringConfig`, by
+//! default the points of its `FoodKind`, shown in a popup that floats up from where
+//! the food was. When a run ends the score is compared against the best, and a short
+//! celebration is shown if a new record was set.
+//!
+//! Alongside the total, `PlayerScores` credits the points of each piece to the player
+//! whose snake ate it.
+//!
+//! To discourage stalling, `ScoreDecay` can take points away for every movement tick
+//! the snake goes without eating, once a grace period has passed. The score never
+//! drops below zero, and the idle count starts over with every piece eaten.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    arena::{self, ArenaConfig, Position},
+    effect::{EffectPlugin, TimedEffect},
+    food::FoodKind,
+    snake::{
+        FoodEatenEvent, GameOverEvent, MoveCount, PlayClock, PlayerId, ResetGameEvent,
+        SnakeSegments, SnakeSet, TickEvent,
+    },
+    telemetry::GameEvent,
+};
+
+const CELEBRATION_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+const CELEBRATION_SECS: f32 = 2.0;
+const POPUP_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+/// Keeps popups drawn above the snake and food
+const POPUP_Z: f32 = 10.;
+
+/// The score of the run in progress and the best score reached in any run
+#[derive(Resource, Default, Debug)]
+pub struct Score {
+    pub current: u32,
+    pub best: u32,
+}
+
+impl Score {
+    /// Ends the current run, updating the best score.
+    ///
+    /// Returns true only if the run beat the previous best; matching it is not a record.
+    pub fn finish_run(&mut self) -> bool {
+        let record = self.current > self.best;
+        if record {
+            self.best = self.current;
+        }
+        self.current = 0;
+        record
+    }
+}
+
+/// The score of each player in the run in progress, while `Score::current` keeps the
+/// total of them all
+#[derive(Resource, Default, Debug)]
+pub struct PlayerScores(Vec<(PlayerId, u32)>);
+
+impl PlayerScores {
+    /// The score of `player`, zero until they have scored
+    pub fn of(&self, player: PlayerId) -> u32 {
+        self.0
+            .iter()
+            .find(|(scored, _)| *scored == player)
+            .map_or(0, |(_, points)| *points)
+    }
+
+    fn add(&mut self, player: PlayerId, points: u32) {
+        match self.0.iter_mut().find(|(scored, _)| *scored == player) {
+            Some((_, total)) => *total += points,
+            None => self.0.push((player, points)),
+        }
+    }
+
+    /// Takes `points` away from every player, stopping at zero
+    fn decay(&mut self, points: u32) {
+        for (_, total) in self.0.iter_mut() {
+            *total = total.saturating_sub(points);
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Marks the "new high score" message
+#[derive(Component)]
+struct Celebration;
+
+/// Everything a scoring formula can base the points for a piece of food on
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EatContext {
+    pub kind: FoodKind,
+    /// The length of the snake when it ate, head included
+    pub length: usize,
+    /// How many pieces have been eaten in a row, this one included, each within
+    /// `ScoringConfig::combo_window` ticks of the last
+    pub combo: u32,
+    /// Play time since the run started
+    pub elapsed: Duration,
+}
+
+/// How the points for a piece of food are worked out
+#[derive(Clone, Copy, Debug, Default)]
+pub enum ScoringFormula {
+    /// The points of the food's `FoodKind`
+    #[default]
+    FoodValue,
+    /// `per_segment` points for every tile of the snake's length
+    LengthScaled { per_segment: u32 },
+    /// The points of the food's `FoodKind` multiplied by the combo
+    Combo,
+    /// Points worked out by a function of the player's choosing
+    Custom(fn(&EatContext) -> u32),
+}
+
+/// The scoring rules of a run
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ScoringConfig {
+    pub formula: ScoringFormula,
+    /// The most movement ticks allowed between two pieces of food for the combo to
+    /// carry on
+    pub combo_window: u64,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            formula: ScoringFormula::default(),
+            combo_window: 10,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// The points awarded for eating a piece of food
+    pub fn points(&self, eat: &EatContext) -> u32 {
+        match self.formula {
+            ScoringFormula::FoodValue => eat.kind.points(),
+            ScoringFormula::LengthScaled { per_segment } => eat.length as u32 * per_segment,
+            ScoringFormula::Combo => eat.kind.points() * eat.combo,
+            ScoringFormula::Custom(formula) => formula(eat),
+        }
+    }
+}
+
+/// The current run of food eaten in quick succession
+#[derive(Resource, Default, Debug)]
+pub(crate) struct Combo {
+    count: u32,
+    last_tick: u64,
+    /// Movement ticks since the last piece of food was eaten, or since the run started
+    idle: u64,
+}
+
+/// Takes points away while the snake goes without eating. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScoreDecay {
+    pub enabled: bool,
+    /// Points lost for every idle tick past the grace period
+    pub per_tick: u32,
+    /// How many ticks the snake can go without eating before the score decays
+    pub grace_ticks: u64,
+}
+
+impl Default for ScoreDecay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            per_tick: 1,
+            grace_ticks: 20,
+        }
+    }
+}
+
+/// Sent for every piece of food eaten, with the points it was worth
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ScoreEvent {
+    /// The tile the food was anchored on
+    pub position: Position,
+    pub points: u32,
+    pub player: PlayerId,
+    /// How many pieces were eaten in a row up to this one
+    pub combo: u32,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn count_points(
+    mut score: ResMut<Score>,
+    config: Res<ScoringConfig>,
+    mut combo: ResMut<Combo>,
+    mut players: ResMut<PlayerScores>,
+    moves: Option<Res<MoveCount>>,
+    clock: Option<Res<PlayClock>>,
+    segments: Option<Res<SnakeSegments>>,
+    mut eaten: EventReader<FoodEatenEvent>,
+    mut scored: EventWriter<ScoreEvent>,
+    mut events: EventWriter<GameEvent>,
+) {
+    let tick = moves.map_or(0, |moves| moves.total());
+    for food in eaten.read() {
+        let gap = tick.checked_sub(combo.last_tick);
+        if combo.count > 0 && gap.is_some_and(|gap| gap <= config.combo_window) {
+            combo.count += 1;
+        } else {
+            combo.count = 1;
+        }
+        combo.last_tick = tick;
+        let points = config.points(&EatContext {
+            kind: food.kind,
+            length: segments.as_ref().map_or(0, |segments| segments.len()),
+            combo: combo.count,
+            elapsed: clock
+                .as_ref()
+                .map_or(Duration::ZERO, |clock| clock.elapsed()),
+        });
+        score.current += points;
+        players.add(food.player, points);
+        scored.send(ScoreEvent {
+            position: food.position,
+            points,
+            player: food.player,
+            combo: combo.count,
+        });
+        events.send(GameEvent::ScoreChanged {
+            total: score.current,
+        });
+    }
+}
+
+/// How the points popup shown when food is eaten looks and behaves
+#[derive(Resource, Clone, Debug)]
+pub struct PointsPopup {
+    pub enabled: bool,
+    /// Seconds the popup stays on screen
+    pub lifetime: f32,
+    /// Distance in pixels the popup drifts up over its lifetime
+    pub rise: f32,
+}
+
+impl Default for PointsPopup {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lifetime: 0.8,
+            rise: 30.,
+        }
+    }
+}
+
+/// A floating "+N" label, remembering where it started so it can drift up from there
+#[derive(Component)]
+struct Popup {
+    origin: Vec2,
+}
+
+fn spawn_popups(
+    mut commands: Commands,
+    settings: Res<PointsPopup>,
+    arena: Res<ArenaConfig>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut scored: EventReader<ScoreEvent>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    for scored in scored.read() {
+        if !settings.enabled {
+            continue;
+        }
+        let origin = arena::grid_to_world(scored.position, window.size(), &arena);
+        commands.spawn((
+            Text2d::new(format!("+{}", scored.points)),
+            TextFont {
+                font_size: 18.,
+                ..Default::default()
+            },
+            TextColor(POPUP_COLOR),
+            Transform::from_translation(origin.extend(POPUP_Z)),
+            Popup { origin },
+            TimedEffect::from_seconds(settings.lifetime),
+        ));
+    }
+}
+
+/// Drifts popups up while fading them out, then despawns them
+fn animate_popups(
+    mut commands: Commands,
+    settings: Res<PointsPopup>,
+    mut popups: Query<(Entity, &Popup, &TimedEffect, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, popup, effect, mut transform, mut color) in popups.iter_mut() {
+        if effect.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = effect.fraction();
+        transform.translation.y = popup.origin.y + settings.rise * progress;
+        color.0 = POPUP_COLOR.with_alpha(1. - progress);
+    }
+}
+
+/// Counts the ticks since the snake last ate and decays the score once the grace
+/// period is over
+fn decay_score(
+    decay: Res<ScoreDecay>,
+    mut score: ResMut<Score>,
+    mut players: ResMut<PlayerScores>,
+    mut combo: ResMut<Combo>,
+    mut ticks: EventReader<TickEvent>,
+    mut eaten: EventReader<FoodEatenEvent>,
+    mut events: EventWriter<GameEvent>,
+) {
+    if eaten.read().count() > 0 {
+        combo.idle = 0;
+        ticks.clear();
+        return;
+    }
+    let before = score.current;
+    for _ in ticks.read() {
+        combo.idle += 1;
+        if decay.enabled && combo.idle > decay.grace_ticks {
+            score.current = score.current.saturating_sub(decay.per_tick);
+            players.decay(decay.per_tick);
+        }
+    }
+    if score.current != before {
+        events.send(GameEvent::ScoreChanged {
+            total: score.current,
+        });
+    }
+}
+
+/// Discards the score of a run that was reset rather than finished
+fn reset_score(
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    mut players: ResMut<PlayerScores>,
+    mut resets: EventReader<ResetGameEvent>,
+) {
+    if resets.read().count() > 0 {
+        score.current = 0;
+        *combo = Combo::default();
+        players.clear();
+    }
+}
+
+fn record_score(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    mut players: ResMut<PlayerScores>,
+    mut game_over: EventReader<GameOverEvent>,
+) {
+    if game_over.read().next().is_none() {
+        return;
+    }
+    *combo = Combo::default();
+    players.clear();
+    let best = score.current;
+    if score.finish_run() {
+        commands.spawn((
+            Text::new(format!("New high score: {}!", best)),
+            TextFont {
+                font_size: 28.,
+                ..Default::default()
+            },
+            TextColor(CELEBRATION_COLOR),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(40.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            Celebration,
+            TimedEffect::from_seconds(CELEBRATION_SECS),
+        ));
+    }
+}
+
+fn fade_celebration(
+    mut commands: Commands,
+    celebrations: Query<(Entity, &TimedEffect), With<Celebration>>,
+) {
+    for (entity, effect) in celebrations.iter() {
+        if effect.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EffectPlugin>() {
+            app.add_plugins(EffectPlugin);
+        }
+        app.init_resource::<Score>();
+        app.init_resource::<ScoringConfig>();
+        app.init_resource::<Combo>();
+        app.init_resource::<PlayerScores>();
+        app.init_resource::<ScoreDecay>();
+        app.init_resource::<PointsPopup>();
+        app.add_event::<ScoreEvent>();
+        app.add_event::<TickEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(
+            Update,
+            (
+                count_points,
+                decay_score,
+                reset_score,
+                record_score,
+                fade_celebration,
+                spawn_popups,
+                animate_popups,
+            )
+                .chain()
+                .after(SnakeSet::Collision)
+                .before(SnakeSet::Growth),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::arena::Direction;
+
+    #[test]
+    fn test_new_record_only_when_best_is_beaten() {
+        let mut score = Score {
+            current: 5,
+            ..Default::default()
+        };
+
+        assert!(score.finish_run());
+        assert_eq!(score.best, 5);
+        assert_eq!(score.current, 0);
+
+        score.current = 5;
+        assert!(!score.finish_run(), "tying the best is not a new record");
+
+        score.current = 3;
+        assert!(!score.finish_run());
+        assert_eq!(score.best, 5);
+
+        score.current = 6;
+        assert!(score.finish_run());
+        assert_eq!(score.best, 6);
+    }
+
+    #[test]
+    fn test_points_are_credited_to_the_player_who_ate() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_plugins(ScorePlugin);
+
+        app.world_mut().send_event(FoodEatenEvent {
+            position: Position { x: 4, y: 4 },
+            kind: FoodKind::Golden,
+            player: PlayerId(1),
+        });
+        app.update();
+
+        let players = app.world().resource::<PlayerScores>();
+        assert_eq!(players.of(PlayerId(1)), FoodKind::Golden.points());
+        assert_eq!(players.of(PlayerId(0)), 0);
+        assert_eq!(
+            app.world().resource::<Score>().current,
+            FoodKind::Golden.points()
+        );
+
+        app.world_mut().send_event(ResetGameEvent);
+        app.update();
+        assert_eq!(app.world().resource::<PlayerScores>().of(PlayerId(1)), 0);
+    }
+
+    #[test]
+    fn test_popup_shows_points_and_expires() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_plugins(ScorePlugin);
+        app.world_mut().spawn((Window::default(), PrimaryWindow));
+
+        app.world_mut().send_event(FoodEatenEvent {
+            position: Position { x: 4, y: 4 },
+            kind: FoodKind::Golden,
+            player: PlayerId::default(),
+        });
+        app.update();
+
+        let world = app.world_mut();
+        let text = world.query_filtered::<&Text2d, With<Popup>>().single(world);
+        assert_eq!(text.0, "+3");
+        assert_eq!(app.world().resource::<Score>().current, 3);
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_secs_f32(PointsPopup::default().lifetime));
+        app.update();
+
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Popup>().iter(world).count(), 0);
+    }
+
+    #[test]
+    fn test_idle_ticks_decay_score_until_next_eat() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ScoreDecay {
+            enabled: true,
+            per_tick: 1,
+            grace_ticks: 2,
+        });
+        app.insert_resource(ArenaConfig::default());
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_plugins(ScorePlugin);
+        app.world_mut().resource_mut::<Score>().current = 3;
+        let mut tick = 0;
+        let mut step = |app: &mut App, eat: bool| {
+            tick += 1;
+            app.world_mut().send_event(TickEvent {
+                tick,
+                direction: Direction::Up,
+            });
+            if eat {
+                app.world_mut().send_event(FoodEatenEvent {
+                    position: Position { x: 4, y: 4 },
+                    kind: FoodKind::Normal,
+                    player: PlayerId::default(),
+                });
+            }
+            app.update();
+            app.world().resource::<Score>().current
+        };
+
+        let idle: Vec<u32> = (0..6).map(|_| step(&mut app, false)).collect();
+        assert_eq!(idle, [3, 3, 2, 1, 0, 0]);
+
+        // Eating scores as usual and starts the grace period over
+        let recovered: Vec<u32> = [true, false, false, false]
+            .into_iter()
+            .map(|eat| step(&mut app, eat))
+            .collect();
+        assert_eq!(recovered, [1, 1, 1, 0]);
+    }
+
+    fn eat(kind: FoodKind, length: usize, combo: u32) -> EatContext {
+        EatContext {
+            kind,
+            length,
+            combo,
+            elapsed: Duration::from_secs(30),
+        }
+    }
+
+    #[test]
+    fn test_scoring_formulas() {
+        let points = |formula| ScoringConfig {
+            formula,
+            ..Default::default()
+        };
+        let golden = eat(FoodKind::Golden, 5, 2);
+        assert_eq!(points(ScoringFormula::FoodValue).points(&golden), 3);
+        assert_eq!(
+            points(ScoringFormula::LengthScaled { per_segment: 2 }).points(&golden),
+            10
+        );
+        assert_eq!(points(ScoringFormula::Combo).points(&golden), 6);
+        let time_bonus = |eat: &EatContext| 100u32.saturating_sub(eat.elapsed.as_secs() as u32);
+        assert_eq!(
+            points(ScoringFormula::Custom(time_bonus)).points(&golden),
+            70
+        );
+    }
+
+    #[test]
+    fn test_longer_snakes_earn_more_with_length_scaling() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(ScoringConfig {
+            formula: ScoringFormula::LengthScaled { per_segment: 2 },
+            ..Default::default()
+        });
+        app.init_resource::<SnakeSegments>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_plugins(ScorePlugin);
+
+        let mut awarded = Vec::new();
+        for _ in 0..3 {
+            let segment = app.world_mut().spawn_empty().id();
+            app.world_mut()
+                .resource_mut::<SnakeSegments>()
+                .push(segment);
+            let before = app.world().resource::<Score>().current;
+            app.world_mut().send_event(FoodEatenEvent {
+                position: Position { x: 1, y: 1 },
+                kind: FoodKind::Normal,
+                player: PlayerId::default(),
+            });
+            app.update();
+            awarded.push(app.world().resource::<Score>().current - before);
+        }
+
+        assert_eq!(awarded, [2, 4, 6]);
+    }
+
+    #[test]
+    fn test_combo_breaks_after_window() {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(ScoringConfig {
+            formula: ScoringFormula::Combo,
+            combo_window: 3,
+        });
+        app.init_resource::<MoveCount>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_plugins(ScorePlugin);
+
+        let mut awarded = Vec::new();
+        for gap in [0, 2, 3, 4, 1] {
+            for _ in 0..gap {
+                app.world_mut().resource_mut::<MoveCount>().increment();
+            }
+            let before = app.world().resource::<Score>().current;
+            app.world_mut().send_event(FoodEatenEvent {
+                position: Position { x: 1, y: 1 },
+                kind: FoodKind::Normal,
+                player: PlayerId::default(),
+            });
+            app.update();
+            awarded.push(app.world().resource::<Score>().current - before);
+        }
+
+        assert_eq!(awarded, [1, 2, 3, 1, 2]);
+    }
+}
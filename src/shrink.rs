@@ -0,0 +1,305 @@
+//! Shrinking arena module
+//!
+//! This module adds a survival mode where the arena closes in over time. Every few
+//! seconds the outermost playable ring of tiles is cut off the arena, squeezing the
+//! snake toward the middle of the board.
+//!
+//! Cut off tiles are out of bounds like the area beyond the walls, so running onto
+//! one ends the run and no food spawns there. A snake with any part of it on the ring
+//! as it closes crashes with it, and food left on the ring is removed. Wrapped edges
+//! wrap across the shrunk arena. The arena returns to its full size at the start of
+//! every run.
+//!
+//! The mode is off by default. Enable it with `ShrinkingArena::enabled` or the
+//! `--shrink` command line flag.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    arena::{self, ArenaConfig, FootprintSize, Position},
+    countdown::countdown_finished,
+    food::Food,
+    snake::{
+        Deaths, GameOverEvent, LifeLostEvent, ResetGameEvent, SnakeHead, SnakeSegment, SnakeSet,
+    },
+    telemetry::{DeathCause, GameEvent},
+};
+
+/// Settings for the shrinking arena mode
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct ShrinkingArena {
+    pub enabled: bool,
+    /// Seconds of play between each ring being cut off
+    pub every_secs: f32,
+    /// The arena stops shrinking before its shorter side would drop below this many
+    /// tiles
+    pub min_size: i32,
+}
+
+impl Default for ShrinkingArena {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            every_secs: 10.0,
+            min_size: 4,
+        }
+    }
+}
+
+impl ShrinkingArena {
+    /// Returns true if another ring can be cut off `arena` without going below
+    /// `min_size`
+    pub fn can_shrink(&self, arena: &ArenaConfig) -> bool {
+        let shorter = arena.width.min(arena.height) - 2 * (arena.inset + 1);
+        shorter >= self.min_size.max(1)
+    }
+}
+
+/// Time until the next ring is cut off
+#[derive(Resource, Debug)]
+struct ShrinkClock(Timer);
+
+impl ShrinkClock {
+    fn new(settings: &ShrinkingArena) -> Self {
+        Self(Timer::new(
+            Duration::from_secs_f32(settings.every_secs.max(0.)),
+            TimerMode::Repeating,
+        ))
+    }
+}
+
+/// Returns true if any tile of a footprint anchored at `pos` has been cut off
+fn cut_off(pos: Position, size: Option<&FootprintSize>, arena: &ArenaConfig) -> bool {
+    size.copied()
+        .unwrap_or_default()
+        .tiles(pos)
+        .any(|tile| !arena::in_bounds(tile, arena))
+}
+
+/// Cuts off the outer ring each time the shrink clock runs out, removing the food on
+/// it and ending the run if any part of the snake is caught on it
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn shrink_arena(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    settings: Res<ShrinkingArena>,
+    mut clock: ResMut<ShrinkClock>,
+    mut arena: ResMut<ArenaConfig>,
+    snake: Query<(&Position, Option<&FootprintSize>), Or<(With<SnakeHead>, With<SnakeSegment>)>>,
+    food: Query<(Entity, &Position, Option<&FootprintSize>), With<Food>>,
+    mut deaths: Deaths,
+    mut events: EventWriter<GameEvent>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    if settings.is_changed() {
+        *clock = ShrinkClock::new(&settings);
+    }
+    let rings = clock.0.tick(time.delta()).times_finished_this_tick();
+    let inset = arena.inset;
+    for _ in 0..rings {
+        if !settings.can_shrink(&arena) {
+            break;
+        }
+        arena.inset += 1;
+    }
+    if arena.inset == inset {
+        return;
+    }
+    for (entity, pos, size) in food.iter() {
+        if cut_off(*pos, size, &arena) {
+            commands.entity(entity).despawn();
+        }
+    }
+    if snake.iter().any(|(pos, size)| cut_off(*pos, size, &arena)) {
+        deaths.crash();
+        events.send(GameEvent::Died {
+            cause: DeathCause::Wall,
+        });
+    }
+}
+
+/// Restores the full arena and restarts the shrink clock for the next run
+fn restore_arena(
+    settings: Res<ShrinkingArena>,
+    mut clock: ResMut<ShrinkClock>,
+    mut arena: ResMut<ArenaConfig>,
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if !died && !reset {
+        return;
+    }
+    if arena.inset != 0 {
+        arena.inset = 0;
+    }
+    *clock = ShrinkClock::new(&settings);
+}
+
+pub struct ShrinkPlugin;
+
+impl Plugin for ShrinkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShrinkingArena>();
+        let clock = ShrinkClock::new(app.world().resource::<ShrinkingArena>());
+        app.insert_resource(clock);
+        app.init_resource::<ArenaConfig>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<LifeLostEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_event::<GameEvent>();
+        // The arena is restored before the snake respawns, so the new run starts on
+        // the full board
+        app.add_systems(
+            Update,
+            (shrink_arena.run_if(countdown_finished), restore_arena)
+                .chain()
+                .after(SnakeSet::Movement)
+                .before(SnakeSet::Collision),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snake::EdgeWrap;
+
+    fn shrink_app(head: Position) -> App {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(ShrinkingArena {
+            enabled: true,
+            every_secs: 1.0,
+            min_size: 4,
+        });
+        app.add_plugins(ShrinkPlugin);
+        app.world_mut().spawn((SnakeHead::default(), head));
+        app
+    }
+
+    fn advance_secs(app: &mut App, secs: u64) {
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_secs(secs));
+        app.update();
+    }
+
+    #[test]
+    fn test_outer_ring_becomes_lethal() {
+        let full = ArenaConfig::default();
+        let mut app = shrink_app(full.center_tile());
+
+        advance_secs(&mut app, 1);
+
+        let arena = app.world().resource::<ArenaConfig>().clone();
+        assert_eq!(arena.inset, 1);
+        for edge in [
+            Position { x: 0, y: 4 },
+            Position { x: 9, y: 4 },
+            Position { x: 4, y: 0 },
+            Position { x: 4, y: 9 },
+        ] {
+            assert!(arena::in_bounds(edge, &full));
+            assert!(
+                !arena::in_bounds(edge, &arena),
+                "{:?} should be cut off",
+                edge
+            );
+        }
+        let free = arena::free_tiles(&arena, &[]);
+        assert_eq!(free.len(), 64);
+        assert!(free
+            .iter()
+            .all(|tile| (1..9).contains(&tile.x) && (1..9).contains(&tile.y)));
+        assert!(app.world().resource::<Events<GameOverEvent>>().is_empty());
+    }
+
+    #[test]
+    fn test_shrinking_stops_at_min_size() {
+        let mut app = shrink_app(ArenaConfig::default().center_tile());
+
+        advance_secs(&mut app, 10);
+
+        // A 10x10 board shrinks to 4x4 and no further
+        assert_eq!(app.world().resource::<ArenaConfig>().inset, 3);
+    }
+
+    #[test]
+    fn test_head_on_closing_ring_dies() {
+        let mut app = shrink_app(Position { x: 0, y: 4 });
+
+        advance_secs(&mut app, 1);
+
+        assert_eq!(app.world().resource::<Events<GameOverEvent>>().len(), 1);
+        let events = app.world().resource::<Events<GameEvent>>();
+        let died: Vec<GameEvent> = events.iter_current_update_events().copied().collect();
+        assert_eq!(
+            died,
+            [GameEvent::Died {
+                cause: DeathCause::Wall
+            }]
+        );
+        // The next run starts on the full board
+        assert_eq!(app.world().resource::<ArenaConfig>().inset, 0);
+    }
+
+    #[test]
+    fn test_body_on_closing_ring_dies() {
+        let mut app = shrink_app(Position { x: 1, y: 4 });
+        app.world_mut()
+            .spawn((SnakeSegment, Position { x: 0, y: 4 }));
+
+        advance_secs(&mut app, 1);
+
+        assert_eq!(app.world().resource::<Events<GameOverEvent>>().len(), 1);
+    }
+
+    #[test]
+    fn test_food_on_closing_ring_is_removed() {
+        let mut app = shrink_app(ArenaConfig::default().center_tile());
+        let stranded = app.world_mut().spawn((Food, Position { x: 9, y: 2 })).id();
+        let straddling = app
+            .world_mut()
+            .spawn((Food, Position { x: 2, y: 0 }, FootprintSize::square(2)))
+            .id();
+        let inside = app.world_mut().spawn((Food, Position { x: 2, y: 2 })).id();
+
+        advance_secs(&mut app, 1);
+
+        assert!(app.world().get_entity(stranded).is_err());
+        assert!(app.world().get_entity(straddling).is_err());
+        assert!(app.world().get_entity(inside).is_ok());
+        assert!(app.world().resource::<Events<GameOverEvent>>().is_empty());
+    }
+
+    #[test]
+    fn test_wrapped_edges_follow_the_shrunk_arena() {
+        let arena = ArenaConfig {
+            inset: 2,
+            ..Default::default()
+        };
+        let wrap = EdgeWrap {
+            wrap_x: true,
+            wrap_y: true,
+        };
+
+        assert_eq!(
+            wrap.apply(Position { x: 1, y: 4 }, &arena),
+            Position { x: 7, y: 4 }
+        );
+        assert_eq!(
+            wrap.apply(Position { x: 4, y: 8 }, &arena),
+            Position { x: 4, y: 2 }
+        );
+        assert_eq!(
+            wrap.apply(Position { x: 5, y: 5 }, &arena),
+            Position { x: 5, y: 5 }
+        );
+    }
+}
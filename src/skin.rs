@@ -0,0 +1,305 @@
+//! Skin module
+//!
+//! This module lets the snake and food be drawn with textures instead of solid colors.
+//!
+//! Texture paths are read from the `SkinPaths` resource at startup and loaded into
+//! `SpriteAssets`. Any entity without a configured texture keeps its solid color.
+//!
+//! Untextured snake segments are drawn with rounded outer corners following the
+//! `SegmentCorners` radius of the theme. The rounded shapes are generated on demand as
+//! white, anti-aliased textures so the usual sprite colors still tint them.
+
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use crate::{
+    arena::Position,
+    snake::{SnakeSegments, SnakeSet},
+    theme::SegmentCorners,
+};
+
+/// Paths, relative to the assets directory, of the textures to draw with
+#[derive(Resource, Default, Clone, Debug)]
+pub struct SkinPaths {
+    pub head: Option<String>,
+    pub segment: Option<String>,
+    pub food: Option<String>,
+}
+
+/// Loaded textures for the snake and food
+#[derive(Resource, Default, Clone, Debug)]
+pub struct SpriteAssets {
+    pub head: Option<Handle<Image>>,
+    pub segment: Option<Handle<Image>>,
+    pub food: Option<Handle<Image>>,
+}
+
+/// Builds the sprite for a game entity, using `image` when there is one and the solid
+/// `color` otherwise.
+///
+/// Textured sprites are given a custom size of a single unit so `scale_size` stretches
+/// them to the tile size just like solid sprites, whatever the resolution of the image.
+pub fn sprite(image: Option<&Handle<Image>>, color: Color) -> Sprite {
+    match image {
+        Some(image) => Sprite {
+            image: image.clone(),
+            custom_size: Some(Vec2::ONE),
+            ..Default::default()
+        },
+        None => Sprite {
+            color,
+            ..Default::default()
+        },
+    }
+}
+
+/// The side length in pixels of the generated rounded corner textures
+const CORNER_TEXTURE_SIZE: u32 = 32;
+
+/// A set of corners of a tile, as bit flags
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Corners(u8);
+
+impl Corners {
+    pub const TOP_LEFT: Corners = Corners(1);
+    pub const TOP_RIGHT: Corners = Corners(2);
+    pub const BOTTOM_RIGHT: Corners = Corners(4);
+    pub const BOTTOM_LEFT: Corners = Corners(8);
+    pub const NONE: Corners = Corners(0);
+
+    pub fn contains(self, corner: Corners) -> bool {
+        self.0 & corner.0 == corner.0
+    }
+}
+
+impl std::ops::BitOr for Corners {
+    type Output = Corners;
+
+    fn bitor(self, rhs: Corners) -> Corners {
+        Corners(self.0 | rhs.0)
+    }
+}
+
+/// The corners of the segment at `current` to round, given the segments before and
+/// after it in the body.
+///
+/// A corner is rounded when neither of the sides it joins connects to a neighbouring
+/// segment. A straight segment keeps all four corners square, an end of the body
+/// rounds the two corners facing away from the body, and a turn joint rounds only
+/// the outer corner of the bend.
+pub fn rounded_corners(
+    previous: Option<Position>,
+    current: Position,
+    next: Option<Position>,
+) -> Corners {
+    let connected = |dx: i32, dy: i32| {
+        [previous, next]
+            .into_iter()
+            .flatten()
+            .any(|neighbor| neighbor - current == Position { x: dx, y: dy })
+    };
+    let (up, down, left, right) = (
+        connected(0, 1),
+        connected(0, -1),
+        connected(-1, 0),
+        connected(1, 0),
+    );
+    [
+        (!up && !left, Corners::TOP_LEFT),
+        (!up && !right, Corners::TOP_RIGHT),
+        (!down && !right, Corners::BOTTOM_RIGHT),
+        (!down && !left, Corners::BOTTOM_LEFT),
+    ]
+    .into_iter()
+    .filter(|(round, _)| *round)
+    .fold(Corners::NONE, |corners, (_, corner)| corners | corner)
+}
+
+/// Draws a white square with the given corners rounded to `radius`, a fraction of
+/// the side length. Edge pixels are partially transparent so the curve is smooth.
+fn rounded_square(corners: Corners, radius: f32) -> Image {
+    let size = CORNER_TEXTURE_SIZE;
+    let radius = radius * size as f32;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for row in 0..size {
+        for column in 0..size {
+            // Pixel centers, with y growing upwards to match the arena
+            let x = column as f32 + 0.5;
+            let y = (size - row) as f32 - 0.5;
+            let near_left = x < radius;
+            let near_right = x > size as f32 - radius;
+            let near_bottom = y < radius;
+            let near_top = y > size as f32 - radius;
+            let corner = match (near_left, near_right, near_bottom, near_top) {
+                (true, _, _, true) => Some((Corners::TOP_LEFT, radius, size as f32 - radius)),
+                (_, true, _, true) => Some((
+                    Corners::TOP_RIGHT,
+                    size as f32 - radius,
+                    size as f32 - radius,
+                )),
+                (_, true, true, _) => Some((Corners::BOTTOM_RIGHT, size as f32 - radius, radius)),
+                (true, _, true, _) => Some((Corners::BOTTOM_LEFT, radius, radius)),
+                _ => None,
+            };
+            let coverage = match corner {
+                Some((corner, cx, cy)) if corners.contains(corner) => {
+                    let distance = Vec2::new(x - cx, y - cy).length();
+                    (radius - distance + 0.5).clamp(0., 1.)
+                }
+                _ => 1.,
+            };
+            data.extend_from_slice(&[255, 255, 255, (coverage * 255.).round() as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// The generated rounded textures, keyed by the corners they round
+#[derive(Resource, Default)]
+struct CornerTextures {
+    radius: f32,
+    images: HashMap<Corners, Handle<Image>>,
+}
+
+/// Gives each untextured snake segment the rounded texture matching its place in the
+/// body
+fn round_segment_corners(
+    style: Res<SegmentCorners>,
+    segments: Res<SnakeSegments>,
+    mut textures: ResMut<CornerTextures>,
+    mut images: ResMut<Assets<Image>>,
+    positions: Query<&Position>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let radius = style.clamped_radius();
+    if textures.radius != radius {
+        textures.radius = radius;
+        textures.images.clear();
+    }
+    let body: Vec<(Entity, Position)> = segments
+        .iter()
+        .filter_map(|entity| positions.get(*entity).ok().map(|pos| (*entity, *pos)))
+        .collect();
+    for (index, (entity, current)) in body.iter().enumerate() {
+        let Ok(mut sprite) = sprites.get_mut(*entity) else {
+            continue;
+        };
+        let generated = sprite.image == Handle::default()
+            || textures.images.values().any(|image| *image == sprite.image);
+        if !generated {
+            continue;
+        }
+        let previous = index.checked_sub(1).map(|index| body[index].1);
+        let next = body.get(index + 1).map(|(_, pos)| *pos);
+        let corners = if radius > 0. {
+            rounded_corners(previous, *current, next)
+        } else {
+            Corners::NONE
+        };
+        if corners == Corners::NONE {
+            if sprite.image != Handle::default() {
+                sprite.image = Handle::default();
+                sprite.custom_size = None;
+            }
+            continue;
+        }
+        let image = textures
+            .images
+            .entry(corners)
+            .or_insert_with(|| images.add(rounded_square(corners, radius)))
+            .clone();
+        if sprite.image != image {
+            sprite.image = image;
+            sprite.custom_size = Some(Vec2::ONE);
+        }
+    }
+}
+
+fn load_textures(mut commands: Commands, paths: Res<SkinPaths>, asset_server: Res<AssetServer>) {
+    let load = |path: &Option<String>| path.as_ref().map(|path| asset_server.load(path.clone()));
+    commands.insert_resource(SpriteAssets {
+        head: load(&paths.head),
+        segment: load(&paths.segment),
+        food: load(&paths.food),
+    });
+}
+
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkinPaths>();
+        app.init_resource::<SegmentCorners>();
+        app.init_resource::<CornerTextures>();
+        app.add_systems(PreStartup, load_textures);
+        app.add_systems(Update, round_segment_corners.after(SnakeSet::Growth));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AT: Position = Position { x: 3, y: 3 };
+
+    fn at(dx: i32, dy: i32) -> Option<Position> {
+        Some(AT + Position { x: dx, y: dy })
+    }
+
+    #[test]
+    fn test_straight_segment_keeps_square_corners() {
+        assert_eq!(rounded_corners(at(0, 1), AT, at(0, -1)), Corners::NONE);
+        assert_eq!(rounded_corners(at(-1, 0), AT, at(1, 0)), Corners::NONE);
+    }
+
+    #[test]
+    fn test_turn_joint_rounds_outer_corner() {
+        // Coming from below and turning right bends around the top left corner
+        assert_eq!(rounded_corners(at(0, -1), AT, at(1, 0)), Corners::TOP_LEFT);
+        assert_eq!(
+            rounded_corners(at(-1, 0), AT, at(0, 1)),
+            Corners::BOTTOM_RIGHT
+        );
+    }
+
+    #[test]
+    fn test_ends_round_the_side_facing_away() {
+        assert_eq!(
+            rounded_corners(None, AT, at(0, -1)),
+            Corners::TOP_LEFT | Corners::TOP_RIGHT
+        );
+        assert_eq!(
+            rounded_corners(at(1, 0), AT, None),
+            Corners::TOP_LEFT | Corners::BOTTOM_LEFT
+        );
+    }
+
+    #[test]
+    fn test_rounded_square_fades_only_rounded_corners() {
+        let image = rounded_square(Corners::TOP_LEFT, 0.5);
+        let alpha = |column: u32, row: u32| {
+            image.data[((row * CORNER_TEXTURE_SIZE + column) * 4 + 3) as usize]
+        };
+        let last = CORNER_TEXTURE_SIZE - 1;
+        assert_eq!(alpha(0, 0), 0, "top left is cut away");
+        assert_eq!(alpha(last, 0), 255, "top right stays square");
+        assert_eq!(alpha(last / 2, last / 2), 255, "center is solid");
+    }
+}
@@ -9,80 +9,137 @@
 //! - Movement and growth systems
 //! - Collision detection with food and self
 
-use std::slice::Iter;
+use std::{collections::VecDeque, error::Error, fmt, mem, slice::Iter, time::Duration};
 
 use bevy::{
     app::{Plugin, Startup, Update},
-    color::Color,
+    asset::Handle,
+    audio::{AudioPlayer, PlaybackSettings},
+    color::{Alpha, Color},
     ecs::{
         component::Component,
         entity::Entity,
         event::{Event, EventReader, EventWriter},
-        query::With,
-        schedule::IntoSystemConfigs,
-        system::{Commands, Query, Res, ResMut, Resource},
+        query::{Has, Or, With, Without},
+        schedule::{
+            common_conditions::resource_exists_and_changed, IntoSystemConfigs,
+            IntoSystemSetConfigs, SystemSet,
+        },
+        system::{Commands, Local, Query, Res, ResMut, Resource, SystemParam},
+        world::World,
     },
     input::{keyboard::KeyCode, ButtonInput},
+    log::warn,
+    math::Vec2,
+    render::view::Visibility,
     sprite::Sprite,
-    time::{Time, Timer, TimerMode},
+    time::{Time, Timer, TimerMode, Virtual},
+    transform::components::Transform,
+    window::{PrimaryWindow, Window},
 };
 
 use crate::{
-    arena::{Position, Size, HEIGHT, WIDTH},
-    food::Food,
+    ai,
+    arena::{self, ArenaConfig, Direction, Position, Size, SizeModifier},
+    audio::AudioAssets,
+    countdown::countdown_finished,
+    effect::{EffectPlugin, TimedEffect},
+    food::{self, Food, FoodKind, FoodOrder, FootprintSize, OrderedFood},
+    score::{Combo, PlayerScores, Score},
+    skin::{self, SpriteAssets},
+    telemetry::{DeathCause, GameEvent},
+    theme::{PlayerColors, SnakeColors},
+    tile::{TileEffects, TileOutcome},
 };
 
-#[derive(PartialEq, Clone, Copy, Debug)]
-enum Direction {
-    Left,
-    Up,
-    Right,
-    Down,
+/// The player a snake belongs to, carried by its head and every segment. There is a
+/// single player for now, the default one.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct PlayerId(pub u8);
+
+/// The colors the snake of `player` is drawn in, falling back to the default palette
+fn colors_of(colors: Option<&PlayerColors>, player: PlayerId) -> SnakeColors {
+    colors.map_or_else(
+        || PlayerColors::default().of(player),
+        |colors| colors.of(player),
+    )
 }
 
-impl Direction {
-    fn opposite(self) -> Self {
-        match self {
-            Direction::Left => Self::Right,
-            Direction::Up => Self::Down,
-            Direction::Right => Self::Left,
-            Direction::Down => Self::Up,
+#[derive(Component)]
+pub(crate) struct SnakeHead {
+    pub(crate) direction: Direction,
+    /// The direction of the last step `movement` actually took. `direction` may change
+    /// several times between steps, so reversals are checked against this instead.
+    last_moved: Direction,
+    /// Turns waiting to be applied by `movement`, used by the delayed `TurnTiming` modes
+    turns: VecDeque<QueuedTurn>,
+}
+
+/// A turn waiting in `SnakeHead::turns` and how long it has waited
+#[derive(Clone, Copy, Debug)]
+struct QueuedTurn {
+    direction: Direction,
+    age: Duration,
+}
+
+impl QueuedTurn {
+    fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            age: Duration::ZERO,
         }
     }
 }
 
-const SNAKE_HEAD_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
-const SNAKE_SEGMENT_COLOR: Color = Color::srgb(0.3, 0.3, 0.3);
+impl SnakeHead {
+    /// A head about to move in `direction`
+    pub(crate) fn facing(direction: Direction) -> Self {
+        Self {
+            direction,
+            last_moved: direction,
+            turns: VecDeque::new(),
+        }
+    }
 
-#[derive(Component)]
-struct SnakeHead {
-    direction: Direction,
+    /// Applies the next pending turn that is still legal, discarding any that would
+    /// keep the snake going the same way or, unless `allow_reverse` is set, reverse it
+    fn apply_turn(&mut self, allow_reverse: bool) {
+        while let Some(QueuedTurn { direction, .. }) = self.turns.pop_front() {
+            let reverse = direction == self.direction.opposite();
+            if direction != self.direction && (allow_reverse || !reverse) {
+                self.direction = direction;
+                return;
+            }
+        }
+    }
 }
 
 impl Default for SnakeHead {
     fn default() -> Self {
-        Self {
-            direction: Direction::Up,
-        }
+        Self::facing(Direction::Up)
     }
 }
 
 #[derive(Component)]
-struct SnakeSegment;
+pub(crate) struct SnakeSegment;
+
+/// Marks the final segment of the snake so it can be styled as the tail
+#[derive(Component)]
+struct SnakeTail;
 
 #[derive(Resource, Default)]
-struct SnakeSegments(Vec<Entity>);
+pub(crate) struct SnakeSegments(Vec<Entity>);
 
 impl SnakeSegments {
-    fn iter(&self) -> Iter<Entity> {
+    pub(crate) fn iter(&self) -> Iter<'_, Entity> {
         self.0.iter()
     }
 
-    fn len(&self) -> usize {
+    pub(crate) fn len(&self) -> usize {
         self.0.len()
     }
 
-    fn push(&mut self, e: Entity) {
+    pub(crate) fn push(&mut self, e: Entity) {
         self.0.push(e);
     }
 }
@@ -90,348 +147,4333 @@ impl SnakeSegments {
 #[derive(Resource, Default)]
 struct LastTailPosition(Option<Position>);
 
-fn spawn_segment(mut commands: Commands, position: Position) -> Entity {
+fn spawn_segment(
+    commands: &mut Commands,
+    assets: Option<&SpriteAssets>,
+    colors: Option<&PlayerColors>,
+    player: PlayerId,
+    position: Position,
+) -> Entity {
+    let image = assets.and_then(|assets| assets.segment.as_ref());
     commands
-        .spawn(Sprite {
-            color: SNAKE_SEGMENT_COLOR,
-            ..Default::default()
-        })
+        .spawn(skin::sprite(image, colors_of(colors, player).body))
         .insert(SnakeSegment)
+        .insert(player)
         .insert(position)
         .insert(Size::square(0.65))
         .id()
 }
 
-fn spawn_snake(mut commands: Commands, mut segments: ResMut<SnakeSegments>) {
-    *segments = SnakeSegments(vec![
-        commands
-            .spawn(Sprite {
-                color: SNAKE_HEAD_COLOR,
-                ..Default::default()
+/// Where the snake starts each run, which way it is heading and how long it is.
+///
+/// The body is laid out in a straight line behind the head, so it never overlaps
+/// itself. A start that does not fit the arena is not used as is, see `layout`.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SnakeStart {
+    pub head: Position,
+    pub direction: Direction,
+    /// The number of tiles the snake covers, head included
+    pub length: usize,
+}
+
+impl Default for SnakeStart {
+    fn default() -> Self {
+        Self {
+            head: Position { x: 3, y: 3 },
+            direction: Direction::Up,
+            length: 2,
+        }
+    }
+}
+
+impl SnakeStart {
+    /// The tiles covered by this start, head first, or `None` if any of them is
+    /// outside the arena or unplayable
+    fn tiles(&self, arena: &ArenaConfig) -> Option<Vec<Position>> {
+        let behind = self.direction.opposite().offset();
+        let tiles: Vec<Position> = (0..self.length.max(1) as i32)
+            .map(|i| {
+                self.head
+                    + Position {
+                        x: behind.x * i,
+                        y: behind.y * i,
+                    }
+            })
+            .collect();
+        tiles
+            .iter()
+            .all(|tile| arena::in_bounds(*tile, arena))
+            .then_some(tiles)
+    }
+
+    /// The tiles covered by this start if the snake also survives its first move,
+    /// heading into a free tile of the arena or wrapping across an edge
+    fn legal_tiles(&self, arena: &ArenaConfig, wrap: EdgeWrap) -> Option<Vec<Position>> {
+        let next = wrap.apply(self.head.neighbor(self.direction), arena);
+        self.tiles(arena)
+            .filter(|tiles| arena::in_bounds(next, arena) && !tiles.contains(&next))
+    }
+
+    /// The start to use on `arena`.
+    ///
+    /// A start that fits and survives its first move is kept. Otherwise a warning is
+    /// logged and the longest legal snake is used instead, trying the requested head,
+    /// then the default head, then every playable tile, facing the requested direction
+    /// first. A snake spawned on an edge facing out is therefore turned to face inward
+    /// when it can be, and moved in when it cannot. If no tile of the arena is playable
+    /// the default start is returned.
+    pub fn validated(&self, arena: &ArenaConfig, wrap: EdgeWrap) -> SnakeStart {
+        if self.legal_tiles(arena, wrap).is_some() {
+            return *self;
+        }
+        let default = SnakeStart::default();
+        let heads: Vec<Position> = [self.head, default.head]
+            .into_iter()
+            .chain(arena::free_tiles(arena, &[]))
+            .collect();
+        let directions = [
+            self.direction,
+            self.direction.clockwise(),
+            self.direction.counter_clockwise(),
+            self.direction.opposite(),
+        ];
+        let fallback = (1..=self.length.max(1))
+            .rev()
+            .flat_map(|length| {
+                heads.iter().flat_map(move |&head| {
+                    directions.map(|direction| SnakeStart {
+                        head,
+                        direction,
+                        length,
+                    })
+                })
             })
-            .insert(SnakeHead::default())
-            .insert(Position { x: 3, y: 3 })
-            .insert(Size::square(0.8))
-            .id(),
-        spawn_segment(commands, Position { x: 3, y: 2 }),
-    ]);
+            .find(|start| start.legal_tiles(arena, wrap).is_some())
+            .unwrap_or(default);
+        let problem = if self.tiles(arena).is_some() {
+            "would leave the arena on its first move"
+        } else {
+            "does not fit the arena"
+        };
+        warn!(
+            "snake start {:?} {}, using {:?} instead",
+            self, problem, fallback
+        );
+        fallback
+    }
 }
 
-fn handle_input(input: Res<ButtonInput<KeyCode>>, mut heads: Query<&mut SnakeHead>) {
-    for mut head in heads.iter_mut() {
-        if let Some(key) = [
-            KeyCode::ArrowLeft,
-            KeyCode::ArrowRight,
-            KeyCode::ArrowDown,
-            KeyCode::ArrowUp,
-            KeyCode::KeyA,
-            KeyCode::KeyD,
-            KeyCode::KeyS,
-            KeyCode::KeyW,
-        ]
-        .into_iter()
-        .find(|key| input.pressed(*key))
-        {
-            let dir = match key {
-                KeyCode::ArrowLeft | KeyCode::KeyA => Direction::Left,
-                KeyCode::ArrowRight | KeyCode::KeyD => Direction::Right,
-                KeyCode::ArrowDown | KeyCode::KeyS => Direction::Down,
-                KeyCode::ArrowUp | KeyCode::KeyW => Direction::Up,
-                _ => head.direction,
-            };
-            if dir != head.direction.opposite() {
-                head.direction = dir
-            }
+#[allow(clippy::too_many_arguments)]
+fn spawn_snake(
+    mut commands: Commands,
+    start: Res<SnakeStart>,
+    arena: Res<ArenaConfig>,
+    wrap: Res<EdgeWrap>,
+    head_size: Res<HeadSize>,
+    assets: Option<Res<SpriteAssets>>,
+    colors: Option<Res<PlayerColors>>,
+    mut segments: ResMut<SnakeSegments>,
+) {
+    let assets = assets.as_deref();
+    let colors = colors.as_deref();
+    let player = PlayerId::default();
+    let image = assets.and_then(|assets| assets.head.as_ref());
+    let start = start.validated(&arena, *wrap);
+    let mut tiles = start
+        .tiles(&arena)
+        .unwrap_or_else(|| vec![start.head])
+        .into_iter();
+    let footprint = head_size.footprint();
+    let mut head = commands.spawn(skin::sprite(image, colors_of(colors, player).head));
+    head.insert(SnakeHead::facing(start.direction))
+        .insert(player)
+        .insert(tiles.next().unwrap_or(start.head))
+        .insert(Size::square(footprint.width as f32 - 0.2));
+    if footprint != FootprintSize::default() {
+        head.insert(footprint);
+    }
+    let head = head.id();
+    *segments = SnakeSegments(
+        std::iter::once(head)
+            .chain(tiles.map(|tile| spawn_segment(&mut commands, assets, colors, player, tile)))
+            .collect(),
+    );
+}
+
+/// Speeds the snake up while the player holds the key for the direction it is already heading.
+///
+/// `handle_input` records whether the dash is held each frame and `movement` consumes the
+/// multiplier when ticking its timer, so releasing the key restores the normal speed on the
+/// very next tick. A `speedup` of `1.0` disables dashing.
+///
+/// Eating `FoodKind::Dash` food queues `pending` tiles for the head to dash straight
+/// ahead, which `movement` takes all at once on its next run.
+#[derive(Resource)]
+struct Dash {
+    held: bool,
+    speedup: f32,
+    pending: u32,
+}
+
+impl Dash {
+    fn multiplier(&self) -> f32 {
+        if self.held {
+            self.speedup
+        } else {
+            1.0
+        }
+    }
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        Self {
+            held: false,
+            speedup: 2.0,
+            pending: 0,
         }
     }
 }
 
+/// Sent when the player presses the direction opposite to the one the snake is heading
+#[derive(Event)]
+struct ReverseRejected;
+
+/// Settings for the head flash shown when a reverse is rejected. Off by default.
 #[derive(Resource)]
-struct MovementTimer {
-    clock: Timer,
+pub struct ReverseFeedback {
+    pub enabled: bool,
+    /// How long the head stays highlighted, in seconds
+    pub duration: f32,
 }
 
-impl MovementTimer {
-    fn from_seconds(secs: f32) -> Self {
+impl Default for ReverseFeedback {
+    fn default() -> Self {
         Self {
-            clock: Timer::from_seconds(secs, TimerMode::Repeating),
+            enabled: false,
+            duration: 0.12,
         }
     }
 }
 
-fn movement(
-    time: Res<Time>,
-    mut timer: ResMut<MovementTimer>,
-    segments: ResMut<SnakeSegments>,
-    mut last_tail_position: ResMut<LastTailPosition>,
-    mut heads: Query<(Entity, &SnakeHead)>,
-    mut positions: Query<&mut Position>,
-    mut game_over: EventWriter<GameOverEvent>,
-) {
-    if !timer.clock.tick(time.delta()).just_finished() {
-        return;
+const SNAKE_HEAD_FLASH_COLOR: Color = Color::srgb(1.0, 0.35, 0.35);
+
+/// Tracks an active flash on the snake head
+#[derive(Component)]
+struct HeadFlash;
+
+/// Returned when a key is bound to a direction while it already controls another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindingConflict {
+    pub key: KeyCode,
+    pub bound_to: Direction,
+}
+
+impl fmt::Display for BindingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is already bound to {:?}", self.key, self.bound_to)
     }
-    if let Some((head_entity, head)) = heads.iter_mut().next() {
-        let segment_positions: Vec<Position> = segments
-            .iter()
-            .filter_map(|e| positions.get_mut(*e).ok().map(|p| *p))
-            .collect();
-        if segment_positions.len() != segments.len() {
-            // Some segments were missing positions, exit early
-            return;
-        }
-        if let Ok(mut head_pos) = positions.get_mut(head_entity) {
-            match &head.direction {
-                Direction::Left => head_pos.x -= 1,
-                Direction::Up => head_pos.y += 1,
-                Direction::Right => head_pos.x += 1,
-                Direction::Down => head_pos.y -= 1,
-            }
+}
 
-            if head_pos.x < 0
-                || head_pos.y < 0
-                || head_pos.x as f32 >= WIDTH
-                || head_pos.y as f32 >= HEIGHT
-            {
-                game_over.send(GameOverEvent);
-            }
+impl Error for BindingConflict {}
 
-            if segment_positions.contains(&head_pos) {
-                game_over.send(GameOverEvent);
+/// The keys that steer the snake.
+///
+/// Every key controls at most one direction, which `bind` enforces.
+#[derive(Resource, Clone, Debug)]
+pub struct KeyBindings {
+    /// Bindings in priority order for when several keys are held at once
+    bindings: Vec<(KeyCode, Direction)>,
+}
+
+impl KeyBindings {
+    /// Replaces the keys bound to `direction`.
+    ///
+    /// Fails without changing anything if any of `keys` already controls a different
+    /// direction.
+    pub fn bind(&mut self, direction: Direction, keys: &[KeyCode]) -> Result<(), BindingConflict> {
+        for key in keys {
+            if let Some(bound_to) = self.direction(*key).filter(|dir| *dir != direction) {
+                return Err(BindingConflict {
+                    key: *key,
+                    bound_to,
+                });
             }
         }
+        self.bindings.retain(|(_, dir)| *dir != direction);
+        self.bindings
+            .extend(keys.iter().map(|key| (*key, direction)));
+        Ok(())
+    }
 
-        segment_positions
+    /// The direction controlled by `key`, if any
+    pub fn direction(&self, key: KeyCode) -> Option<Direction> {
+        self.bindings
             .iter()
-            .zip(segments.iter().skip(1))
-            .for_each(|(pos, segment)| {
-                if let Ok(mut position) = positions.get_mut(*segment) {
-                    *position = *pos
-                }
-            });
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, dir)| *dir)
+    }
 
-        if let Some(last_segment) = segment_positions.last() {
-            *last_tail_position = LastTailPosition(Some(*last_segment));
+    /// The most recently pressed bound key that is still held, along with its direction.
+    ///
+    /// `held` records the bound keys in the order they were pressed and is kept up to
+    /// date from `input` on every call. When several keys go down in the same frame
+    /// the one listed first in the bindings counts as the most recent, so the result is
+    /// deterministic.
+    fn most_recent(
+        &self,
+        input: &ButtonInput<KeyCode>,
+        held: &mut Vec<KeyCode>,
+    ) -> Option<(KeyCode, Direction)> {
+        held.retain(|key| input.pressed(*key));
+        for (key, _) in self.bindings.iter().rev() {
+            if input.just_pressed(*key) {
+                held.retain(|held| held != key);
+                held.push(*key);
+            }
+        }
+        // Keys already down before they were bound, or before the first call
+        for (key, _) in self.bindings.iter().rev() {
+            if input.pressed(*key) && !held.contains(key) {
+                held.insert(0, *key);
+            }
         }
+        held.last()
+            .and_then(|key| self.direction(*key).map(|dir| (*key, dir)))
     }
 }
 
-fn grow(
-    commands: Commands,
-    last_tail_position: Res<LastTailPosition>,
-    mut segments: ResMut<SnakeSegments>,
-    mut growth_reader: EventReader<GrowthEvent>,
-) {
-    if growth_reader.read().next().is_some() {
-        if let Some(last_position) = last_tail_position.0 {
-            let segment = spawn_segment(commands, last_position);
-            segments.push(segment);
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (KeyCode::ArrowLeft, Direction::Left),
+                (KeyCode::ArrowRight, Direction::Right),
+                (KeyCode::ArrowDown, Direction::Down),
+                (KeyCode::ArrowUp, Direction::Up),
+                (KeyCode::KeyA, Direction::Left),
+                (KeyCode::KeyD, Direction::Right),
+                (KeyCode::KeyS, Direction::Down),
+                (KeyCode::KeyW, Direction::Up),
+            ],
         }
     }
 }
 
-fn game_over(
-    mut commands: Commands,
-    mut reader: EventReader<GameOverEvent>,
-    segment_resource: ResMut<SnakeSegments>,
-    food: Query<Entity, With<Food>>,
-    segments: Query<Entity, With<SnakeSegment>>,
-    heads: Query<Entity, With<SnakeHead>>,
+/// When a turn pressed by the player takes effect
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TurnTiming {
+    /// The turn applies to the next move, even if that move happens this frame.
+    /// This is the default as it keeps controls as responsive as possible.
+    #[default]
+    Immediate,
+    /// The direction of the imminent move is locked in and the turn applies to the
+    /// move after it. A press landing just before a tick boundary is never taken by
+    /// the move already underway.
+    NextTick,
+    /// Every distinct press is buffered and one turn is applied per move, so quick
+    /// presses such as Right, Up, Left produce a clean staircase instead of a missed
+    /// turn. Each buffered turn is checked against the turn queued before it.
+    Queued,
+}
+
+/// The most turns `TurnTiming::Queued` will buffer ahead of the snake
+const MAX_QUEUED_TURNS: usize = 3;
+
+/// How long a turn may wait in the buffer of the delayed `TurnTiming` modes before it
+/// is dropped, so a turn the player has since given up on is not applied late, such as
+/// one pressed during the countdown. Turns never go stale by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TurnStaleness {
+    /// The most movement ticks a buffered turn may wait. A tick is the current
+    /// movement interval, measured in play time whether or not the snake is moving.
+    pub ticks: Option<u32>,
+}
+
+/// Ages the buffered turns and drops those older than `TurnStaleness` allows
+fn expire_stale_turns(
+    time: Res<Time<Virtual>>,
+    timer: Res<MovementTimer>,
+    staleness: Res<TurnStaleness>,
+    mut heads: Query<&mut SnakeHead>,
 ) {
-    if reader.read().next().is_some() {
-        for ent in food.iter().chain(heads.iter()).chain(segments.iter()) {
-            commands.entity(ent).despawn();
+    let Some(ticks) = staleness.ticks else {
+        return;
+    };
+    let limit = timer.interval() * ticks;
+    for mut head in heads.iter_mut() {
+        for turn in head.turns.iter_mut() {
+            turn.age += time.delta();
         }
-        spawn_snake(commands, segment_resource);
+        head.turns.retain(|turn| turn.age <= limit);
     }
 }
 
-#[derive(Event)]
-struct GrowthEvent;
+/// Lets the player turn straight back while doing so cannot be fatal, which is whenever
+/// the snake can pass through its own body: in `GameMode::Peaceful`, and during the
+/// grace period after a respawn, see `Lives`. The usual guard against reversing is back
+/// as soon as that ends. Off by default, or on with the `--free-reverse` command line
+/// flag.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FreeReverse {
+    pub enabled: bool,
+}
 
-fn eater(
-    mut commands: Commands,
-    mut growth_writer: EventWriter<GrowthEvent>,
-    food_positions: Query<(Entity, &Position), With<Food>>,
-    head_positions: Query<&Position, With<SnakeHead>>,
+/// Decides whether the guard against reversing is lifted right now
+#[derive(SystemParam)]
+struct ReverseGuard<'w> {
+    free_reverse: Option<Res<'w, FreeReverse>>,
+    mode: Option<Res<'w, GameMode>>,
+    lives: Option<Res<'w, Lives>>,
+}
+
+impl ReverseGuard<'_> {
+    fn is_lifted(&self) -> bool {
+        let peaceful = self.mode.as_deref() == Some(&GameMode::Peaceful);
+        let invulnerable = self.lives.as_ref().is_some_and(|lives| lives.in_grace());
+        self.free_reverse.as_ref().is_some_and(|free| free.enabled) && (peaceful || invulnerable)
+    }
+}
+
+/// Makes a held direction key turn the snake only once, when it is pressed. Until it is
+/// released the key is ignored, so it cannot undo a turn made with another key, for
+/// example by taking over again after a tapped key is let go. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RepeatSuppression {
+    pub enabled: bool,
+}
+
+/// Challenge modifier that mirrors the direction keys, so Left turns the snake right
+/// and Up turns it down. Reversals are still checked against the mirrored direction.
+/// Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct MirrorControls {
+    pub enabled: bool,
+}
+
+/// The bound keys `handle_input` has seen go down and not yet come up
+#[derive(Default, Debug)]
+struct HeldKeys {
+    /// In the order they were pressed, as tracked by `KeyBindings::most_recent`
+    pressed: Vec<KeyCode>,
+    /// The keys that have already turned the snake, for `RepeatSuppression`
+    applied: Vec<KeyCode>,
+}
+
+/// Run condition that leaves the direction keys alone while the game is paused, so
+/// moving through the pause menu doesn't turn the snake
+fn game_running(time: Res<Time<Virtual>>) -> bool {
+    !time.is_paused()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_input(
+    input: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    timing: Res<TurnTiming>,
+    reverse: ReverseGuard,
+    suppression: Option<Res<RepeatSuppression>>,
+    mirror: Option<Res<MirrorControls>>,
+    mut dash: ResMut<Dash>,
+    mut rejected: EventWriter<ReverseRejected>,
+    mut held: Local<HeldKeys>,
+    mut heads: Query<&mut SnakeHead>,
 ) {
-    for head_pos in head_positions.iter() {
-        for (ent, food_pos) in food_positions.iter() {
-            if food_pos == head_pos {
-                commands.entity(ent).despawn();
-                growth_writer.send(GrowthEvent);
+    dash.held = false;
+    let allow_reverse = reverse.is_lifted();
+    let mirrored = mirror.is_some_and(|mirror| mirror.enabled);
+    let pressed = bindings
+        .most_recent(&input, &mut held.pressed)
+        .map(|(key, dir)| (key, if mirrored { dir.opposite() } else { dir }));
+    held.applied.retain(|key| input.pressed(*key));
+    let suppress = suppression.is_some_and(|suppression| suppression.enabled);
+    // A suppressed key still dashes, it just no longer turns
+    let turning = pressed.filter(|(key, _)| !(suppress && held.applied.contains(key)));
+    if let Some((key, _)) = turning {
+        held.applied.push(key);
+    }
+    for mut head in heads.iter_mut() {
+        if pressed.is_some_and(|(_, dir)| dir == head.direction) {
+            dash.held = true;
+        }
+        if let Some((key, dir)) = turning {
+            match *timing {
+                TurnTiming::Queued => {
+                    if input.just_pressed(key) {
+                        let last = head
+                            .turns
+                            .back()
+                            .map_or(head.direction, |turn| turn.direction);
+                        if dir == last.opposite() && !allow_reverse {
+                            rejected.send(ReverseRejected);
+                        } else if dir != last && head.turns.len() < MAX_QUEUED_TURNS {
+                            head.turns.push_back(QueuedTurn::new(dir));
+                        }
+                    }
+                }
+                _ if dir == head.last_moved.opposite() && !allow_reverse => {
+                    if input.just_pressed(key) {
+                        rejected.send(ReverseRejected);
+                    }
+                }
+                TurnTiming::Immediate => head.direction = dir,
+                TurnTiming::NextTick => {
+                    head.turns.clear();
+                    if dir != head.direction {
+                        head.turns.push_back(QueuedTurn::new(dir));
+                    }
+                }
             }
         }
     }
 }
 
-#[derive(Event)]
-struct GameOverEvent;
-
-pub struct SnakePlugin;
+/// Flashes the snake head when a reverse is rejected, if enabled in `ReverseFeedback`
+fn flash_rejected_reverse(
+    mut commands: Commands,
+    feedback: Res<ReverseFeedback>,
+    mut rejected: EventReader<ReverseRejected>,
+    mut heads: Query<(Entity, &mut Sprite), With<SnakeHead>>,
+) {
+    if rejected.read().count() == 0 || !feedback.enabled {
+        return;
+    }
+    for (entity, mut sprite) in heads.iter_mut() {
+        sprite.color = SNAKE_HEAD_FLASH_COLOR;
+        commands
+            .entity(entity)
+            .insert((HeadFlash, TimedEffect::from_seconds(feedback.duration)));
+    }
+}
 
-impl Plugin for SnakePlugin {
-    fn build(&self, app: &mut bevy::app::App) {
-        let timer = MovementTimer::from_seconds(0.150);
-        app.insert_resource(timer);
-        app.insert_resource(SnakeSegments::default());
-        app.insert_resource(LastTailPosition::default());
-        app.add_event::<GrowthEvent>();
-        app.add_event::<GameOverEvent>();
-        app.add_systems(Startup, spawn_snake);
-        app.add_systems(
-            Update,
-            (handle_input, movement, game_over, eater, grow).chain(),
-        );
+/// Restores the head color once its flash has run its course
+fn fade_head_flash(
+    mut commands: Commands,
+    colors: Option<Res<PlayerColors>>,
+    mut flashes: Query<(Entity, &TimedEffect, &mut Sprite, Option<&PlayerId>), With<HeadFlash>>,
+) {
+    for (entity, effect, mut sprite, player) in flashes.iter_mut() {
+        if effect.finished() {
+            sprite.color = colors_of(colors.as_deref(), player.copied().unwrap_or_default()).head;
+            commands.entity(entity).remove::<(HeadFlash, TimedEffect)>();
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use bevy::{ecs::system::SystemState, prelude::*};
+/// Settings for the flashes shown on both edges when the head wraps across the arena.
+/// On by default.
+#[derive(Resource, Clone, Debug)]
+pub struct WrapFlash {
+    pub enabled: bool,
+    /// How long the flashes take to fade out, in seconds
+    pub duration: f32,
+}
 
-    #[test]
-    fn test_basic_movement_keys() {
-        let mut app = App::new();
-        app.add_plugins(MinimalPlugins);
-        app.insert_resource(MovementTimer::from_seconds(0.150));
-        app.insert_resource(SnakeSegments::default());
-        app.insert_resource(LastTailPosition::default());
-        app.add_event::<GameOverEvent>();
+impl Default for WrapFlash {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            duration: 0.3,
+        }
+    }
+}
 
-        let snake_entity = app
-            .world_mut()
-            .spawn((SnakeHead::default(), Position { x: 3, y: 3 }))
-            .id();
+const WRAP_FLASH_COLOR: Color = Color::srgb(0.55, 0.85, 1.0);
 
-        let test_cases = [
-            (KeyCode::ArrowLeft, Position { x: 2, y: 3 }),
-            (KeyCode::ArrowRight, Position { x: 4, y: 3 }),
-            (KeyCode::ArrowDown, Position { x: 3, y: 4 }), // snake should not move because it's already going up
-            (KeyCode::ArrowUp, Position { x: 3, y: 4 }),
-            (KeyCode::KeyA, Position { x: 2, y: 3 }),
-            (KeyCode::KeyD, Position { x: 4, y: 3 }),
-            (KeyCode::KeyW, Position { x: 3, y: 4 }), // snake should not move because it's already going up
-            (KeyCode::KeyW, Position { x: 3, y: 4 }),
-        ];
+/// Drawn above the board but below the snake
+const WRAP_FLASH_Z: f32 = -0.5;
 
-        for (key, expected_position) in test_cases {
-            // Reset position
-            let world = app.world_mut();
-            *world.get_mut::<Position>(snake_entity).unwrap() = Position { x: 3, y: 3 };
-            *world.get_mut::<SnakeHead>(snake_entity).unwrap() = SnakeHead::default();
+/// A fading flash on a tile the head wrapped out of or into
+#[derive(Component)]
+struct EdgeFlash;
 
-            // Simulate key press and direction change
-            let mut input = ButtonInput::<KeyCode>::default();
-            input.press(key);
-            app.insert_resource(input);
+/// Flashes the tiles on both sides of every wrap
+fn spawn_wrap_flashes(
+    mut commands: Commands,
+    settings: Res<WrapFlash>,
+    arena: Res<ArenaConfig>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut wraps: EventReader<WrapEvent>,
+) {
+    let Ok(window) = window.get_single() else {
+        wraps.clear();
+        return;
+    };
+    let tile = arena::tile_size(window.size(), &arena);
+    for wrap in wraps.read() {
+        if !settings.enabled {
+            continue;
+        }
+        for pos in [wrap.from, wrap.to] {
+            let center = arena::grid_to_world(pos, window.size(), &arena);
+            commands.spawn((
+                Sprite::from_color(WRAP_FLASH_COLOR, Vec2::ONE),
+                Transform::from_translation(center.extend(WRAP_FLASH_Z))
+                    .with_scale(tile.extend(1.)),
+                EdgeFlash,
+                TimedEffect::from_seconds(settings.duration),
+            ));
+        }
+    }
+}
 
-            let mut world = app.world_mut();
-            let mut input_state: SystemState<(Res<ButtonInput<KeyCode>>, Query<&mut SnakeHead>)> =
-                SystemState::new(&mut world);
-            let (input, heads) = input_state.get_mut(&mut world);
-            handle_input(input, heads);
+/// Fades the wrap flashes out, then despawns them
+fn fade_wrap_flashes(
+    mut commands: Commands,
+    mut flashes: Query<(Entity, &TimedEffect, &mut Sprite), With<EdgeFlash>>,
+) {
+    for (entity, effect, mut sprite) in flashes.iter_mut() {
+        if effect.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        sprite.color = WRAP_FLASH_COLOR.with_alpha(1. - effect.fraction());
+    }
+}
 
-            // Simulate movement
-            let mut world = app.world_mut();
-            let mut system_state: SystemState<(
-                Res<Time>,
-                ResMut<MovementTimer>,
-                ResMut<SnakeSegments>,
-                ResMut<LastTailPosition>,
-                Query<(Entity, &SnakeHead)>,
-                Query<&mut Position>,
-                EventWriter<GameOverEvent>,
-            )> = SystemState::new(&mut world);
-            let (time, mut timer, segments, last_tail, heads, positions, game_over) =
-                system_state.get_mut(&mut world);
+/// The default cap on how many movement steps may be processed in a single frame
+const MAX_STEPS_PER_FRAME: u32 = 4;
 
-            // Ensure timer finishes
-            let duration = timer.clock.duration();
-            timer.clock.set_elapsed(duration);
-            movement(
-                time, timer, segments, last_tail, heads, positions, game_over,
-            );
+/// Paces the snake. Each time the clock finishes the snake advances one tile.
+#[derive(Resource)]
+pub struct MovementTimer {
+    clock: Timer,
+    /// Upper bound on the steps taken in one frame when a long frame covers several ticks.
+    /// Keeps a severe hitch from turning into a burst of moves the player cannot react to.
+    max_steps_per_frame: u32,
+}
 
-            // Check position
-            let position = app.world_mut().get::<Position>(snake_entity).unwrap();
-            assert_eq!(*position, expected_position, "the position of the snake was not in the right place after pressing the {:?} button", key);
+impl MovementTimer {
+    fn from_seconds(secs: f32) -> Self {
+        Self {
+            clock: Timer::from_seconds(secs, TimerMode::Repeating),
+            max_steps_per_frame: MAX_STEPS_PER_FRAME,
         }
     }
 
-    #[test]
-    fn test_snake_movement_sequence() {
-        let mut app = App::new();
-        app.add_plugins(MinimalPlugins);
+    /// The current time between movement ticks
+    pub fn interval(&self) -> Duration {
+        self.clock.duration()
+    }
+}
+
+/// The number of entries in `SnakeSegments` for a freshly spawned snake
+const STARTING_LENGTH: usize = 2;
+
+/// How the movement interval shrinks as the snake grows
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpeedCurve {
+    /// The snake keeps its starting speed regardless of length
+    Constant,
+    /// Each segment grown takes `step` seconds off the interval
+    Linear { step: f32 },
+    /// Each segment grown multiplies the interval by `factor`
+    Geometric { factor: f32 },
+    /// Explicit intervals in seconds keyed by snake length. The entry with the largest
+    /// length not exceeding the current length applies; below the first entry the
+    /// base interval is used.
+    Table(Vec<(usize, f32)>),
+}
+
+/// Shrinks the movement interval on a fixed schedule of elapsed play time
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeSchedule {
+    /// Seconds of play between each speed-up
+    pub every: f32,
+    /// The interval is multiplied by this factor at each speed-up
+    pub factor: f32,
+}
+
+/// Controls how the movement tick interval evolves as the snake gets longer and,
+/// optionally, as play time passes.
+///
+/// The length curve and the time schedule compose multiplicatively: the interval
+/// from the curve is scaled by the time schedule, then clamped to `min`.
+///
+/// Insert this resource before adding `SnakePlugin` to pick a different ramp,
+/// otherwise the ramp for the current `Difficulty` is used.
+#[derive(Resource, Clone, Debug)]
+pub struct SpeedRamp {
+    /// The interval in seconds for a freshly spawned snake
+    pub base: f32,
+    /// The interval in seconds will never drop below this value
+    pub min: f32,
+    pub curve: SpeedCurve,
+    pub over_time: Option<TimeSchedule>,
+}
+
+impl SpeedRamp {
+    /// The tick interval in seconds for a snake of the given length after `elapsed` play time
+    pub fn interval(&self, length: usize, elapsed: Duration) -> f32 {
+        let grown = length.saturating_sub(STARTING_LENGTH);
+        let secs = match &self.curve {
+            SpeedCurve::Constant => self.base,
+            SpeedCurve::Linear { step } => self.base - step * grown as f32,
+            SpeedCurve::Geometric { factor } => self.base * factor.powi(grown as i32),
+            SpeedCurve::Table(entries) => entries
+                .iter()
+                .filter(|(at, _)| *at <= length)
+                .max_by_key(|(at, _)| *at)
+                .map_or(self.base, |(_, secs)| *secs),
+        };
+        let scale = self.over_time.as_ref().map_or(1.0, |schedule| {
+            let steps = (elapsed.as_secs_f32() / schedule.every).floor();
+            schedule.factor.powi(steps as i32)
+        });
+        (secs * scale).max(self.min)
+    }
+}
+
+impl Default for SpeedRamp {
+    fn default() -> Self {
+        Self {
+            base: 0.150,
+            min: 0.050,
+            curve: SpeedCurve::Constant,
+            over_time: None,
+        }
+    }
+}
+
+/// A named difficulty preset that picks the default `SpeedRamp`
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// The speed ramp used for this difficulty unless a `SpeedRamp` is inserted explicitly
+    pub fn speed_ramp(self) -> SpeedRamp {
+        match self {
+            Difficulty::Easy => SpeedRamp {
+                base: 0.200,
+                ..Default::default()
+            },
+            Difficulty::Normal => SpeedRamp::default(),
+            Difficulty::Hard => SpeedRamp {
+                base: 0.110,
+                curve: SpeedCurve::Linear { step: 0.002 },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The total number of moves the snake has made since the game started
+#[derive(Resource, Default)]
+pub struct MoveCount(u64);
+
+impl MoveCount {
+    pub fn total(&self) -> u64 {
+        self.0
+    }
+
+    pub fn increment(&mut self) {
+        self.0 += 1;
+    }
+}
+
+/// The time spent playing the current run, excluding time spent paused
+#[derive(Resource, Default)]
+pub struct PlayClock(Duration);
+
+impl PlayClock {
+    pub fn elapsed(&self) -> Duration {
+        self.0
+    }
+}
+
+fn tick_play_clock(time: Res<Time<Virtual>>, mut clock: ResMut<PlayClock>) {
+    clock.0 += time.delta();
+}
+
+/// Updates the movement interval from the `SpeedRamp` as the snake changes length
+/// and as play time passes
+fn ramp_speed(
+    ramp: Res<SpeedRamp>,
+    clock: Res<PlayClock>,
+    segments: Res<SnakeSegments>,
+    mut timer: ResMut<MovementTimer>,
+) {
+    let interval = Duration::from_secs_f32(ramp.interval(segments.len(), clock.elapsed()));
+    if timer.clock.duration() != interval {
+        timer.clock.set_duration(interval);
+    }
+}
+
+/// The settings that decide how the snake moves and what it runs into
+#[derive(SystemParam)]
+struct MovementRules<'w, 's> {
+    arena: Res<'w, ArenaConfig>,
+    assist: Res<'w, WallAssist>,
+    wrap: Res<'w, EdgeWrap>,
+    timing: Res<'w, TurnTiming>,
+    body_movement: Res<'w, BodyMovement>,
+    tiles: Res<'w, TileEffects>,
+    tail_chase: Res<'w, TailChase>,
+    free_reverse: Option<Res<'w, FreeReverse>>,
+    slowdown: Option<Res<'w, WallSlowdown>>,
+    pending_growth: Option<Res<'w, PendingGrowth>>,
+    footprints: Query<'w, 's, &'static FootprintSize, With<SnakeHead>>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn movement(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut timer: ResMut<MovementTimer>,
+    mut dash: ResMut<Dash>,
+    rules: MovementRules,
+    mut path: ResMut<BodyPath>,
+    mut moves: ResMut<MoveCount>,
+    segments: ResMut<SnakeSegments>,
+    mut last_tail_position: ResMut<LastTailPosition>,
+    mut heads: Query<(Entity, &mut SnakeHead)>,
+    mut positions: Query<&mut Position>,
+    mut deaths: Deaths,
+    mut ticks: EventWriter<TickEvent>,
+    mut wraps: EventWriter<WrapEvent>,
+    mut events: EventWriter<GameEvent>,
+) {
+    // `SnakeSegments` describes a single snake, so exactly one head is expected. A stray
+    // extra head is a bug; debug builds fail loudly and release builds move the first.
+    let mut heads = heads.iter_mut();
+    let Some((head_entity, mut head)) = heads.next() else {
+        timer.clock.tick(time.delta().mul_f32(dash.multiplier()));
+        return;
+    };
+    debug_assert!(
+        heads.next().is_none(),
+        "movement expects a single SnakeHead but found {}",
+        heads.len() + 2
+    );
+    let wrap = if deaths.is_peaceful() {
+        EdgeWrap {
+            wrap_x: true,
+            wrap_y: true,
+        }
+    } else {
+        *rules.wrap
+    };
+    let slowdown = match (&rules.slowdown, positions.get(head_entity)) {
+        (Some(slowdown), Ok(head_pos)) => {
+            slowdown.interval_scale(*head_pos, head.direction, &rules.arena, wrap)
+        }
+        _ => 1.0,
+    };
+    let delta = time.delta().mul_f32(dash.multiplier()).div_f32(slowdown);
+    let steps = timer
+        .clock
+        .tick(delta)
+        .times_finished_this_tick()
+        .min(timer.max_steps_per_frame);
+    let head_size = rules
+        .footprints
+        .get(head_entity)
+        .copied()
+        .unwrap_or_default();
+    let allow_reverse = rules.free_reverse.is_some_and(|free| free.enabled)
+        && (deaths.is_peaceful() || deaths.is_invulnerable());
+    // A delayed segment due on the next tick fills the tile the tail leaves
+    let growing = rules
+        .pending_growth
+        .as_ref()
+        .is_some_and(|pending| pending.0.front().is_some_and(|left| *left <= 1));
+    let tail_leaving = rules.tail_chase.enabled && !growing;
+    // Dashed tiles come before the timed steps and keep to the current direction. Every
+    // tile is stepped onto, so walls and the body are still checked along the way, but
+    // only the tile the dash ends on can be eaten from.
+    let dashed = mem::take(&mut dash.pending);
+    for index in 0..dashed + steps {
+        let dashing = index < dashed;
+        if !dashing && *rules.timing == TurnTiming::Queued {
+            head.apply_turn(allow_reverse);
+        }
+        let from = positions.get(head_entity).ok().copied();
+        let death = step(
+            head_entity,
+            &mut head,
+            &rules.arena,
+            &rules.assist,
+            wrap,
+            head_size,
+            *rules.body_movement,
+            tail_leaving,
+            &mut path,
+            &segments,
+            &mut last_tail_position,
+            &mut positions,
+        );
+        moves.increment();
+        let head_pos = positions.get(head_entity).ok().copied();
+        if let (Some(from), Some(to)) = (from, head_pos) {
+            if to != from.neighbor(head.last_moved) {
+                wraps.send(WrapEvent { from, to });
+            }
+        }
+        let death = death.or_else(|| {
+            let tile = head_pos?;
+            let outcome = rules.tiles.enter(tile, moves.total(), &mut commands);
+            (outcome == TileOutcome::Kill).then_some(DeathCause::Tile)
+        });
+        // A freshly respawned snake can pass through its body and deadly tiles, but
+        // never through a wall. Nothing is lethal in peaceful mode.
+        let death = death
+            .filter(|cause| *cause == DeathCause::Wall || !deaths.is_invulnerable())
+            .filter(|_| !deaths.is_peaceful());
+        deaths.tick();
+        match (death, head_pos) {
+            (Some(cause), _) => {
+                deaths.crash();
+                events.send(GameEvent::Died { cause });
+            }
+            (None, Some(head)) => {
+                events.send(GameEvent::Moved { head });
+            }
+            (None, None) => {}
+        }
+        ticks.send(TickEvent {
+            tick: moves.total(),
+            direction: head.last_moved,
+        });
+        if !dashing && *rules.timing == TurnTiming::NextTick {
+            head.apply_turn(allow_reverse);
+        }
+        if death.is_some() {
+            break;
+        }
+    }
+}
+
+/// How the body follows the head
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BodyMovement {
+    /// Each segment moves onto the tile the segment ahead of it just left
+    #[default]
+    TailFollow,
+    /// The body shifts as a unit along the path the head has traced, with `spacing`
+    /// tiles of path between neighbouring segments. With a spacing of 1 this matches
+    /// `TailFollow` exactly.
+    Rigid { spacing: usize },
+}
+
+/// The tiles the head has left behind, most recent first, used by `BodyMovement::Rigid`
+#[derive(Resource, Default, Debug)]
+pub(crate) struct BodyPath(VecDeque<Position>);
+
+/// Accessibility assist that eases the snake up right before a wall, giving the player
+/// more time to turn. While the head is next to a wall and heading into it, the tick
+/// takes twice as long. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct WallSlowdown {
+    pub enabled: bool,
+}
+
+impl WallSlowdown {
+    /// How many times longer than usual the next tick takes for a head at `head`
+    /// heading in `direction`
+    pub fn interval_scale(
+        &self,
+        head: Position,
+        direction: Direction,
+        arena: &ArenaConfig,
+        wrap: EdgeWrap,
+    ) -> f32 {
+        let ahead = wrap.apply(head.neighbor(direction), arena);
+        if self.enabled && !arena::in_bounds(ahead, arena) {
+            2.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Accessibility assist that steers the snake along a wall instead of letting it
+/// run into it. Off by default.
+#[derive(Resource, Default)]
+pub struct WallAssist {
+    pub enabled: bool,
+}
+
+/// Lets the head move onto the tile the tail is leaving on the same tick, so chasing
+/// the tail closely is not a collision. The tile only counts as free when the snake
+/// is not about to grow, as a new segment fills the tile the tail left. On by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TailChase {
+    pub enabled: bool,
+}
+
+impl Default for TailChase {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// The width and height of the snake head in tiles, for big snake variants.
+///
+/// The head is anchored at its bottom left tile and still moves a tile at a time,
+/// but the whole block counts for walls, for running into the body and for eating.
+/// The rest of the body stays one tile wide. A single tile by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HeadSize {
+    pub tiles: i32,
+}
+
+impl HeadSize {
+    pub fn footprint(self) -> FootprintSize {
+        FootprintSize::square(self.tiles.max(1))
+    }
+}
+
+impl Default for HeadSize {
+    fn default() -> Self {
+        Self { tiles: 1 }
+    }
+}
+
+/// Which arena edges the snake passes through instead of dying on. Leaving across a
+/// wrapped axis brings the head back in on the opposite edge. Both axes are walls by
+/// default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct EdgeWrap {
+    /// Wrap between the left and right edges
+    pub wrap_x: bool,
+    /// Wrap between the top and bottom edges
+    pub wrap_y: bool,
+}
+
+impl EdgeWrap {
+    /// Brings `pos` back inside the arena along every wrapped axis, onto the tiles
+    /// left within `ArenaConfig::inset`
+    pub fn apply(self, pos: Position, arena: &ArenaConfig) -> Position {
+        let wrap = |value: i32, len: i32| {
+            let span = (len - 2 * arena.inset).max(1);
+            arena.inset + (value - arena.inset).rem_euclid(span)
+        };
+        Position {
+            x: if self.wrap_x {
+                wrap(pos.x, arena.width)
+            } else {
+                pos.x
+            },
+            y: if self.wrap_y {
+                wrap(pos.y, arena.height)
+            } else {
+                pos.y
+            },
+        }
+    }
+}
+
+/// Picks a turn that keeps the snake from leaving the arena at `pos` while heading in `dir`.
+///
+/// Only the two perpendicular turns are considered, and a turn is only legal if it stays in
+/// bounds and clear of `body`. When both are legal the one with more room before the next
+/// wall wins, and a tie goes to the clockwise turn.
+fn wall_turn(
+    pos: Position,
+    dir: Direction,
+    arena: &ArenaConfig,
+    body: &[Position],
+) -> Option<Direction> {
+    let room = |turn: Direction| {
+        let mut tile = pos.neighbor(turn);
+        let mut tiles = 0;
+        while arena::in_bounds(tile, arena) {
+            tiles += 1;
+            tile = tile.neighbor(turn);
+        }
+        tiles
+    };
+    [dir.counter_clockwise(), dir.clockwise()]
+        .into_iter()
+        .filter(|turn| {
+            let next = pos.neighbor(*turn);
+            arena::in_bounds(next, arena) && !body.contains(&next)
+        })
+        .max_by_key(|turn| room(*turn))
+}
+
+/// Advances the snake by a single tile in the direction the head is facing.
+///
+/// Returns what killed the snake if the move ended the game, either leaving the arena
+/// across an edge that does not wrap or the head running into the body.
+#[allow(clippy::too_many_arguments)]
+fn step(
+    head_entity: Entity,
+    head: &mut SnakeHead,
+    arena: &ArenaConfig,
+    assist: &WallAssist,
+    wrap: EdgeWrap,
+    head_size: FootprintSize,
+    body_movement: BodyMovement,
+    tail_leaving: bool,
+    path: &mut BodyPath,
+    segments: &SnakeSegments,
+    last_tail_position: &mut LastTailPosition,
+    positions: &mut Query<&mut Position>,
+) -> Option<DeathCause> {
+    let segment_positions: Vec<Position> = segments
+        .iter()
+        .filter_map(|e| positions.get_mut(*e).ok().map(|p| *p))
+        .collect();
+    if segment_positions.len() != segments.len() {
+        // Some segments were missing positions, exit early
+        return None;
+    }
+    let mut death = None;
+    if let Ok(mut head_pos) = positions.get_mut(head_entity) {
+        let mut next = wrap.apply(head_pos.neighbor(head.direction), arena);
+        if assist.enabled && !arena::in_bounds(next, arena) {
+            if let Some(turn) = wall_turn(*head_pos, head.direction, arena, &segment_positions) {
+                head.direction = turn;
+                next = head_pos.neighbor(turn);
+            }
+        }
+        *head_pos = next;
+        head.last_moved = head.direction;
+
+        let head_tiles: Vec<Position> = head_size
+            .tiles(*head_pos)
+            .map(|tile| wrap.apply(tile, arena))
+            .collect();
+        if !head_tiles.iter().all(|tile| arena::in_bounds(*tile, arena)) {
+            death = Some(DeathCause::Wall);
+        }
+
+        // A big head always overlaps the segments just behind it, so only the body
+        // past that neck counts
+        let neck = (head_size.width.max(head_size.height) - 1).max(0) as usize;
+        let mut body = &segment_positions[neck.min(segment_positions.len())..];
+        // The tail moves off its tile on this same tick
+        if tail_leaving && segment_positions.len() > 1 {
+            body = &body[..body.len().saturating_sub(1)];
+        }
+        if head_tiles.iter().any(|tile| body.contains(tile)) {
+            death = death.or(Some(DeathCause::Body));
+        }
+    }
+
+    match body_movement {
+        BodyMovement::TailFollow => segment_positions
+            .iter()
+            .zip(segments.iter().skip(1))
+            .for_each(|(pos, segment)| {
+                if let Ok(mut position) = positions.get_mut(*segment) {
+                    *position = *pos
+                }
+            }),
+        BodyMovement::Rigid { spacing } => {
+            let spacing = spacing.max(1);
+            if path.0.is_empty() {
+                path.0.extend(segment_positions.iter().skip(1));
+            }
+            path.0.push_front(segment_positions[0]);
+            path.0.truncate(segments.len() * spacing);
+            for (index, segment) in segments.iter().enumerate().skip(1) {
+                let trail = path.0.get(index * spacing - 1);
+                if let (Some(trail), Ok(mut position)) = (trail, positions.get_mut(*segment)) {
+                    *position = *trail;
+                }
+            }
+        }
+    }
+
+    if let Some(last_segment) = segment_positions.last() {
+        *last_tail_position = LastTailPosition(Some(*last_segment));
+    }
+    death
+}
+
+/// The goal of a run
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    /// Play until the snake dies
+    #[default]
+    Classic,
+    /// The run is won once the snake, head included, reaches the given length
+    ReachLength(usize),
+    /// Play until the snake dies while uneaten food drifts one tile toward the center
+    /// of the arena every `every_ticks` movement ticks
+    Gravity { every_ticks: u64 },
+    /// Play until the snake dies on a board scattered with walls, with `density`
+    /// percent of the tiles walled off. A new layout is generated for every board.
+    Procedural { density: u32 },
+    /// A sandbox where nothing is lethal. Every edge wraps, the snake passes through
+    /// walls, its own body and deadly tiles, and the run never ends.
+    Peaceful,
+}
+
+/// Sent when the snake meets the win condition of the current `GameMode`
+#[derive(Event)]
+pub struct GameWonEvent;
+
+/// Caps how long the snake can grow, useful for profiling at a fixed length.
+/// Unbounded by default.
+#[derive(Resource, Default)]
+pub struct GrowthLimit {
+    /// The maximum number of entries in `SnakeSegments`, head included
+    pub max_segments: Option<usize>,
+}
+
+/// How many movement ticks a new segment takes to appear after food is eaten.
+///
+/// With a delay the segment is added once the tail has moved on, emerging from the
+/// tile the tail just left rather than appearing on the growth tick. Zero, the
+/// default, adds the segment straight away.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct GrowthDelay {
+    pub ticks: u32,
+}
+
+/// Segments still waiting out their `GrowthDelay`, as the number of ticks left for each
+#[derive(Resource, Default, Debug)]
+struct PendingGrowth(VecDeque<u32>);
+
+#[allow(clippy::too_many_arguments)]
+fn grow(
+    mut commands: Commands,
+    assets: Option<Res<SpriteAssets>>,
+    colors: Option<Res<PlayerColors>>,
+    mode: Res<GameMode>,
+    limit: Res<GrowthLimit>,
+    delay: Res<GrowthDelay>,
+    mut pending: ResMut<PendingGrowth>,
+    last_tail_position: Res<LastTailPosition>,
+    mut segments: ResMut<SnakeSegments>,
+    mut growth_reader: EventReader<GrowthEvent>,
+    mut ticks: EventReader<TickEvent>,
+    mut won: EventWriter<GameWonEvent>,
+    mut events: EventWriter<GameEvent>,
+    players: Query<&PlayerId, With<SnakeHead>>,
+) {
+    let elapsed = ticks.read().count() as u32;
+    for left in pending.0.iter_mut() {
+        *left = left.saturating_sub(elapsed);
+    }
+    // Only one segment is added per frame so that two never share the tile the tail left
+    let ready = pending.0.front() == Some(&0);
+    if ready {
+        pending.0.pop_front();
+    }
+    let grew = growth_reader.read().next().is_some();
+    if grew && delay.ticks > 0 {
+        pending.0.push_back(delay.ticks);
+    }
+    if !(ready || grew && delay.ticks == 0) {
+        return;
+    }
+    if limit.max_segments.is_some_and(|max| segments.len() >= max) {
+        return;
+    }
+    if let Some(last_position) = last_tail_position.0 {
+        let player = players.get_single().copied().unwrap_or_default();
+        let segment = spawn_segment(
+            &mut commands,
+            assets.as_deref(),
+            colors.as_deref(),
+            player,
+            last_position,
+        );
+        segments.push(segment);
+        events.send(GameEvent::Grew);
+        if let GameMode::ReachLength(target) = *mode {
+            if segments.len() == target {
+                won.send(GameWonEvent);
+                events.send(GameEvent::Won);
+            }
+        }
+    }
+}
+
+/// Keeps the tail style on the last entity in `SnakeSegments`.
+///
+/// Runs after the snake has moved and grown so the marker follows the end of the
+/// snake as segments are added or removed. Whichever segment previously carried
+/// the tail style is restored to the regular segment color.
+#[allow(clippy::type_complexity)]
+fn mark_tail(
+    mut commands: Commands,
+    segments: Res<SnakeSegments>,
+    colors: Option<Res<PlayerColors>>,
+    mut sprites: Query<
+        (Entity, &mut Sprite, Has<SnakeTail>, Option<&PlayerId>),
+        With<SnakeSegment>,
+    >,
+) {
+    let tail = segments.0.last().copied();
+    for (entity, mut sprite, is_tail, player) in sprites.iter_mut() {
+        let colors = colors_of(colors.as_deref(), player.copied().unwrap_or_default());
+        if Some(entity) == tail {
+            if !is_tail {
+                commands.entity(entity).insert(SnakeTail);
+                sprite.color = colors.tail;
+            }
+        } else if is_tail {
+            commands.entity(entity).remove::<SnakeTail>();
+            // Textured segments are drawn untinted
+            sprite.color = if sprite.image == Handle::default() {
+                colors.body
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
+
+/// Repaints the snake when the player colors change, as they do with the theme.
+/// Textured sprites are left untinted and a flashing head keeps its flash.
+#[allow(clippy::type_complexity)]
+fn recolor_snake(
+    colors: Res<PlayerColors>,
+    mut heads: Query<(&mut Sprite, Option<&PlayerId>), (With<SnakeHead>, Without<HeadFlash>)>,
+    mut segments: Query<
+        (&mut Sprite, Option<&PlayerId>, Has<SnakeTail>),
+        (With<SnakeSegment>, Without<SnakeHead>),
+    >,
+) {
+    for (mut sprite, player) in heads.iter_mut() {
+        if sprite.image == Handle::default() {
+            sprite.color = colors.of(player.copied().unwrap_or_default()).head;
+        }
+    }
+    for (mut sprite, player, is_tail) in segments.iter_mut() {
+        if sprite.image == Handle::default() {
+            let colors = colors.of(player.copied().unwrap_or_default());
+            sprite.color = if is_tail { colors.tail } else { colors.body };
+        }
+    }
+}
+
+/// Thins the body toward the tail. Off by default, or on with the `--taper` command
+/// line flag.
+///
+/// When enabled the segment behind the head is drawn at full size and each segment
+/// after it a little smaller, down to `tail_scale` for the tail.
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct BodyTaper {
+    pub enabled: bool,
+    /// The size of the tail relative to a full segment
+    pub tail_scale: f32,
+}
+
+impl Default for BodyTaper {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tail_scale: 0.6,
+        }
+    }
+}
+
+impl BodyTaper {
+    /// The size modifier of the segment at `index` in `SnakeSegments`, where the
+    /// head is at index 0 and the tail at `len - 1`
+    pub fn modifier(&self, index: usize, len: usize) -> f32 {
+        let body = len.saturating_sub(1);
+        if index == 0 || body == 0 {
+            return 1.0;
+        }
+        // A lone segment behind the head is the tail
+        let along = if body == 1 {
+            1.0
+        } else {
+            (index - 1) as f32 / (body - 1) as f32
+        };
+        1.0 - (1.0 - self.tail_scale) * along
+    }
+}
+
+/// How the body is drawn. Only the look changes: every segment still occupies its
+/// tile and collides as usual. `Solid` by default, or `Dotted { every: 2 }` with the
+/// `--dotted` command line flag.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BodyStyle {
+    /// Every segment is drawn
+    #[default]
+    Solid,
+    /// Only one segment in every `every` is drawn, counting from the head, which
+    /// gives a beaded snake. The head is always drawn.
+    Dotted { every: usize },
+}
+
+impl BodyStyle {
+    /// Whether the segment at `index` in `SnakeSegments` is drawn, where the head is
+    /// at index 0
+    pub fn is_drawn(self, index: usize) -> bool {
+        match self {
+            BodyStyle::Solid => true,
+            BodyStyle::Dotted { every } => index.is_multiple_of(every.max(1)),
+        }
+    }
+}
+
+/// Shows or hides each segment according to `BodyStyle`
+fn style_body(
+    style: Res<BodyStyle>,
+    segments: Res<SnakeSegments>,
+    mut visibilities: Query<&mut Visibility>,
+) {
+    for (index, entity) in segments.iter().enumerate() {
+        let Ok(mut visibility) = visibilities.get_mut(*entity) else {
+            continue;
+        };
+        let wanted = if style.is_drawn(index) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+/// Sizes each segment by its place in the body according to `BodyTaper`
+fn taper_body(
+    mut commands: Commands,
+    taper: Res<BodyTaper>,
+    segments: Res<SnakeSegments>,
+    mut modifiers: Query<Option<&mut SizeModifier>, With<SnakeSegment>>,
+) {
+    for (index, entity) in segments.iter().enumerate() {
+        let Ok(modifier) = modifiers.get_mut(*entity) else {
+            continue;
+        };
+        match (taper.enabled, modifier) {
+            (true, Some(mut modifier)) => {
+                modifier.0 = taper.modifier(index, segments.len());
+            }
+            (true, None) => {
+                let modifier = SizeModifier(taper.modifier(index, segments.len()));
+                commands.entity(*entity).insert(modifier);
+            }
+            (false, Some(_)) => {
+                commands.entity(*entity).remove::<SizeModifier>();
+            }
+            (false, None) => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn game_over(
+    mut commands: Commands,
+    mut reader: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+    mut clock: ResMut<PlayClock>,
+    mut timer: ResMut<MovementTimer>,
+    mut path: ResMut<BodyPath>,
+    mut pending: ResMut<PendingGrowth>,
+    start: Res<SnakeStart>,
+    arena: Res<ArenaConfig>,
+    wrap: Res<EdgeWrap>,
+    head_size: Res<HeadSize>,
+    assets: Option<Res<SpriteAssets>>,
+    colors: Option<Res<PlayerColors>>,
+    segment_resource: ResMut<SnakeSegments>,
+    board: Query<Entity, Or<(With<Food>, With<SnakeHead>, With<SnakeSegment>)>>,
+) {
+    // Read both so neither event lingers into the next frame
+    let died = reader.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if died || reset {
+        // Despawn everything in a single command rather than one per entity, which adds
+        // up for a long snake.
+        let entities: Vec<Entity> = board.iter().collect();
+        commands.queue(move |world: &mut World| {
+            for ent in entities {
+                world.despawn(ent);
+            }
+        });
+        *clock = PlayClock::default();
+        timer.clock.reset();
+        path.0.clear();
+        pending.0.clear();
+        spawn_snake(
+            commands,
+            start,
+            arena,
+            wrap,
+            head_size,
+            assets,
+            colors,
+            segment_resource,
+        );
+    }
+}
+
+/// Puts the snake back at its start after it loses a life, leaving the food as it is
+#[allow(clippy::too_many_arguments)]
+fn respawn(
+    mut commands: Commands,
+    mut life_lost: EventReader<LifeLostEvent>,
+    mut timer: ResMut<MovementTimer>,
+    mut path: ResMut<BodyPath>,
+    mut pending: ResMut<PendingGrowth>,
+    start: Res<SnakeStart>,
+    arena: Res<ArenaConfig>,
+    wrap: Res<EdgeWrap>,
+    head_size: Res<HeadSize>,
+    assets: Option<Res<SpriteAssets>>,
+    colors: Option<Res<PlayerColors>>,
+    segment_resource: ResMut<SnakeSegments>,
+    segments: Query<Entity, With<SnakeSegment>>,
+    heads: Query<Entity, With<SnakeHead>>,
+) {
+    if life_lost.read().count() == 0 {
+        return;
+    }
+    let entities: Vec<Entity> = heads.iter().chain(segments.iter()).collect();
+    commands.queue(move |world: &mut World| {
+        for ent in entities {
+            world.despawn(ent);
+        }
+    });
+    timer.clock.reset();
+    path.0.clear();
+    pending.0.clear();
+    spawn_snake(
+        commands,
+        start,
+        arena,
+        wrap,
+        head_size,
+        assets,
+        colors,
+        segment_resource,
+    );
+}
+
+/// Gives every life back once a run ends
+fn restore_lives(
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+    mut lives: ResMut<Lives>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if died || reset {
+        *lives = Lives::new(lives.per_run);
+    }
+}
+
+/// Removes everything the game has spawned and returns its state to the start of a
+/// fresh game, so the game can be stopped cleanly inside a larger app.
+///
+/// Every entity placed on the board, along with any running effect, is despawned and
+/// the run state is reset. Nothing is respawned, so it suits being scheduled when
+/// leaving the game, for example `app.add_systems(OnExit(MyState::Playing), despawn_game)`.
+pub fn despawn_game(world: &mut World) {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Position>, With<TimedEffect>)>>()
+        .iter(world)
+        .collect();
+    for entity in entities {
+        world.despawn(entity);
+    }
+    world.insert_resource(SnakeSegments::default());
+    world.insert_resource(LastTailPosition::default());
+    if let Some(mut path) = world.get_resource_mut::<BodyPath>() {
+        path.0.clear();
+    }
+    if let Some(mut pending) = world.get_resource_mut::<PendingGrowth>() {
+        pending.0.clear();
+    }
+    if let Some(mut clock) = world.get_resource_mut::<PlayClock>() {
+        *clock = PlayClock::default();
+    }
+    if let Some(mut moves) = world.get_resource_mut::<MoveCount>() {
+        *moves = MoveCount::default();
+    }
+    if let Some(mut score) = world.get_resource_mut::<Score>() {
+        score.current = 0;
+    }
+    if let Some(mut combo) = world.get_resource_mut::<Combo>() {
+        *combo = Combo::default();
+    }
+    if let Some(mut players) = world.get_resource_mut::<PlayerScores>() {
+        players.clear();
+    }
+    if let Some(mut lives) = world.get_resource_mut::<Lives>() {
+        *lives = Lives::new(lives.per_run);
+    }
+    food::clear_food(world);
+}
+
+/// Send to restart the run from a clean state without it counting as a death.
+///
+/// The board is cleared and the snake respawned just as after a `GameOverEvent`, the
+/// score of the run is discarded without being compared against the best, and the
+/// play clock and movement timer start over.
+#[derive(Event)]
+pub struct ResetGameEvent;
+
+/// Sent when the snake eats food and is about to grow by a segment
+#[derive(Event)]
+pub struct GrowthEvent;
+
+/// Sent when a piece of food is eaten, with the tile it was anchored on, its kind and
+/// the player whose snake ate it
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct FoodEatenEvent {
+    pub position: Position,
+    pub kind: FoodKind,
+    pub player: PlayerId,
+}
+
+/// Eats food under the snake head.
+///
+/// Numbered food is only eaten when its `FoodOrder` is next in line, otherwise the
+/// head passes over it. Eating dash food queues the dash for the next `movement`.
+///
+/// At most one piece of food is eaten per head each tick, so overlapping food never
+/// grows the snake by more than one segment at once. Food anchored exactly on the head
+/// tile is preferred over food that only covers it with a larger footprint, and any
+/// remaining tie goes to the food spawned first. The rest is left in place, which is
+/// also how the tiles of a food cluster are eaten one by one as the snake passes.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn eater(
+    mut commands: Commands,
+    mut growth_writer: EventWriter<GrowthEvent>,
+    mut eaten_writer: EventWriter<FoodEatenEvent>,
+    mut events: EventWriter<GameEvent>,
+    audio: Option<Res<AudioAssets>>,
+    mut ordered: Option<ResMut<OrderedFood>>,
+    mut dash: Option<ResMut<Dash>>,
+    food_positions: Query<
+        (
+            Entity,
+            &Position,
+            Option<&FootprintSize>,
+            Option<&FoodKind>,
+            Option<&FoodOrder>,
+        ),
+        With<Food>,
+    >,
+    head_positions: Query<(&Position, Option<&FootprintSize>, Option<&PlayerId>), With<SnakeHead>>,
+) {
+    for (head_pos, head_size, player) in head_positions.iter() {
+        let head_size = head_size.copied().unwrap_or_default();
+        let eaten = food_positions
+            .iter()
+            .filter(|(_, food_pos, footprint, _, order)| {
+                let footprint = footprint.copied().unwrap_or_default();
+                ordered
+                    .as_ref()
+                    .is_none_or(|ordered| ordered.allows(*order))
+                    && head_size
+                        .tiles(*head_pos)
+                        .any(|tile| footprint.covers(food_pos, &tile))
+            })
+            .min_by_key(|(ent, food_pos, ..)| (*food_pos != head_pos, *ent));
+        if let Some((ent, food_pos, _, kind, order)) = eaten {
+            if let Some(ordered) = ordered.as_mut() {
+                ordered.advance(order);
+            }
+            commands.entity(ent).despawn();
+            growth_writer.send(GrowthEvent);
+            let kind = kind.copied().unwrap_or_default();
+            if let (FoodKind::Dash { distance }, Some(dash)) = (kind, dash.as_mut()) {
+                dash.pending += distance;
+            }
+            eaten_writer.send(FoodEatenEvent {
+                position: *food_pos,
+                kind,
+                player: player.copied().unwrap_or_default(),
+            });
+            events.send(GameEvent::Ate { kind });
+            if let Some(sound) = audio.as_ref().and_then(|audio| audio.for_kind(kind)) {
+                commands.spawn((AudioPlayer::new(sound), PlaybackSettings::DESPAWN));
+            }
+        }
+    }
+}
+
+/// Practice aid that marks the tile the pathfinder would move to next. Off by default.
+#[derive(Resource, Default)]
+pub struct PracticeGhost {
+    pub enabled: bool,
+}
+
+const GHOST_COLOR: Color = Color::srgba(0.7, 0.7, 0.7, 0.25);
+
+/// Marks the faint tile shown by `PracticeGhost`
+#[derive(Component)]
+struct Ghost;
+
+/// Keeps the practice ghost on the pathfinder's next step for the player's snake
+#[allow(clippy::type_complexity)]
+fn update_ghost(
+    mut commands: Commands,
+    ghost: Res<PracticeGhost>,
+    arena: Res<ArenaConfig>,
+    heads: Query<(&SnakeHead, &Position), Without<Ghost>>,
+    body: Query<&Position, (With<SnakeSegment>, Without<Ghost>)>,
+    food: Query<(&Position, Option<&FootprintSize>), (With<Food>, Without<Ghost>)>,
+    mut ghosts: Query<(Entity, &mut Position), With<Ghost>>,
+) {
+    let hint = heads
+        .iter()
+        .next()
+        .filter(|_| ghost.enabled)
+        .and_then(|(head, pos)| {
+            let blocked: Vec<Position> = body.iter().copied().collect();
+            let food: Vec<Position> = food
+                .iter()
+                .flat_map(|(pos, footprint)| footprint.copied().unwrap_or_default().tiles(*pos))
+                .collect();
+            ai::next_step(*pos, head.direction, &arena, &blocked, &food)
+        });
+
+    match (hint, ghosts.get_single_mut()) {
+        (Some(hint), Ok((_, mut position))) => *position = hint,
+        (Some(hint), Err(_)) => {
+            commands.spawn((
+                Sprite {
+                    color: GHOST_COLOR,
+                    ..Default::default()
+                },
+                Ghost,
+                hint,
+                Size::square(0.8),
+            ));
+        }
+        (None, _) => {
+            for (entity, _) in ghosts.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// Sent once for every movement step the snake takes, giving integrators a clock
+/// aligned to game logic rather than to rendered frames.
+///
+/// `tick` is the value of `MoveCount` after the step, so it starts at 1 and increases
+/// by exactly one per event. `direction` is the way the head moved on that step, so a
+/// frame that takes several steps still reports each turn.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickEvent {
+    pub tick: u64,
+    pub direction: Direction,
+}
+
+/// Sent when the head passes through a wrapped edge, with the edge tile it left and
+/// the tile on the opposite edge it came back in on
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrapEvent {
+    pub from: Position,
+    pub to: Position,
+}
+
+/// Sent when the snake leaves the arena or runs into itself, ending the run.
+///
+/// Other plugins can listen for it to react to the end of a run:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use gametime::snake::GameOverEvent;
+///
+/// fn celebrate(mut game_over: EventReader<GameOverEvent>) {
+///     for _ in game_over.read() {
+///         info!("game over!");
+///     }
+/// }
+///
+/// App::new().add_systems(Update, celebrate);
+/// ```
+#[derive(Event)]
+pub struct GameOverEvent;
+
+/// The ticks a snake respawned after losing a life cannot die on its own body or on
+/// deadly tiles
+pub const RESPAWN_GRACE_TICKS: u32 = 5;
+
+/// The lives the snake has in a run.
+///
+/// A lethal collision costs a life and respawns the snake short again at its start,
+/// briefly safe from its own body and deadly tiles for `RESPAWN_GRACE_TICKS` ticks,
+/// while the food and the score stay as they are. Only losing the last life ends the
+/// run with a `GameOverEvent`, and the next run starts with every life back. A single
+/// life by default, so the first collision ends the run.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Lives {
+    /// The lives each run starts with
+    pub per_run: u32,
+    remaining: u32,
+    grace_ticks: u32,
+}
+
+impl Lives {
+    pub fn new(per_run: u32) -> Self {
+        Self {
+            per_run,
+            remaining: per_run,
+            grace_ticks: 0,
+        }
+    }
+
+    /// The lives left in the current run, counting the one being played
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Returns true while a respawned snake is still safe from its own body
+    pub fn in_grace(&self) -> bool {
+        self.grace_ticks > 0
+    }
+
+    /// Takes a life, returning true if the run carries on
+    pub(crate) fn lose(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        if self.remaining == 0 {
+            return false;
+        }
+        self.grace_ticks = RESPAWN_GRACE_TICKS;
+        true
+    }
+}
+
+impl Default for Lives {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+/// Sent when the snake loses a life but has more left, with the number remaining
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifeLostEvent {
+    pub remaining: u32,
+}
+
+/// Decides what a lethal collision costs: a life while there are more left, otherwise
+/// the run
+#[derive(SystemParam)]
+pub(crate) struct Deaths<'w> {
+    mode: Option<Res<'w, GameMode>>,
+    lives: Option<ResMut<'w, Lives>>,
+    game_over: EventWriter<'w, GameOverEvent>,
+    life_lost: EventWriter<'w, LifeLostEvent>,
+}
+
+impl Deaths<'_> {
+    /// Costs the snake a life, ending the run once none are left. Nothing is lost in
+    /// `GameMode::Peaceful`.
+    pub(crate) fn crash(&mut self) {
+        if self.is_peaceful() {
+            return;
+        }
+        let Some(lives) = self.lives.as_deref_mut() else {
+            self.game_over.send(GameOverEvent);
+            return;
+        };
+        if lives.lose() {
+            self.life_lost.send(LifeLostEvent {
+                remaining: lives.remaining,
+            });
+        } else {
+            self.game_over.send(GameOverEvent);
+        }
+    }
+
+    fn is_peaceful(&self) -> bool {
+        self.mode.as_deref() == Some(&GameMode::Peaceful)
+    }
+
+    fn is_invulnerable(&self) -> bool {
+        self.lives.as_ref().is_some_and(|lives| lives.in_grace())
+    }
+
+    /// Counts down the grace period after a respawn by a movement tick
+    fn tick(&mut self) {
+        if let Some(lives) = self.lives.as_mut().filter(|lives| lives.grace_ticks > 0) {
+            lives.grace_ticks -= 1;
+        }
+    }
+}
+
+/// The stages the snake runs through each frame, in order.
+///
+/// Systems from other plugins can be ordered relative to these, for example an AI
+/// steering the snake would run `.before(SnakeSet::Movement)`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnakeSet {
+    /// Reading player input and choosing the direction of travel
+    Input,
+    /// Advancing the snake along the grid
+    Movement,
+    /// Reacting to what the head ran into: walls, the body or food
+    Collision,
+    /// Adding segments and updating anything that depends on the snake's length
+    Growth,
+}
+
+/// Adds the snake and its systems.
+///
+/// Settings are taken from resources already in the app, falling back to defaults.
+/// `GameConfig::insert_into` sets all of them at once before the plugins are added.
+pub struct SnakePlugin;
+
+impl Plugin for SnakePlugin {
+    fn build(&self, app: &mut bevy::app::App) {
+        if !app.is_plugin_added::<EffectPlugin>() {
+            app.add_plugins(EffectPlugin);
+        }
+        app.init_resource::<Difficulty>();
+        if !app.world().contains_resource::<SpeedRamp>() {
+            let ramp = app.world().resource::<Difficulty>().speed_ramp();
+            app.insert_resource(ramp);
+        }
+        let timer = MovementTimer::from_seconds(app.world().resource::<SpeedRamp>().base);
+        app.insert_resource(timer);
+        app.insert_resource(Dash::default());
+        app.init_resource::<PlayClock>();
+        app.init_resource::<KeyBindings>();
+        app.init_resource::<TurnTiming>();
+        app.init_resource::<TurnStaleness>();
+        app.init_resource::<WallAssist>();
+        app.init_resource::<WallSlowdown>();
+        app.init_resource::<TailChase>();
+        app.init_resource::<EdgeWrap>();
+        app.init_resource::<HeadSize>();
+        app.init_resource::<BodyMovement>();
+        app.init_resource::<BodyPath>();
+        app.init_resource::<TileEffects>();
+        app.init_resource::<SnakeStart>();
+        app.init_resource::<MoveCount>();
+        app.init_resource::<GameMode>();
+        app.init_resource::<GrowthLimit>();
+        app.init_resource::<GrowthDelay>();
+        app.init_resource::<PendingGrowth>();
+        app.init_resource::<PracticeGhost>();
+        app.init_resource::<ReverseFeedback>();
+        app.init_resource::<WrapFlash>();
+        app.init_resource::<BodyTaper>();
+        app.init_resource::<BodyStyle>();
+        app.init_resource::<FreeReverse>();
+        app.init_resource::<RepeatSuppression>();
+        app.init_resource::<MirrorControls>();
+        app.init_resource::<Lives>();
+        app.insert_resource(SnakeSegments::default());
+        app.insert_resource(LastTailPosition::default());
+        app.add_event::<GrowthEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<LifeLostEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_event::<TickEvent>();
+        app.add_event::<WrapEvent>();
+        app.add_event::<ReverseRejected>();
+        app.add_event::<GameWonEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Startup, spawn_snake);
+        app.configure_sets(
+            Update,
+            (
+                SnakeSet::Input,
+                SnakeSet::Movement,
+                SnakeSet::Collision,
+                SnakeSet::Growth,
+            )
+                .chain(),
+        );
+        app.add_systems(
+            Update,
+            (
+                (
+                    tick_play_clock,
+                    expire_stale_turns,
+                    handle_input.run_if(game_running),
+                )
+                    .chain()
+                    .in_set(SnakeSet::Input),
+                movement
+                    .run_if(countdown_finished)
+                    .in_set(SnakeSet::Movement),
+                (game_over, restore_lives, eater, respawn)
+                    .chain()
+                    .in_set(SnakeSet::Collision),
+                (grow, mark_tail, taper_body, style_body, ramp_speed)
+                    .chain()
+                    .in_set(SnakeSet::Growth),
+            ),
+        );
+        app.add_systems(Update, update_ghost.after(SnakeSet::Growth));
+        app.add_systems(
+            Update,
+            recolor_snake
+                .run_if(resource_exists_and_changed::<PlayerColors>)
+                .after(SnakeSet::Growth),
+        );
+        app.add_systems(
+            Update,
+            (flash_rejected_reverse, fade_head_flash)
+                .chain()
+                .after(SnakeSet::Input),
+        );
+        app.add_systems(
+            Update,
+            (spawn_wrap_flashes, fade_wrap_flashes)
+                .chain()
+                .after(SnakeSet::Movement),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::ArenaMask,
+        countdown::{Countdown, CountdownPlugin},
+        hazard::{SpikeCycle, Spikes},
+        testing::TestGame,
+        tile::TileEffect,
+    };
+    use bevy::state::app::StatesPlugin;
+    use bevy::{ecs::system::SystemState, prelude::*};
+
+    #[test]
+    #[allow(clippy::type_complexity, clippy::needless_borrow)]
+    fn test_basic_movement_keys() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.insert_resource(MovementTimer::from_seconds(0.150));
+        app.insert_resource(Dash::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<WallAssist>();
+        app.init_resource::<TailChase>();
+        app.init_resource::<EdgeWrap>();
+        app.init_resource::<BodyMovement>();
+        app.init_resource::<BodyPath>();
+        app.init_resource::<TileEffects>();
+        app.init_resource::<MoveCount>();
+        app.insert_resource(SnakeSegments::default());
+        app.insert_resource(LastTailPosition::default());
+        app.add_event::<GameOverEvent>();
+        app.add_event::<LifeLostEvent>();
+        app.add_event::<TickEvent>();
+        app.add_event::<WrapEvent>();
+        app.add_event::<GameEvent>();
+        app.add_event::<ReverseRejected>();
+        app.init_resource::<KeyBindings>();
+        app.init_resource::<TurnTiming>();
+
+        let snake_entity = app
+            .world_mut()
+            .spawn((SnakeHead::default(), Position { x: 3, y: 3 }))
+            .id();
+
+        let test_cases = [
+            (KeyCode::ArrowLeft, Position { x: 2, y: 3 }),
+            (KeyCode::ArrowRight, Position { x: 4, y: 3 }),
+            (KeyCode::ArrowDown, Position { x: 3, y: 4 }), // snake should not move because it's already going up
+            (KeyCode::ArrowUp, Position { x: 3, y: 4 }),
+            (KeyCode::KeyA, Position { x: 2, y: 3 }),
+            (KeyCode::KeyD, Position { x: 4, y: 3 }),
+            (KeyCode::KeyW, Position { x: 3, y: 4 }), // snake should not move because it's already going up
+            (KeyCode::KeyW, Position { x: 3, y: 4 }),
+        ];
+
+        for (key, expected_position) in test_cases {
+            // Reset position
+            let world = app.world_mut();
+            *world.get_mut::<Position>(snake_entity).unwrap() = Position { x: 3, y: 3 };
+            *world.get_mut::<SnakeHead>(snake_entity).unwrap() = SnakeHead::default();
+
+            // Simulate key press and direction change
+            let mut input = ButtonInput::<KeyCode>::default();
+            input.press(key);
+            app.insert_resource(input);
+
+            let mut world = app.world_mut();
+            let mut input_state: SystemState<(
+                Res<ButtonInput<KeyCode>>,
+                Res<KeyBindings>,
+                Res<TurnTiming>,
+                ReverseGuard,
+                Option<Res<RepeatSuppression>>,
+                Option<Res<MirrorControls>>,
+                ResMut<Dash>,
+                EventWriter<ReverseRejected>,
+                Local<HeldKeys>,
+                Query<&mut SnakeHead>,
+            )> = SystemState::new(&mut world);
+            let (
+                input,
+                bindings,
+                timing,
+                reverse,
+                suppression,
+                mirror,
+                dash,
+                rejected,
+                held,
+                heads,
+            ) = input_state.get_mut(&mut world);
+            handle_input(
+                input,
+                bindings,
+                timing,
+                reverse,
+                suppression,
+                mirror,
+                dash,
+                rejected,
+                held,
+                heads,
+            );
+
+            // Simulate movement
+            let mut world = app.world_mut();
+            let mut system_state: SystemState<(
+                Commands,
+                Res<Time<Virtual>>,
+                ResMut<MovementTimer>,
+                ResMut<Dash>,
+                MovementRules,
+                ResMut<BodyPath>,
+                ResMut<MoveCount>,
+                ResMut<SnakeSegments>,
+                ResMut<LastTailPosition>,
+                Query<(Entity, &mut SnakeHead)>,
+                Query<&mut Position>,
+                Deaths,
+                EventWriter<TickEvent>,
+                EventWriter<WrapEvent>,
+                EventWriter<GameEvent>,
+            )> = SystemState::new(&mut world);
+            let (
+                commands,
+                time,
+                mut timer,
+                dash,
+                rules,
+                path,
+                moves,
+                segments,
+                last_tail,
+                heads,
+                positions,
+                deaths,
+                ticks,
+                wraps,
+                events,
+            ) = system_state.get_mut(&mut world);
+
+            // Ensure timer finishes
+            let duration = timer.clock.duration();
+            timer.clock.set_elapsed(duration);
+            movement(
+                commands, time, timer, dash, rules, path, moves, segments, last_tail, heads,
+                positions, deaths, ticks, wraps, events,
+            );
+
+            // Check position
+            let position = app.world_mut().get::<Position>(snake_entity).unwrap();
+            assert_eq!(*position, expected_position, "the position of the snake was not in the right place after pressing the {:?} button", key);
+        }
+    }
+
+    #[test]
+    fn test_snake_movement_sequence() {
+        let mut game = TestGame::new();
+        assert_eq!(game.head(), Position { x: 3, y: 3 });
+
+        let movement_sequence = [
+            (Direction::Up, Position { x: 3, y: 4 }),
+            (Direction::Right, Position { x: 4, y: 4 }),
+            (Direction::Down, Position { x: 4, y: 3 }),
+            (Direction::Right, Position { x: 5, y: 3 }),
+            (Direction::Up, Position { x: 5, y: 4 }),
+            (Direction::Up, Position { x: 5, y: 5 }),
+            (Direction::Left, Position { x: 4, y: 5 }),
+            (Direction::Left, Position { x: 3, y: 5 }),
+            (Direction::Down, Position { x: 3, y: 4 }),
+            (Direction::Right, Position { x: 4, y: 4 }),
+        ];
+
+        for (direction, expected_position) in movement_sequence {
+            let head = game.turn(direction).advance(1).head();
+            assert_eq!(
+                head, expected_position,
+                "Snake position incorrect after moving {:?}. Expected {:?}, got {:?}",
+                direction, expected_position, head
+            );
+        }
+        assert_eq!(game.segments().len(), STARTING_LENGTH);
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn test_eating_multi_tile_food_from_each_corner() {
+        let corners = [
+            Position { x: 4, y: 4 },
+            Position { x: 5, y: 4 },
+            Position { x: 4, y: 5 },
+            Position { x: 5, y: 5 },
+        ];
+
+        for corner in corners {
+            let mut app = App::new();
+            app.add_event::<GrowthEvent>();
+            app.add_event::<FoodEatenEvent>();
+            app.add_event::<GameEvent>();
+            app.add_systems(Update, eater);
+
+            let food = app
+                .world_mut()
+                .spawn((Food, Position { x: 4, y: 4 }, FootprintSize::square(2)))
+                .id();
+            app.world_mut().spawn((SnakeHead::default(), corner));
+
+            app.update();
+
+            let events = app.world().resource::<Events<GrowthEvent>>();
+            assert_eq!(
+                events.len(),
+                1,
+                "expected a single eat event at {:?}",
+                corner
+            );
+            assert!(
+                app.world().get::<Food>(food).is_none(),
+                "food should be despawned after being eaten at {:?}",
+                corner
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_tile_food_is_not_eaten_outside_footprint() {
+        let mut app = App::new();
+        app.add_event::<GrowthEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, eater);
+
+        app.world_mut()
+            .spawn((Food, Position { x: 4, y: 4 }, FootprintSize::square(2)));
+        app.world_mut()
+            .spawn((SnakeHead::default(), Position { x: 6, y: 5 }));
+
+        app.update();
+
+        let events = app.world().resource::<Events<GrowthEvent>>();
+        assert!(events.is_empty());
+    }
+
+    fn input_app() -> App {
+        let mut app = App::new();
+        app.insert_resource(Dash::default());
+        app.insert_resource(ButtonInput::<KeyCode>::default());
+        app.init_resource::<KeyBindings>();
+        app.init_resource::<TurnTiming>();
+        app.add_event::<ReverseRejected>();
+        app.add_systems(Update, handle_input);
+        app.world_mut().spawn(SnakeHead::default());
+        app
+    }
+
+    fn head_direction(app: &mut App) -> Direction {
+        let world = app.world_mut();
+        world.query::<&SnakeHead>().single(world).direction
+    }
+
+    #[test]
+    fn test_reverse_is_checked_against_last_move() {
+        let mut app = input_app();
+
+        // Turn left, then try to head down before the snake has moved off its upward step
+        for key in [KeyCode::ArrowLeft, KeyCode::ArrowDown] {
+            let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            input.release_all();
+            input.clear();
+            input.press(key);
+            app.update();
+        }
+
+        assert_eq!(head_direction(&mut app), Direction::Left);
+        assert_eq!(app.world().resource::<Events<ReverseRejected>>().len(), 1);
+    }
+
+    #[test]
+    fn test_mirror_controls_flip_turns_and_guard_the_result() {
+        let mut app = input_app();
+        app.insert_resource(MirrorControls { enabled: true });
+        let press = |app: &mut App, key| {
+            let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            input.release_all();
+            input.clear();
+            input.press(key);
+            app.update();
+            let world = app.world_mut();
+            let mut head = world.query::<&mut SnakeHead>().single_mut(world);
+            // Take the step, so only a turn back the way the snake came is a reversal
+            head.last_moved = head.direction;
+            head.direction
+        };
+
+        assert_eq!(press(&mut app, KeyCode::ArrowRight), Direction::Left);
+        assert_eq!(press(&mut app, KeyCode::ArrowUp), Direction::Down);
+        assert_eq!(press(&mut app, KeyCode::KeyA), Direction::Right);
+        assert_eq!(press(&mut app, KeyCode::KeyS), Direction::Up);
+        assert!(app.world().resource::<Events<ReverseRejected>>().is_empty());
+
+        // Up would be a turn down, straight back into the body while heading up
+        assert_eq!(press(&mut app, KeyCode::ArrowUp), Direction::Up);
+        assert_eq!(app.world().resource::<Events<ReverseRejected>>().len(), 1);
+    }
+
+    #[test]
+    fn test_movement_records_last_moved_direction() {
+        let (mut app, head) = movement_app(0.150);
+        app.world_mut()
+            .get_mut::<SnakeHead>(head)
+            .unwrap()
+            .direction = Direction::Left;
+        assert_eq!(
+            app.world().get::<SnakeHead>(head).unwrap().last_moved,
+            Direction::Up
+        );
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+
+        assert_eq!(
+            app.world().get::<SnakeHead>(head).unwrap().last_moved,
+            Direction::Left
+        );
+    }
+
+    #[test]
+    fn test_most_recent_key_wins() {
+        let mut app = input_app();
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowRight);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Right);
+        // Take the step right so Down is no longer a reversal
+        let world = app.world_mut();
+        world.query::<&mut SnakeHead>().single_mut(world).last_moved = Direction::Right;
+
+        // Down goes down while Right is still held
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.press(KeyCode::ArrowDown);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Down);
+
+        // Letting go of Down falls back to the key that is still held
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.release(KeyCode::ArrowDown);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Right);
+    }
+
+    #[test]
+    fn test_held_key_does_not_undo_tapped_turn() {
+        let mut app = input_app();
+        app.insert_resource(RepeatSuppression { enabled: true });
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.press(KeyCode::ArrowRight);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Right);
+        let world = app.world_mut();
+        world.query::<&mut SnakeHead>().single_mut(world).last_moved = Direction::Right;
+
+        // Up turns straight away while Right is still held
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.press(KeyCode::ArrowUp);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Up);
+
+        // Letting go of Up leaves the snake heading up rather than back to the right
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.release(KeyCode::ArrowUp);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Up);
+
+        // Pressing Right again after letting go of it turns as usual
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.release(KeyCode::ArrowRight);
+        app.update();
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.press(KeyCode::ArrowRight);
+        app.update();
+        assert_eq!(head_direction(&mut app), Direction::Right);
+    }
+
+    #[test]
+    fn test_suppressed_key_still_dashes() {
+        let mut app = input_app();
+        app.insert_resource(RepeatSuppression { enabled: true });
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowUp);
+        app.update();
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .clear();
+        app.update();
+
+        assert!(app.world().resource::<Dash>().held);
+    }
+
+    #[test]
+    fn test_same_frame_presses_follow_binding_order() {
+        let mut app = input_app();
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.press(KeyCode::ArrowDown);
+        input.press(KeyCode::ArrowRight);
+        app.update();
+
+        // Right is bound before Down, so it wins the tie
+        assert_eq!(head_direction(&mut app), Direction::Right);
+    }
+
+    #[test]
+    fn test_holding_current_direction_dashes() {
+        let mut app = input_app();
+
+        let base = Duration::from_millis(150);
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowUp);
+        app.update();
+        let dash = app.world().resource::<Dash>();
+        assert!(dash.held);
+        assert_eq!(base.div_f32(dash.multiplier()).as_millis(), 75);
+
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.release(KeyCode::ArrowUp);
+        app.update();
+        let dash = app.world().resource::<Dash>();
+        assert!(!dash.held);
+        assert_eq!(base.div_f32(dash.multiplier()).as_millis(), 150);
+    }
+
+    #[test]
+    fn test_turning_does_not_dash() {
+        let mut app = input_app();
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        app.update();
+
+        assert!(!app.world().resource::<Dash>().held);
+    }
+
+    #[test]
+    fn test_only_last_segment_is_marked_as_tail() {
+        let mut app = App::new();
+        app.insert_resource(SnakeSegments::default());
+        app.add_systems(Update, mark_tail);
+
+        let spawn = |app: &mut App, position: Position| {
+            let entity = app
+                .world_mut()
+                .spawn((Sprite::default(), SnakeSegment, position))
+                .id();
+            app.world_mut().resource_mut::<SnakeSegments>().push(entity);
+            entity
+        };
+
+        let first = spawn(&mut app, Position { x: 3, y: 2 });
+        app.update();
+        assert!(app.world().get::<SnakeTail>(first).is_some());
+
+        let second = spawn(&mut app, Position { x: 3, y: 1 });
+        app.update();
+        assert!(app.world().get::<SnakeTail>(first).is_none());
+        assert!(app.world().get::<SnakeTail>(second).is_some());
+        assert_eq!(
+            app.world().get::<Sprite>(first).unwrap().color,
+            PlayerColors::default().of(PlayerId(0)).body
+        );
+        assert_eq!(
+            app.world().get::<Sprite>(second).unwrap().color,
+            PlayerColors::default().of(PlayerId(0)).tail
+        );
+
+        app.world_mut().resource_mut::<SnakeSegments>().0.pop();
+        app.world_mut().despawn(second);
+        app.update();
+        assert!(app.world().get::<SnakeTail>(first).is_some());
+    }
+
+    fn movement_app(tick: f32) -> (App, Entity) {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(MovementTimer::from_seconds(tick));
+        app.insert_resource(Dash::default());
+        app.insert_resource(ArenaConfig::default());
+        app.init_resource::<WallAssist>();
+        app.init_resource::<TailChase>();
+        app.init_resource::<EdgeWrap>();
+        app.init_resource::<TurnTiming>();
+        app.init_resource::<BodyMovement>();
+        app.init_resource::<BodyPath>();
+        app.init_resource::<TileEffects>();
+        app.init_resource::<MoveCount>();
+        app.insert_resource(SnakeSegments::default());
+        app.insert_resource(LastTailPosition::default());
+        app.add_event::<GameOverEvent>();
+        app.add_event::<LifeLostEvent>();
+        app.add_event::<TickEvent>();
+        app.add_event::<WrapEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, movement);
+
+        let head = app
+            .world_mut()
+            .spawn((SnakeHead::default(), Position { x: 3, y: 0 }))
+            .id();
+        app.world_mut().resource_mut::<SnakeSegments>().push(head);
+        (app, head)
+    }
+
+    #[test]
+    fn test_large_delta_processes_multiple_steps() {
+        let (mut app, head) = movement_app(0.150);
+        let interval = app.world().resource::<MovementTimer>().clock.duration();
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(interval * 3);
+        app.update();
+
+        let position = app.world().get::<Position>(head).unwrap();
+        assert_eq!(*position, Position { x: 3, y: 3 });
+    }
+
+    #[test]
+    fn test_tick_event_is_sent_once_per_step() {
+        let (mut app, _) = movement_app(0.150);
+        let ticks_after = |app: &mut App, delta_ms: u64| {
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(delta_ms));
+            app.update();
+            app.world_mut()
+                .resource_mut::<Events<TickEvent>>()
+                .drain()
+                .map(|event| event.tick)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ticks_after(&mut app, 100), Vec::<u64>::new());
+        assert_eq!(ticks_after(&mut app, 100), vec![1]);
+        assert_eq!(ticks_after(&mut app, 300), vec![2, 3]);
+        assert_eq!(app.world().resource::<MoveCount>().total(), 3);
+    }
+
+    #[test]
+    fn test_moving_into_masked_tile_ends_run() {
+        let (mut app, _) = movement_app(0.150);
+        let mut rows = [".........."; 10];
+        rows[8] = "...#......";
+        app.insert_resource(ArenaConfig::from_mask(ArenaMask::from_rows(&rows)));
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+
+        assert_eq!(app.world().resource::<Events<GameOverEvent>>().len(), 1);
+    }
+
+    #[test]
+    fn test_peaceful_mode_never_ends_the_run() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(GameMode::Peaceful);
+        game.app
+            .insert_resource(ArenaConfig::from_mask(ArenaMask::from_rows(&[
+                "..........",
+                "..........",
+                "..........",
+                "...#......",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+            ])));
+        let mut game_overs = 0;
+        let mut step = |game: &mut TestGame, direction| {
+            game.turn(direction).advance(1);
+            game_overs += game.app.world().resource::<Events<GameOverEvent>>().len();
+        };
+
+        // Through the wall at (3, 6) and across the top edge
+        for _ in 0..8 {
+            step(&mut game, Direction::Up);
+        }
+        assert_eq!(game.head(), Position { x: 3, y: 1 });
+        // Straight back into the body
+        step(&mut game, Direction::Down);
+        assert_eq!(game.head(), Position { x: 3, y: 0 });
+        // Out of the left edge
+        for _ in 0..4 {
+            step(&mut game, Direction::Left);
+        }
+        assert_eq!(game.head(), Position { x: 9, y: 0 });
+        assert_eq!(game_overs, 0);
+        assert_eq!(game.segments().len(), STARTING_LENGTH);
+    }
+
+    #[test]
+    fn test_dash_food_jumps_head_and_checks_path() {
+        let dash_food = |game: &mut TestGame, position| {
+            game.app
+                .world_mut()
+                .spawn((Food, FoodKind::Dash { distance: 3 }, position));
+        };
+        let dash_frame = |game: &mut TestGame| {
+            let mut time = game.app.world_mut().resource_mut::<Time<Virtual>>();
+            time.advance_by(Duration::ZERO);
+            game.app.update();
+        };
+        let mut game = TestGame::new();
+        dash_food(&mut game, Position { x: 3, y: 4 });
+        game.turn(Direction::Up).advance(1);
+        assert_eq!(game.head(), Position { x: 3, y: 4 });
+        // The dash is taken on the next frame, without waiting for a tick
+        dash_frame(&mut game);
+        assert_eq!(game.head(), Position { x: 3, y: 7 });
+        assert_eq!(
+            game.app.world().resource::<Events<GameOverEvent>>().len(),
+            0
+        );
+
+        // A dash from the same tile runs into the wall at (3, 6) on its second tile
+        let mut game = TestGame::new();
+        game.app
+            .insert_resource(ArenaConfig::from_mask(ArenaMask::from_rows(&[
+                "..........",
+                "..........",
+                "..........",
+                "...#......",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+            ])));
+        dash_food(&mut game, Position { x: 3, y: 4 });
+        game.turn(Direction::Up).advance(1);
+        dash_frame(&mut game);
+        let died: Vec<DeathCause> = game
+            .app
+            .world()
+            .resource::<Events<GameEvent>>()
+            .iter_current_update_events()
+            .filter_map(|event| match event {
+                GameEvent::Died { cause } => Some(*cause),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(died, [DeathCause::Wall]);
+        assert_eq!(game.app.world().resource::<MoveCount>().total(), 3);
+    }
+
+    #[test]
+    fn test_wrap_event_reports_exit_and_entry_tiles() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(EdgeWrap {
+            wrap_x: false,
+            wrap_y: true,
+        });
+        let wraps = |game: &TestGame| -> Vec<WrapEvent> {
+            let events = game.app.world().resource::<Events<WrapEvent>>();
+            events.iter_current_update_events().copied().collect()
+        };
+
+        // Up to the top edge without wrapping
+        for _ in 0..6 {
+            game.advance(1);
+            assert!(wraps(&game).is_empty());
+        }
+        assert_eq!(game.head(), Position { x: 3, y: 9 });
+
+        game.advance(1);
+        assert_eq!(
+            wraps(&game),
+            [WrapEvent {
+                from: Position { x: 3, y: 9 },
+                to: Position { x: 3, y: 0 },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_wall_slowdown_doubles_interval_only_heading_into_wall() {
+        let arena = ArenaConfig::default();
+        let slowdown = WallSlowdown { enabled: true };
+        let edge = Position { x: 3, y: 9 };
+        let scale = |slowdown: WallSlowdown, head, direction| {
+            slowdown.interval_scale(head, direction, &arena, EdgeWrap::default())
+        };
+        assert_eq!(scale(slowdown, edge, Direction::Up), 2.0);
+        assert_eq!(scale(slowdown, edge, Direction::Right), 1.0);
+        assert_eq!(scale(slowdown, Position { x: 3, y: 8 }, Direction::Up), 1.0);
+        assert_eq!(scale(WallSlowdown::default(), edge, Direction::Up), 1.0);
+        assert_eq!(
+            slowdown.interval_scale(
+                edge,
+                Direction::Up,
+                &arena,
+                EdgeWrap {
+                    wrap_x: false,
+                    wrap_y: true
+                }
+            ),
+            1.0
+        );
+
+        // The snake takes a tick twice as long to run into the wall
+        let (mut app, head) = movement_app(0.150);
+        app.insert_resource(slowdown);
+        *app.world_mut().get_mut::<Position>(head).unwrap() = edge;
+        let interval = app.world().resource::<MovementTimer>().interval();
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(interval);
+        app.update();
+        assert!(app.world().resource::<Events<GameOverEvent>>().is_empty());
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(interval);
+        app.update();
+        assert_eq!(app.world().resource::<Events<GameOverEvent>>().len(), 1);
+    }
+
+    /// Coils a four tile snake into a square with the head heading up into the tail,
+    /// then moves it a tick and returns how many runs ended
+    fn chase_tail(chase: TailChase, pending: Option<PendingGrowth>) -> usize {
+        let (mut app, _) = movement_app(0.150);
+        app.insert_resource(chase);
+        if let Some(pending) = pending {
+            app.insert_resource(pending);
+        }
+        for (x, y) in [(4, 0), (4, 1), (3, 1)] {
+            let segment = app
+                .world_mut()
+                .spawn((SnakeSegment, Position { x, y }))
+                .id();
+            app.world_mut()
+                .resource_mut::<SnakeSegments>()
+                .push(segment);
+        }
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+
+        app.world().resource::<Events<GameOverEvent>>().len()
+    }
+
+    #[test]
+    fn test_head_can_follow_tail_leaving_its_tile() {
+        assert_eq!(chase_tail(TailChase::default(), None), 0);
+        assert_eq!(chase_tail(TailChase { enabled: false }, None), 1);
+        // A segment due this tick takes the tile the tail leaves
+        let due = PendingGrowth(VecDeque::from([1]));
+        assert_eq!(chase_tail(TailChase::default(), Some(due)), 1);
+    }
+
+    /// Moves a four tile snake through `turns`, one per tick, returning every segment
+    /// position after each tick
+    fn body_path(mode: BodyMovement, turns: &[Direction]) -> Vec<Vec<Position>> {
+        let (mut app, head) = movement_app(0.150);
+        app.insert_resource(mode);
+        for x in (0..3).rev() {
+            let segment = app
+                .world_mut()
+                .spawn((SnakeSegment, Position { x, y: 0 }))
+                .id();
+            app.world_mut()
+                .resource_mut::<SnakeSegments>()
+                .push(segment);
+        }
+
+        turns
+            .iter()
+            .map(|turn| {
+                app.world_mut()
+                    .get_mut::<SnakeHead>(head)
+                    .unwrap()
+                    .direction = *turn;
+                app.world_mut()
+                    .resource_mut::<Time<Virtual>>()
+                    .advance_by(Duration::from_millis(150));
+                app.update();
+                let world = app.world();
+                world
+                    .resource::<SnakeSegments>()
+                    .iter()
+                    .map(|segment| *world.get::<Position>(*segment).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rigid_movement_matches_tail_follow_for_contiguous_snake() {
+        let turns = [
+            Direction::Up,
+            Direction::Up,
+            Direction::Right,
+            Direction::Right,
+            Direction::Down,
+            Direction::Right,
+        ];
+        assert_eq!(
+            body_path(BodyMovement::Rigid { spacing: 1 }, &turns),
+            body_path(BodyMovement::TailFollow, &turns)
+        );
+    }
+
+    #[test]
+    fn test_rigid_movement_spaces_segments_along_path() {
+        let turns = [Direction::Up; 6];
+        let path = body_path(BodyMovement::Rigid { spacing: 2 }, &turns);
+        assert_eq!(
+            path.last().unwrap(),
+            &[
+                Position { x: 3, y: 6 },
+                Position { x: 3, y: 4 },
+                Position { x: 3, y: 2 },
+                Position { x: 3, y: 0 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spikes_kill_only_while_lethal() {
+        // The first step is tick 1: lethal with a cycle of 2 lethal ticks, safe once
+        // the count has moved past the lethal phase
+        for (moves_before, expected) in [(0, 1), (1, 0), (3, 1)] {
+            let (mut app, _) = movement_app(0.150);
+            for _ in 0..moves_before {
+                app.world_mut().resource_mut::<MoveCount>().increment();
+            }
+            app.world_mut().resource_mut::<TileEffects>().insert(
+                Position { x: 3, y: 1 },
+                Spikes {
+                    cycle: SpikeCycle {
+                        lethal_ticks: 2,
+                        safe_ticks: 2,
+                    },
+                },
+            );
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(150));
+            app.update();
+
+            assert_eq!(
+                app.world().resource::<Events<GameOverEvent>>().len(),
+                expected,
+                "after {} moves",
+                moves_before
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrap_per_axis() {
+        let arena = ArenaConfig::default();
+        let (right, top) = (arena.width - 1, arena.height - 1);
+        let at = |x, y| Position { x, y };
+        // (wrap_x, wrap_y, start, direction, expected head, dies)
+        let cases = [
+            (true, false, at(right, 4), Direction::Right, at(0, 4), false),
+            (true, false, at(0, 4), Direction::Left, at(right, 4), false),
+            (true, false, at(4, top), Direction::Up, at(4, top + 1), true),
+            (true, false, at(4, 0), Direction::Down, at(4, -1), true),
+            (false, true, at(4, top), Direction::Up, at(4, 0), false),
+            (false, true, at(4, 0), Direction::Down, at(4, top), false),
+            (
+                false,
+                true,
+                at(right, 4),
+                Direction::Right,
+                at(right + 1, 4),
+                true,
+            ),
+            (false, true, at(0, 4), Direction::Left, at(-1, 4), true),
+            (
+                true,
+                true,
+                at(right, top),
+                Direction::Up,
+                at(right, 0),
+                false,
+            ),
+            (
+                false,
+                false,
+                at(right, 4),
+                Direction::Right,
+                at(right + 1, 4),
+                true,
+            ),
+        ];
+        for (wrap_x, wrap_y, start, direction, expected, dies) in cases {
+            let (mut app, head) = movement_app(0.150);
+            app.insert_resource(EdgeWrap { wrap_x, wrap_y });
+            *app.world_mut().get_mut::<Position>(head).unwrap() = start;
+            app.world_mut()
+                .get_mut::<SnakeHead>(head)
+                .unwrap()
+                .direction = direction;
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(150));
+            app.update();
+
+            let case = (wrap_x, wrap_y, direction);
+            let position = *app.world().get::<Position>(head).unwrap();
+            assert_eq!(position, expected, "{:?}", case);
+            assert_eq!(
+                app.world().resource::<Events<GameOverEvent>>().len(),
+                dies as usize,
+                "{:?}",
+                case
+            );
+        }
+    }
+
+    /// Awards bonus points every time the head crosses the tile
+    struct BonusTile(u32);
+
+    impl TileEffect for BonusTile {
+        fn on_enter(&self, _tile: Position, _tick: u64, commands: &mut Commands) -> TileOutcome {
+            let points = self.0;
+            commands.queue(move |world: &mut World| {
+                world.resource_mut::<Score>().current += points;
+            });
+            TileOutcome::Continue
+        }
+    }
+
+    #[test]
+    fn test_tile_effect_runs_when_head_enters() {
+        let (mut app, head) = movement_app(0.150);
+        app.init_resource::<Score>();
+        app.world_mut()
+            .resource_mut::<TileEffects>()
+            .insert(Position { x: 3, y: 1 }, BonusTile(5));
+
+        for _ in 0..2 {
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(150));
+            app.update();
+        }
+
+        assert_eq!(
+            *app.world().get::<Position>(head).unwrap(),
+            Position { x: 3, y: 2 }
+        );
+        assert_eq!(app.world().resource::<Score>().current, 5);
+        assert!(app.world().resource::<Events<GameOverEvent>>().is_empty());
+    }
+
+    #[test]
+    fn test_steps_per_frame_are_clamped() {
+        let (mut app, head) = movement_app(0.150);
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_secs(1));
+        app.update();
+
+        let position = app.world().get::<Position>(head).unwrap();
+        assert_eq!(
+            *position,
+            Position {
+                x: 3,
+                y: MAX_STEPS_PER_FRAME as i32
+            }
+        );
+    }
+
+    #[test]
+    fn test_movement_does_not_advance_while_paused() {
+        let (mut app, head) = movement_app(0.150);
+        app.add_plugins(bevy::time::TimePlugin);
+        app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+            Duration::from_millis(200),
+        ));
+
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(
+            *app.world().get::<Position>(head).unwrap(),
+            Position { x: 3, y: 0 }
+        );
+        assert_eq!(
+            app.world().resource::<MovementTimer>().clock.elapsed(),
+            Duration::ZERO
+        );
+
+        app.world_mut().resource_mut::<Time<Virtual>>().unpause();
+        app.update();
+        assert_eq!(
+            *app.world().get::<Position>(head).unwrap(),
+            Position { x: 3, y: 1 },
+            "the snake should not catch up on time spent paused"
+        );
+    }
+
+    #[test]
+    fn test_speed_curves() {
+        let ramp = |curve| SpeedRamp {
+            curve,
+            ..Default::default()
+        };
+        let cases = [
+            (SpeedCurve::Constant, [0.150, 0.150, 0.150, 0.150]),
+            (
+                SpeedCurve::Linear { step: 0.010 },
+                [0.150, 0.140, 0.110, 0.050],
+            ),
+            (
+                SpeedCurve::Geometric { factor: 0.5 },
+                [0.150, 0.075, 0.050, 0.050],
+            ),
+            (
+                SpeedCurve::Table(vec![(3, 0.120), (10, 0.080)]),
+                [0.150, 0.120, 0.120, 0.080],
+            ),
+        ];
+        let lengths = [2, 3, 6, 20];
+
+        for (curve, expected) in cases {
+            let ramp = ramp(curve);
+            for (length, expected) in lengths.into_iter().zip(expected) {
+                let interval = ramp.interval(length, Duration::ZERO);
+                assert!(
+                    (interval - expected).abs() < 1e-6,
+                    "{:?} at length {} gave {} but expected {}",
+                    ramp.curve,
+                    length,
+                    interval,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "movement expects a single SnakeHead but found 2")]
+    fn test_movement_rejects_multiple_heads() {
+        let (mut app, _) = movement_app(0.150);
+        app.world_mut()
+            .spawn((SnakeHead::default(), Position { x: 6, y: 0 }));
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+    }
+
+    #[test]
+    fn test_reverse_rejected_only_on_reverse() {
+        let cases = [
+            (KeyCode::ArrowDown, 1),
+            (KeyCode::KeyS, 1),
+            (KeyCode::ArrowUp, 0),
+            (KeyCode::ArrowLeft, 0),
+            (KeyCode::KeyD, 0),
+        ];
+
+        for (key, expected) in cases {
+            let mut app = input_app();
+            app.world_mut()
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(key);
+            app.update();
+
+            let events = app.world().resource::<Events<ReverseRejected>>();
+            assert_eq!(
+                events.len(),
+                expected,
+                "unexpected rejections for {:?}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_snake_takes_the_player_colors() {
+        let mut game = TestGame::with_start(SnakeStart {
+            head: Position { x: 3, y: 3 },
+            direction: Direction::Up,
+            length: 4,
+        });
+        let colors = SnakeColors {
+            head: Color::srgb(1., 0., 0.),
+            body: Color::srgb(0., 1., 0.),
+            tail: Color::srgb(0., 0., 1.),
+        };
+        let assert_colors = |game: &mut TestGame| {
+            let world = game.app.world_mut();
+            let segments = world.resource::<SnakeSegments>().0.clone();
+            let color_of = |entity: &Entity| world.get::<Sprite>(*entity).unwrap().color;
+            assert_eq!(color_of(&segments[0]), colors.head);
+            assert_eq!(color_of(&segments[1]), colors.body);
+            assert_eq!(color_of(segments.last().unwrap()), colors.tail);
+            for entity in &segments {
+                assert_eq!(world.get::<PlayerId>(*entity), Some(&PlayerId(0)));
+            }
+        };
+
+        // The snake on the board is repainted straight away
+        game.app.insert_resource(PlayerColors(vec![colors]));
+        game.advance(1);
+        assert_colors(&mut game);
+
+        // And the next run starts with a snake in the new colors
+        game.app.world_mut().send_event(ResetGameEvent);
+        game.advance(1);
+        assert_colors(&mut game);
+    }
+
+    #[test]
+    fn test_lives_respawn_snake_until_last_is_lost() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(Lives::new(3));
+        let start = game.head();
+        let game_overs =
+            |game: &TestGame| game.app.world().resource::<Events<GameOverEvent>>().len();
+
+        // Seven ticks up from the start runs into the top wall
+        for remaining in [2, 1] {
+            game.advance(7);
+            assert_eq!(game.app.world().resource::<Lives>().remaining(), remaining);
+            assert_eq!(game_overs(&game), 0);
+            assert_eq!(game.head(), start);
+        }
+
+        game.advance(7);
+        assert_eq!(game_overs(&game), 1);
+        assert_eq!(game.app.world().resource::<Lives>().remaining(), 3);
+    }
+
+    #[test]
+    fn test_respawned_snake_passes_through_its_body() {
+        let mut game = TestGame::with_start(SnakeStart {
+            head: Position { x: 5, y: 3 },
+            direction: Direction::Right,
+            length: 5,
+        });
+        game.app.insert_resource(Lives::new(2));
+        game.app.world_mut().resource_mut::<Lives>().lose();
+
+        // Turning back into the body would be fatal without the grace period
+        game.turn(Direction::Up).advance(1);
+        game.turn(Direction::Left).advance(1);
+        game.turn(Direction::Down).advance(1);
+        assert!(game
+            .app
+            .world()
+            .resource::<Events<GameOverEvent>>()
+            .is_empty());
+        assert!(game
+            .app
+            .world()
+            .resource::<Events<LifeLostEvent>>()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_stale_turn_is_dropped_during_countdown() {
+        let mut game = TestGame::new();
+        game.app.insert_resource(TurnTiming::Queued);
+        game.app.insert_resource(TurnStaleness { ticks: Some(2) });
+        game.app.insert_resource(Countdown { secs: 1 });
+        game.app.add_plugins(CountdownPlugin);
+        let world = game.app.world_mut();
+        world
+            .query::<&mut SnakeHead>()
+            .single_mut(world)
+            .turns
+            .push_back(QueuedTurn::new(Direction::Right));
+
+        game.advance(2);
+        let world = game.app.world_mut();
+        assert_eq!(world.query::<&SnakeHead>().single(world).turns.len(), 1);
+
+        game.advance(1);
+        let world = game.app.world_mut();
+        assert!(world.query::<&SnakeHead>().single(world).turns.is_empty());
+
+        // Once the countdown is over the snake carries on up instead of turning late
+        let start = game.head();
+        game.advance(5);
+        assert_eq!(game.head().x, start.x);
+        assert!(game.head().y > start.y);
+    }
+
+    #[test]
+    fn test_body_tapers_toward_tail() {
+        let mut game = TestGame::with_start(SnakeStart {
+            length: 5,
+            ..Default::default()
+        });
+        game.app.insert_resource(BodyTaper {
+            enabled: true,
+            tail_scale: 0.6,
+        });
+        game.advance(1);
+
+        let world = game.app.world();
+        let modifiers: Vec<Option<f32>> = world
+            .resource::<SnakeSegments>()
+            .iter()
+            .map(|entity| {
+                world
+                    .get::<SizeModifier>(*entity)
+                    .map(|modifier| modifier.0)
+            })
+            .collect();
+        let expected = [
+            None,
+            Some(1.0),
+            Some(0.8666667),
+            Some(0.73333335),
+            Some(0.6),
+        ];
+        assert_eq!(modifiers.len(), expected.len());
+        for (modifier, expected) in modifiers.iter().zip(expected) {
+            match (modifier, expected) {
+                (Some(modifier), Some(expected)) => assert!((modifier - expected).abs() < 1e-5),
+                (modifier, expected) => assert_eq!(*modifier, expected),
+            }
+        }
+
+        // Turning tapering off restores the uniform size
+        game.app.world_mut().resource_mut::<BodyTaper>().enabled = false;
+        game.advance(1);
+        let world = game.app.world_mut();
+        assert_eq!(world.query::<&SizeModifier>().iter(world).count(), 0);
+    }
+
+    #[test]
+    fn test_free_reverse_is_allowed_only_during_grace() {
+        let head_after_reverse = |free: bool, grace: bool| {
+            let mut game = TestGame::new();
+            game.app.insert_resource(FreeReverse { enabled: free });
+            game.app.insert_resource(Lives::new(2));
+            if grace {
+                game.app.world_mut().resource_mut::<Lives>().lose();
+            }
+            game.app
+                .world_mut()
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(KeyCode::ArrowDown);
+            game.advance(1);
+            game.head()
+        };
+
+        assert_eq!(head_after_reverse(true, true), Position { x: 3, y: 2 });
+        assert_eq!(head_after_reverse(true, false), Position { x: 3, y: 4 });
+        assert_eq!(head_after_reverse(false, true), Position { x: 3, y: 4 });
+    }
+
+    #[test]
+    fn test_free_reverse_is_allowed_in_peaceful_mode_outside_grace() {
+        let head_after_reverse = |free: bool| {
+            let mut game = TestGame::new();
+            game.app.insert_resource(FreeReverse { enabled: free });
+            game.app.insert_resource(GameMode::Peaceful);
+            game.app
+                .world_mut()
+                .resource_mut::<ButtonInput<KeyCode>>()
+                .press(KeyCode::ArrowDown);
+            game.advance(1);
+            assert_eq!(game.app.world().resource::<Lives>().grace_ticks, 0);
+            game.head()
+        };
+
+        assert_eq!(head_after_reverse(true), Position { x: 3, y: 2 });
+        assert_eq!(head_after_reverse(false), Position { x: 3, y: 4 });
+    }
+
+    #[test]
+    fn test_dotted_style_hides_every_other_segment() {
+        let mut game = TestGame::with_start(SnakeStart {
+            head: Position { x: 3, y: 5 },
+            direction: Direction::Up,
+            length: 5,
+        });
+        game.app.insert_resource(BodyStyle::Dotted { every: 2 });
+        game.advance(1);
+
+        let visibilities = |game: &TestGame| -> Vec<Visibility> {
+            let world = game.app.world();
+            world
+                .resource::<SnakeSegments>()
+                .iter()
+                .map(|entity| *world.get::<Visibility>(*entity).unwrap())
+                .collect()
+        };
+        assert_eq!(
+            visibilities(&game),
+            [
+                Visibility::Inherited,
+                Visibility::Hidden,
+                Visibility::Inherited,
+                Visibility::Hidden,
+                Visibility::Inherited
+            ]
+        );
+        assert_eq!(game.segments().len(), 5);
+
+        // Hidden segments still block the head
+        game.turn(Direction::Right).advance(1);
+        game.turn(Direction::Down).advance(1);
+        game.turn(Direction::Left).advance(1);
+        assert_eq!(
+            game.app.world().resource::<Events<GameOverEvent>>().len(),
+            1
+        );
+
+        game.app.insert_resource(BodyStyle::Solid);
+        game.advance(1);
+        assert!(visibilities(&game)
+            .iter()
+            .all(|visibility| *visibility == Visibility::Inherited));
+    }
+
+    #[derive(Resource, Default)]
+    struct RecordedEvents(Vec<GameEvent>);
+
+    fn record_events(mut events: EventReader<GameEvent>, mut recorded: ResMut<RecordedEvents>) {
+        recorded.0.extend(events.read().copied());
+    }
+
+    #[test]
+    fn test_game_events_follow_a_run() {
+        let mut game = TestGame::new();
+        game.app.init_resource::<RecordedEvents>();
+        game.app.add_systems(Last, record_events);
+        game.app.world_mut().spawn((Food, Position { x: 3, y: 4 }));
+
+        game.advance(1).turn(Direction::Down).advance(1);
+
+        let recorded = &game.app.world().resource::<RecordedEvents>().0;
+        assert_eq!(
+            recorded[..],
+            [
+                GameEvent::Moved {
+                    head: Position { x: 3, y: 4 }
+                },
+                GameEvent::Ate {
+                    kind: FoodKind::Normal
+                },
+                GameEvent::ScoreChanged { total: 1 },
+                GameEvent::Grew,
+                GameEvent::Died {
+                    cause: DeathCause::Body
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reset_event_starts_a_fresh_run() {
+        let mut game = TestGame::new();
+        game.app.world_mut().resource_mut::<Score>().current = 5;
+        game.turn(Direction::Right).advance(3);
+        game.app
+            .world_mut()
+            .resource_mut::<MovementTimer>()
+            .clock
+            .set_elapsed(Duration::from_millis(100));
+
+        game.app.world_mut().send_event(ResetGameEvent);
+        game.app.update();
+
+        assert_eq!(game.head(), Position { x: 3, y: 3 });
+        assert_eq!(game.segments().len(), STARTING_LENGTH);
+        assert_eq!(game.score(), 0);
+        let world = game.app.world();
+        assert_eq!(
+            world.resource::<Score>().best,
+            0,
+            "a reset is not a finished run"
+        );
+        assert_eq!(world.resource::<PlayClock>().elapsed(), Duration::ZERO);
+        assert_eq!(
+            world.resource::<MovementTimer>().clock.elapsed(),
+            Duration::ZERO
+        );
+    }
+
+    #[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+    enum Screen {
+        #[default]
+        Playing,
+        Title,
+    }
+
+    #[test]
+    fn test_leaving_the_game_leaves_nothing_behind() {
+        let mut game = TestGame::new();
+        game.app.add_plugins(StatesPlugin);
+        game.app.init_state::<Screen>();
+        game.app.add_systems(OnExit(Screen::Playing), despawn_game);
+        game.app.world_mut().spawn((Food, Position { x: 6, y: 6 }));
+        game.app.world_mut().spawn(TimedEffect::from_seconds(1.0));
+        game.app.world_mut().resource_mut::<Score>().current = 4;
+        game.advance(2);
+
+        game.app
+            .world_mut()
+            .resource_mut::<NextState<Screen>>()
+            .set(Screen::Title);
+        // Leave the game on a frame where no time passes so nothing ticks afterwards
+        game.app
+            .world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::ZERO);
+        game.app.update();
+
+        let world = game.app.world_mut();
+        let leftovers = world
+            .query_filtered::<Entity, Or<(With<Position>, With<TimedEffect>)>>()
+            .iter(world)
+            .count();
+        assert_eq!(leftovers, 0);
+        assert!(game.segments().is_empty());
+        assert_eq!(game.score(), 0);
+        let world = game.app.world();
+        assert_eq!(world.resource::<MoveCount>().total(), 0);
+        assert_eq!(world.resource::<PlayClock>().elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_game_over_despawns_long_snake_and_food() {
+        let mut app = App::new();
         app.insert_resource(SnakeSegments::default());
-        app.insert_resource(LastTailPosition::default());
+        app.init_resource::<PlayClock>();
+        app.init_resource::<BodyPath>();
+        app.init_resource::<PendingGrowth>();
+        app.init_resource::<SnakeStart>();
+        app.init_resource::<EdgeWrap>();
+        app.init_resource::<HeadSize>();
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(MovementTimer::from_seconds(0.150));
         app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_systems(Update, game_over);
 
-        // Spawn snake
-        let snake_entity = app
+        let head = app
             .world_mut()
-            .spawn((
-                Sprite {
-                    color: SNAKE_HEAD_COLOR,
-                    ..Default::default()
-                },
-                SnakeHead::default(),
-                Position { x: 3, y: 3 },
-                Size::square(0.8),
-            ))
+            .spawn((SnakeHead::default(), Position { x: 0, y: 0 }))
             .id();
+        let mut old = vec![head];
+        for i in 0..200 {
+            old.push(
+                app.world_mut()
+                    .spawn((
+                        SnakeSegment,
+                        Position {
+                            x: i % 10,
+                            y: i / 10,
+                        },
+                    ))
+                    .id(),
+            );
+        }
+        for x in 0..3 {
+            old.push(app.world_mut().spawn((Food, Position { x, y: 9 })).id());
+        }
+        app.world_mut().resource_mut::<SnakeSegments>().0 = old.clone();
 
-        app.insert_resource(MovementTimer::from_seconds(1.0));
+        app.world_mut().send_event(GameOverEvent);
+        app.update();
 
-        let movement_sequence = [
-            (Direction::Up, Position { x: 3, y: 4 }),
-            (Direction::Right, Position { x: 4, y: 4 }),
-            (Direction::Down, Position { x: 4, y: 3 }),
-            (Direction::Right, Position { x: 5, y: 3 }),
-            (Direction::Up, Position { x: 5, y: 4 }),
-            (Direction::Up, Position { x: 5, y: 5 }),
-            (Direction::Left, Position { x: 4, y: 5 }),
-            (Direction::Left, Position { x: 3, y: 5 }),
-            (Direction::Down, Position { x: 3, y: 4 }),
-            (Direction::Right, Position { x: 4, y: 4 }),
+        let world = app.world_mut();
+        for ent in old {
+            assert!(
+                world.get_entity(ent).is_err(),
+                "{:?} survived game over",
+                ent
+            );
+        }
+        assert_eq!(world.query::<&Food>().iter(world).count(), 0);
+        assert_eq!(world.query::<&SnakeHead>().iter(world).count(), 1);
+        assert_eq!(world.query::<&SnakeSegment>().iter(world).count(), 1);
+        assert_eq!(world.resource::<SnakeSegments>().len(), STARTING_LENGTH);
+    }
+
+    #[test]
+    fn test_key_bindings_reject_conflicts() {
+        let mut bindings = KeyBindings::default();
+
+        assert_eq!(
+            bindings.bind(Direction::Left, &[KeyCode::ArrowUp]),
+            Err(BindingConflict {
+                key: KeyCode::ArrowUp,
+                bound_to: Direction::Up
+            })
+        );
+        assert_eq!(bindings.direction(KeyCode::ArrowUp), Some(Direction::Up));
+        assert_eq!(
+            bindings.direction(KeyCode::ArrowLeft),
+            Some(Direction::Left)
+        );
+    }
+
+    #[test]
+    fn test_key_bindings_accept_disjoint_keys() {
+        let mut bindings = KeyBindings::default();
+
+        assert_eq!(
+            bindings.bind(Direction::Left, &[KeyCode::KeyJ, KeyCode::ArrowLeft]),
+            Ok(())
+        );
+        assert_eq!(bindings.direction(KeyCode::KeyJ), Some(Direction::Left));
+        assert_eq!(
+            bindings.direction(KeyCode::ArrowLeft),
+            Some(Direction::Left)
+        );
+        assert_eq!(bindings.direction(KeyCode::KeyA), None);
+    }
+
+    #[test]
+    fn test_speed_ramp_over_time() {
+        let ramp = SpeedRamp {
+            over_time: Some(TimeSchedule {
+                every: 30.0,
+                factor: 0.9,
+            }),
+            ..Default::default()
+        };
+        let cases = [
+            (0, 0.150),
+            (29, 0.150),
+            (30, 0.135),
+            (65, 0.1215),
+            (120, 0.098415),
+            (600, 0.050),
         ];
 
-        for (direction, expected_position) in movement_sequence {
-            // Set snake direction
-            let world = app.world_mut();
-            if let Some(mut head) = world.get_mut::<SnakeHead>(snake_entity) {
-                head.direction = direction;
+        for (secs, expected) in cases {
+            let interval = ramp.interval(STARTING_LENGTH, Duration::from_secs(secs));
+            assert!(
+                (interval - expected).abs() < 1e-6,
+                "after {}s gave {} but expected {}",
+                secs,
+                interval,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_length_and_time_ramps_compose() {
+        let ramp = SpeedRamp {
+            curve: SpeedCurve::Linear { step: 0.010 },
+            over_time: Some(TimeSchedule {
+                every: 30.0,
+                factor: 0.5,
+            }),
+            min: 0.010,
+            ..Default::default()
+        };
+
+        let interval = ramp.interval(STARTING_LENGTH + 5, Duration::from_secs(30));
+        assert!((interval - 0.050).abs() < 1e-6);
+    }
+
+    /// Presses Left just before a tick boundary and returns the head position after
+    /// that tick and after the following one
+    fn turn_before_tick(timing: TurnTiming) -> (Position, Position) {
+        let (mut app, head) = movement_app(0.150);
+        app.insert_resource(timing);
+        app.insert_resource(ButtonInput::<KeyCode>::default());
+        app.init_resource::<KeyBindings>();
+        app.add_event::<ReverseRejected>();
+        app.add_systems(Update, handle_input.before(movement));
+
+        let mut timer = app.world_mut().resource_mut::<MovementTimer>();
+        let elapsed = timer.interval() - Duration::from_millis(10);
+        timer.clock.set_elapsed(elapsed);
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(10));
+        app.update();
+        let first = *app.world().get::<Position>(head).unwrap();
+
+        let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        input.clear();
+        input.release(KeyCode::ArrowLeft);
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+        let second = *app.world().get::<Position>(head).unwrap();
+
+        (first, second)
+    }
+
+    #[test]
+    fn test_keys_are_ignored_while_paused() {
+        let mut game = TestGame::new();
+        game.app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        game.app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        game.app.update();
+
+        let world = game.app.world_mut();
+        let head = world.query::<&SnakeHead>().single(world);
+        assert_eq!(head.direction, Direction::Up);
+        assert!(head.turns.is_empty());
+
+        game.app
+            .world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .unpause();
+        game.app.update();
+        let world = game.app.world_mut();
+        let head = world.query::<&SnakeHead>().single(world);
+        assert_eq!(
+            head.turns
+                .back()
+                .map_or(head.direction, |turn| turn.direction),
+            Direction::Left
+        );
+    }
+
+    #[test]
+    fn test_immediate_turn_applies_to_current_tick() {
+        assert_eq!(
+            turn_before_tick(TurnTiming::Immediate),
+            (Position { x: 2, y: 0 }, Position { x: 1, y: 0 })
+        );
+    }
+
+    #[test]
+    fn test_next_tick_turn_waits_for_current_move() {
+        assert_eq!(
+            turn_before_tick(TurnTiming::NextTick),
+            (Position { x: 3, y: 1 }, Position { x: 2, y: 1 })
+        );
+    }
+
+    #[test]
+    fn test_wall_turn_choices() {
+        let arena = ArenaConfig::default();
+        let cases = [
+            // Heading into each wall away from the corners prefers the side with more room
+            (
+                Position { x: 3, y: 9 },
+                Direction::Up,
+                Some(Direction::Right),
+            ),
+            (
+                Position { x: 6, y: 9 },
+                Direction::Up,
+                Some(Direction::Left),
+            ),
+            (
+                Position { x: 3, y: 0 },
+                Direction::Down,
+                Some(Direction::Right),
+            ),
+            (
+                Position { x: 0, y: 3 },
+                Direction::Left,
+                Some(Direction::Up),
+            ),
+            (
+                Position { x: 9, y: 6 },
+                Direction::Right,
+                Some(Direction::Down),
+            ),
+            // In a corner only one turn stays in bounds
+            (
+                Position { x: 0, y: 9 },
+                Direction::Up,
+                Some(Direction::Right),
+            ),
+            (
+                Position { x: 9, y: 0 },
+                Direction::Down,
+                Some(Direction::Left),
+            ),
+            (
+                Position { x: 0, y: 0 },
+                Direction::Left,
+                Some(Direction::Up),
+            ),
+        ];
+        for (pos, dir, expected) in cases {
+            assert_eq!(
+                wall_turn(pos, dir, &arena, &[]),
+                expected,
+                "heading {:?} at {:?}",
+                dir,
+                pos
+            );
+        }
+
+        // Equal room on both sides turns clockwise
+        let arena = ArenaConfig {
+            width: 9,
+            height: 9,
+            ..Default::default()
+        };
+        assert_eq!(
+            wall_turn(Position { x: 4, y: 8 }, Direction::Up, &arena, &[]),
+            Some(Direction::Right)
+        );
+    }
+
+    #[test]
+    fn test_wall_turn_avoids_body() {
+        let arena = ArenaConfig::default();
+        let body = [Position { x: 4, y: 9 }];
+        assert_eq!(
+            wall_turn(Position { x: 3, y: 9 }, Direction::Up, &arena, &body),
+            Some(Direction::Left)
+        );
+
+        let body = [Position { x: 1, y: 9 }];
+        assert_eq!(
+            wall_turn(Position { x: 0, y: 9 }, Direction::Up, &arena, &body),
+            None
+        );
+    }
+
+    #[test]
+    fn test_wall_assist_follows_wall() {
+        let (mut app, head) = movement_app(0.150);
+        app.insert_resource(WallAssist { enabled: true });
+        *app.world_mut().get_mut::<Position>(head).unwrap() = Position { x: 3, y: 9 };
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+
+        assert_eq!(
+            *app.world().get::<Position>(head).unwrap(),
+            Position { x: 4, y: 9 }
+        );
+        assert_eq!(
+            app.world().get::<SnakeHead>(head).unwrap().direction,
+            Direction::Right
+        );
+        assert!(app.world().resource::<Events<GameOverEvent>>().is_empty());
+    }
+
+    #[test]
+    fn test_reaching_target_length_wins() {
+        let target = 5;
+        let mut app = App::new();
+        app.insert_resource(GameMode::ReachLength(target));
+        app.init_resource::<GrowthLimit>();
+        app.init_resource::<GrowthDelay>();
+        app.init_resource::<PendingGrowth>();
+        app.add_event::<TickEvent>();
+        app.insert_resource(LastTailPosition(Some(Position { x: 3, y: 0 })));
+        app.insert_resource(SnakeSegments::default());
+        app.add_event::<GrowthEvent>();
+        app.add_event::<GameWonEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, grow);
+
+        for _ in 0..STARTING_LENGTH {
+            let segment = app.world_mut().spawn(SnakeSegment).id();
+            app.world_mut()
+                .resource_mut::<SnakeSegments>()
+                .push(segment);
+        }
+
+        for length in STARTING_LENGTH + 1..=target {
+            app.world_mut().send_event(GrowthEvent);
+            app.update();
+
+            assert_eq!(app.world().resource::<SnakeSegments>().len(), length);
+            let won = !app.world().resource::<Events<GameWonEvent>>().is_empty();
+            assert_eq!(
+                won,
+                length == target,
+                "unexpected win state at length {}",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn test_growth_plateaus_at_max_segments() {
+        let max = 4;
+        let mut app = App::new();
+        app.init_resource::<GameMode>();
+        app.insert_resource(GrowthLimit {
+            max_segments: Some(max),
+        });
+        app.init_resource::<GrowthDelay>();
+        app.init_resource::<PendingGrowth>();
+        app.add_event::<TickEvent>();
+        app.insert_resource(LastTailPosition(Some(Position { x: 3, y: 0 })));
+        app.insert_resource(SnakeSegments::default());
+        app.add_event::<GrowthEvent>();
+        app.add_event::<GameWonEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, grow);
+
+        let head = app.world_mut().spawn(SnakeSegment).id();
+        app.world_mut().resource_mut::<SnakeSegments>().push(head);
+
+        let mut lengths = Vec::new();
+        for _ in 0..6 {
+            app.world_mut().send_event(GrowthEvent);
+            app.update();
+            lengths.push(app.world().resource::<SnakeSegments>().len());
+        }
+
+        assert_eq!(lengths, [2, 3, 4, 4, 4, 4]);
+    }
+
+    #[test]
+    fn test_delayed_growth_emerges_from_the_tail() {
+        for delay in [0, 1, 2] {
+            let mut game = TestGame::new();
+            game.app.insert_resource(GrowthDelay { ticks: delay });
+            game.app.world_mut().spawn((Food, Position { x: 3, y: 4 }));
+
+            let mut lengths = Vec::new();
+            let mut emerged_at = None;
+            for _ in 0..4 {
+                game.advance(1);
+                let segments = game.segments();
+                let distinct: std::collections::HashSet<Position> =
+                    segments.iter().copied().collect();
+                assert_eq!(
+                    distinct.len(),
+                    segments.len(),
+                    "delay {}: {:?}",
+                    delay,
+                    segments
+                );
+                if segments.len() == 3 && emerged_at.is_none() {
+                    emerged_at = segments.last().copied();
+                }
+                lengths.push(segments.len());
             }
 
-            // Simulate movement
-            let mut world = app.world_mut();
-            let mut system_state: SystemState<(
-                Res<Time>,
-                ResMut<MovementTimer>,
-                ResMut<SnakeSegments>,
-                ResMut<LastTailPosition>,
-                Query<(Entity, &SnakeHead)>,
-                Query<&mut Position>,
-                EventWriter<GameOverEvent>,
-            )> = SystemState::new(&mut world);
-            let (time, mut timer, segments, last_tail, heads, positions, game_over) =
-                system_state.get_mut(&mut world);
+            let mut expected = [2; 4];
+            expected[delay as usize..].fill(3);
+            assert_eq!(lengths, expected, "delay {}", delay);
+            // The new segment fills the tile the tail left on the tick it appears
+            let tail_left = Position {
+                x: 3,
+                y: 2 + delay as i32,
+            };
+            assert_eq!(emerged_at, Some(tail_left), "delay {}", delay);
+        }
+    }
 
-            // Ensure timer finishes
-            let duration = timer.clock.duration();
-            timer.clock.set_elapsed(duration);
-            movement(
-                time, timer, segments, last_tail, heads, positions, game_over,
+    #[test]
+    fn test_big_head_hits_walls_with_any_tile() {
+        let at = |x, y| Position { x, y };
+        // (start, direction, dies) for a 2x2 head on the 10x10 arena
+        let cases = [
+            (at(7, 4), Direction::Right, false),
+            (at(8, 4), Direction::Right, true),
+            (at(4, 7), Direction::Up, false),
+            (at(4, 8), Direction::Up, true),
+            (at(1, 4), Direction::Left, false),
+            (at(0, 4), Direction::Left, true),
+            (at(4, 1), Direction::Down, false),
+            (at(4, 0), Direction::Down, true),
+        ];
+        for (start, direction, dies) in cases {
+            let (mut app, head) = movement_app(0.150);
+            app.world_mut()
+                .entity_mut(head)
+                .insert((start, FootprintSize::square(2)));
+            app.world_mut()
+                .get_mut::<SnakeHead>(head)
+                .unwrap()
+                .direction = direction;
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::from_millis(150));
+            app.update();
+
+            assert_eq!(
+                app.world().resource::<Events<GameOverEvent>>().len(),
+                dies as usize,
+                "{:?} heading {:?}",
+                start,
+                direction
             );
+        }
+    }
 
-            // Check position
-            let position = app.world_mut().get::<Position>(snake_entity).unwrap();
+    #[test]
+    fn test_big_head_eats_food_under_any_tile() {
+        let mut app = App::new();
+        app.add_event::<GrowthEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, eater);
+        app.world_mut().spawn((
+            SnakeHead::default(),
+            Position { x: 3, y: 3 },
+            FootprintSize::square(2),
+        ));
+        let covered = app.world_mut().spawn((Food, Position { x: 4, y: 4 })).id();
+        let beside = app.world_mut().spawn((Food, Position { x: 5, y: 4 })).id();
+
+        app.update();
+
+        assert!(app.world().get_entity(covered).is_err());
+        assert!(app.world().get_entity(beside).is_ok());
+    }
+
+    #[test]
+    fn test_numbered_food_is_only_eaten_in_order() {
+        let mut app = App::new();
+        app.init_resource::<OrderedFood>();
+        app.add_event::<GrowthEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, eater);
+        let head = app
+            .world_mut()
+            .spawn((SnakeHead::default(), Position { x: 3, y: 3 }))
+            .id();
+        let first = app
+            .world_mut()
+            .spawn((Food, Position { x: 5, y: 5 }, FoodOrder(1)))
+            .id();
+        let second = app
+            .world_mut()
+            .spawn((Food, Position { x: 4, y: 4 }, FoodOrder(2)))
+            .id();
+        let visit = |app: &mut App, x, y| {
+            *app.world_mut().get_mut::<Position>(head).unwrap() = Position { x, y };
+            app.update();
+            app.world().resource::<OrderedFood>().eaten
+        };
+
+        // The second piece is passed over until the first has been eaten
+        assert_eq!(visit(&mut app, 4, 4), 0);
+        assert!(app.world().get_entity(second).is_ok());
+        assert_eq!(visit(&mut app, 5, 5), 1);
+        assert!(app.world().get_entity(first).is_err());
+        assert_eq!(visit(&mut app, 4, 4), 2);
+        assert!(app.world().get_entity(second).is_err());
+    }
+
+    #[test]
+    fn test_big_head_turns_over_its_neck() {
+        let (mut app, head) = movement_app(0.150);
+        app.world_mut()
+            .entity_mut(head)
+            .insert((Position { x: 4, y: 4 }, FootprintSize::square(2)));
+        let neck = app
+            .world_mut()
+            .spawn((SnakeSegment, Position { x: 4, y: 3 }))
+            .id();
+        app.world_mut().resource_mut::<SnakeSegments>().push(neck);
+        app.world_mut()
+            .get_mut::<SnakeHead>(head)
+            .unwrap()
+            .direction = Direction::Left;
+
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .advance_by(Duration::from_millis(150));
+        app.update();
+
+        // The neck moves up under the head, which is not a collision
+        assert_eq!(
+            *app.world().get::<Position>(neck).unwrap(),
+            Position { x: 4, y: 4 }
+        );
+        assert!(app.world().resource::<Events<GameOverEvent>>().is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_food_is_eaten_one_per_tick() {
+        let mut app = App::new();
+        app.add_event::<GrowthEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(Update, eater);
+
+        let covering = app
+            .world_mut()
+            .spawn((Food, Position { x: 2, y: 2 }, FootprintSize::square(2)))
+            .id();
+        let first = app.world_mut().spawn((Food, Position { x: 3, y: 3 })).id();
+        let second = app.world_mut().spawn((Food, Position { x: 3, y: 3 })).id();
+        app.world_mut()
+            .spawn((SnakeHead::default(), Position { x: 3, y: 3 }));
+
+        let mut remaining = Vec::new();
+        for _ in 0..3 {
+            app.update();
+            assert_eq!(app.world().resource::<Events<GrowthEvent>>().len(), 1);
+            app.world_mut()
+                .resource_mut::<Events<GrowthEvent>>()
+                .clear();
+            remaining.push(
+                [covering, first, second].map(|food| app.world().get::<Food>(food).is_some()),
+            );
+        }
+
+        assert_eq!(
+            remaining,
+            [
+                [true, false, true],
+                [true, false, false],
+                [false, false, false]
+            ]
+        );
+    }
+
+    /// Presses each key on its own frame between two ticks with `TurnTiming::Queued`,
+    /// then returns the head position after each of the following ticks
+    fn queued_path(presses: &[KeyCode], ticks: usize) -> Vec<Position> {
+        let (mut app, head) = movement_app(0.150);
+        app.insert_resource(TurnTiming::Queued);
+        app.insert_resource(ButtonInput::<KeyCode>::default());
+        app.init_resource::<KeyBindings>();
+        app.add_event::<ReverseRejected>();
+        app.add_systems(Update, handle_input.before(movement));
+        *app.world_mut().get_mut::<Position>(head).unwrap() = Position { x: 5, y: 5 };
+
+        for key in presses {
+            let mut input = ButtonInput::<KeyCode>::default();
+            input.press(*key);
+            app.insert_resource(input);
+            app.world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(Duration::ZERO);
+            app.update();
+        }
+        app.insert_resource(ButtonInput::<KeyCode>::default());
+
+        (0..ticks)
+            .map(|_| {
+                app.world_mut()
+                    .resource_mut::<Time<Virtual>>()
+                    .advance_by(Duration::from_millis(150));
+                app.update();
+                *app.world().get::<Position>(head).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_queued_turns_apply_one_per_tick() {
+        use KeyCode::*;
+        let cases: [(&[KeyCode], [Position; 4]); 5] = [
+            (
+                &[ArrowRight, ArrowUp, ArrowLeft],
+                [
+                    Position { x: 6, y: 5 },
+                    Position { x: 6, y: 6 },
+                    Position { x: 5, y: 6 },
+                    Position { x: 4, y: 6 },
+                ],
+            ),
+            (
+                &[ArrowDown],
+                [
+                    Position { x: 5, y: 6 },
+                    Position { x: 5, y: 7 },
+                    Position { x: 5, y: 8 },
+                    Position { x: 5, y: 9 },
+                ],
+            ),
+            (
+                &[ArrowLeft, ArrowDown],
+                [
+                    Position { x: 4, y: 5 },
+                    Position { x: 4, y: 4 },
+                    Position { x: 4, y: 3 },
+                    Position { x: 4, y: 2 },
+                ],
+            ),
+            (
+                &[ArrowRight, KeyD, ArrowLeft, ArrowUp],
+                [
+                    Position { x: 6, y: 5 },
+                    Position { x: 6, y: 6 },
+                    Position { x: 6, y: 7 },
+                    Position { x: 6, y: 8 },
+                ],
+            ),
+            (
+                &[KeyA, KeyW, KeyD, KeyS],
+                [
+                    Position { x: 4, y: 5 },
+                    Position { x: 4, y: 6 },
+                    Position { x: 5, y: 6 },
+                    Position { x: 6, y: 6 },
+                ],
+            ),
+        ];
+
+        for (presses, expected) in cases {
             assert_eq!(
-                *position, expected_position,
-                "Snake position incorrect after moving {:?}. Expected {:?}, got {:?}",
-                direction, expected_position, position
+                queued_path(presses, expected.len()),
+                expected,
+                "unexpected path for presses {:?}",
+                presses
             );
         }
     }
+
+    #[test]
+    fn test_ghost_marks_pathfinder_step() {
+        let mut app = App::new();
+        app.insert_resource(PracticeGhost { enabled: true });
+        app.insert_resource(ArenaConfig::default());
+        app.add_systems(Update, update_ghost);
+
+        let head = Position { x: 3, y: 3 };
+        let body = [Position { x: 3, y: 2 }, Position { x: 4, y: 2 }];
+        let food = Position { x: 6, y: 1 };
+        app.world_mut().spawn((SnakeHead::default(), head));
+        for pos in body {
+            app.world_mut().spawn((SnakeSegment, pos));
+        }
+        app.world_mut().spawn((Food, food));
+
+        app.update();
+
+        let expected = ai::next_step(head, Direction::Up, &ArenaConfig::default(), &body, &[food]);
+        let world = app.world_mut();
+        let ghosts: Vec<Position> = world
+            .query_filtered::<&Position, With<Ghost>>()
+            .iter(world)
+            .copied()
+            .collect();
+        assert_eq!(ghosts, vec![expected.unwrap()]);
+
+        world.resource_mut::<PracticeGhost>().enabled = false;
+        app.update();
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Ghost>().iter(world).count(), 0);
+    }
+
+    #[test]
+    fn test_spawn_snake_uses_configured_textures() {
+        let head_image = Handle::weak_from_u128(1);
+        let segment_image = Handle::weak_from_u128(2);
+        let mut app = App::new();
+        app.insert_resource(SnakeSegments::default());
+        app.init_resource::<SnakeStart>();
+        app.init_resource::<EdgeWrap>();
+        app.init_resource::<HeadSize>();
+        app.insert_resource(ArenaConfig::default());
+        app.insert_resource(SpriteAssets {
+            head: Some(head_image.clone()),
+            segment: Some(segment_image.clone()),
+            food: None,
+        });
+        app.add_systems(Update, spawn_snake);
+
+        app.update();
+
+        let world = app.world_mut();
+        let head = world
+            .query_filtered::<&Sprite, With<SnakeHead>>()
+            .single(world);
+        assert_eq!(head.image, head_image);
+        assert_eq!(head.custom_size, Some(Vec2::ONE));
+        let segment = world
+            .query_filtered::<&Sprite, With<SnakeSegment>>()
+            .single(world);
+        assert_eq!(segment.image, segment_image);
+    }
+
+    #[test]
+    fn test_spawn_snake_falls_back_to_colors() {
+        let mut app = App::new();
+        app.insert_resource(SnakeSegments::default());
+        app.init_resource::<SnakeStart>();
+        app.init_resource::<EdgeWrap>();
+        app.init_resource::<HeadSize>();
+        app.insert_resource(ArenaConfig::default());
+        app.add_systems(Update, spawn_snake);
+
+        app.update();
+
+        let world = app.world_mut();
+        let head = world
+            .query_filtered::<&Sprite, With<SnakeHead>>()
+            .single(world);
+        assert_eq!(head.image, Handle::default());
+        assert_eq!(head.color, PlayerColors::default().of(PlayerId(0)).head);
+    }
+
+    #[test]
+    fn test_invalid_starts_fall_back_to_legal_layouts() {
+        let tiny = ArenaConfig {
+            width: 3,
+            height: 3,
+            ..Default::default()
+        };
+        let starts = [
+            (
+                SnakeStart {
+                    length: 20,
+                    ..Default::default()
+                },
+                tiny.clone(),
+            ),
+            (
+                SnakeStart {
+                    head: Position { x: 1, y: 0 },
+                    direction: Direction::Up,
+                    length: 3,
+                },
+                tiny.clone(),
+            ),
+            (
+                SnakeStart {
+                    head: Position { x: -5, y: 40 },
+                    ..Default::default()
+                },
+                ArenaConfig::default(),
+            ),
+            (
+                SnakeStart {
+                    length: 0,
+                    ..Default::default()
+                },
+                ArenaConfig::default(),
+            ),
+        ];
+        for (start, arena) in starts {
+            let tiles = start
+                .validated(&arena, EdgeWrap::default())
+                .tiles(&arena)
+                .unwrap();
+            assert!(!tiles.is_empty() && tiles.len() <= start.length.max(1));
+            assert!(tiles.iter().all(|tile| arena::in_bounds(*tile, &arena)));
+            let distinct: std::collections::HashSet<Position> = tiles.iter().copied().collect();
+            assert_eq!(distinct.len(), tiles.len(), "{:?} overlaps itself", start);
+        }
+
+        let start = SnakeStart {
+            length: 20,
+            ..Default::default()
+        }
+        .validated(&tiny, EdgeWrap::default());
+        assert_eq!(
+            start.length, 2,
+            "the longest snake a 3x3 board fits with room for its first move"
+        );
+    }
+
+    #[test]
+    fn test_legal_start_is_kept() {
+        let start = SnakeStart {
+            head: Position { x: 5, y: 1 },
+            direction: Direction::Left,
+            length: 4,
+        };
+        let arena = ArenaConfig::default();
+        assert_eq!(start.validated(&arena, EdgeWrap::default()), start);
+        assert_eq!(
+            start.tiles(&arena).unwrap(),
+            [5, 6, 7, 8].map(|x| Position { x, y: 1 })
+        );
+    }
+
+    #[test]
+    fn test_edge_spawn_survives_first_tick() {
+        let arena = ArenaConfig::default();
+        let (right, top) = (arena.width - 1, arena.height - 1);
+        let starts = [
+            (Position { x: 0, y: 0 }, Direction::Down),
+            (Position { x: 0, y: 4 }, Direction::Left),
+            (Position { x: right, y: 4 }, Direction::Right),
+            (Position { x: 4, y: top }, Direction::Up),
+            (Position { x: right, y: top }, Direction::Up),
+        ];
+        for (head, direction) in starts {
+            let start = SnakeStart {
+                head,
+                direction,
+                length: 3,
+            };
+            let mut game = TestGame::with_start(start);
+            let spawned = game.segments();
+            assert_eq!(spawned.len(), 3, "{:?}", start);
+
+            game.advance(1);
+
+            let died = !game
+                .app
+                .world()
+                .resource::<Events<GameOverEvent>>()
+                .is_empty();
+            assert!(!died, "{:?} died on the first tick", start);
+            assert_ne!(game.head(), spawned[0], "{:?} did not move", start);
+        }
+
+        // Facing out is fine when the edge wraps
+        let start = SnakeStart {
+            head: Position { x: 4, y: top },
+            direction: Direction::Up,
+            length: 3,
+        };
+        let wrap = EdgeWrap {
+            wrap_x: false,
+            wrap_y: true,
+        };
+        assert_eq!(start.validated(&arena, wrap), start);
+    }
 }
@@ -0,0 +1,315 @@
+//! Stats module
+//!
+//! This module keeps statistics on the run in progress and sums them up when the run
+//! ends: the final score, the longest the snake got, how long the run lasted, the
+//! food eaten of each kind and the longest combo.
+//!
+//! The statistics are kept in `RunStats` while the run goes on. When it ends they
+//! move to `LastRun` and, unless `RunSummary::enabled` is turned off, are shown in a
+//! panel for `RunSummary::secs` seconds. A reset run is discarded without a summary.
+
+use std::{mem, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    effect::{EffectPlugin, TimedEffect},
+    food::FoodKind,
+    score::ScoreEvent,
+    snake::{
+        FoodEatenEvent, GameOverEvent, PlayClock, ResetGameEvent, SnakeSegments, SnakeSet,
+        TickEvent,
+    },
+    telemetry::GameEvent,
+};
+
+const SUMMARY_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+/// The statistics of a run
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub struct RunStats {
+    pub score: u32,
+    /// The most tiles the snake covered at once, head included
+    pub max_length: usize,
+    /// Play time, excluding time spent paused
+    pub duration: Duration,
+    /// The movement ticks taken
+    pub moves: u64,
+    /// How many pieces of each kind were eaten, in the order first eaten
+    pub eaten: Vec<(FoodKind, u32)>,
+    pub longest_combo: u32,
+}
+
+impl RunStats {
+    /// How many pieces of food of `kind` were eaten
+    pub fn eaten_of(&self, kind: FoodKind) -> u32 {
+        self.eaten
+            .iter()
+            .find(|(eaten, _)| *eaten == kind)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    fn count_eaten(&mut self, kind: FoodKind) {
+        match self.eaten.iter_mut().find(|(eaten, _)| *eaten == kind) {
+            Some((_, count)) => *count += 1,
+            None => self.eaten.push((kind, 1)),
+        }
+    }
+
+    /// The lines of the summary shown when the run ends
+    pub fn summary(&self) -> String {
+        let secs = self.duration.as_secs();
+        let eaten = if self.eaten.is_empty() {
+            "none".to_string()
+        } else {
+            self.eaten
+                .iter()
+                .map(|(kind, count)| format!("{} {}", count, kind.name()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        format!(
+            "Score: {}\nLongest snake: {}\nTime: {}:{:02}\nMoves: {}\nFood eaten: {}\n\
+             Longest combo: {}",
+            self.score,
+            self.max_length,
+            secs / 60,
+            secs % 60,
+            self.moves,
+            eaten,
+            self.longest_combo
+        )
+    }
+}
+
+/// The statistics of the last run to end, if any has
+#[derive(Resource, Default, Debug)]
+pub struct LastRun(pub Option<RunStats>);
+
+/// How the summary shown at the end of a run behaves
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct RunSummary {
+    pub enabled: bool,
+    /// Seconds the summary stays on screen
+    pub secs: f32,
+}
+
+impl Default for RunSummary {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            secs: 5.0,
+        }
+    }
+}
+
+/// Marks the panel showing the summary of the last run
+#[derive(Component)]
+struct SummaryPanel;
+
+/// Keeps the play time of the run, sampled before a game over resets the clock
+fn time_run(mut stats: ResMut<RunStats>, clock: Option<Res<PlayClock>>) {
+    if let Some(clock) = clock {
+        stats.duration = clock.elapsed();
+    }
+}
+
+/// Brings the statistics up to date with what happened this frame. The score and the
+/// combo come from events, as both are reset as soon as the run ends.
+fn track_stats(
+    mut stats: ResMut<RunStats>,
+    segments: Option<Res<SnakeSegments>>,
+    mut ticks: EventReader<TickEvent>,
+    mut eaten: EventReader<FoodEatenEvent>,
+    mut scored: EventReader<ScoreEvent>,
+    mut events: EventReader<GameEvent>,
+) {
+    stats.moves += ticks.read().count() as u64;
+    for food in eaten.read() {
+        stats.count_eaten(food.kind);
+    }
+    for scored in scored.read() {
+        stats.longest_combo = stats.longest_combo.max(scored.combo);
+    }
+    for event in events.read() {
+        if let GameEvent::ScoreChanged { total } = event {
+            stats.score = *total;
+        }
+    }
+    if let Some(segments) = segments {
+        stats.max_length = stats.max_length.max(segments.len());
+    }
+}
+
+/// Files away the statistics of a run once it ends and shows their summary
+fn finish_stats(
+    mut commands: Commands,
+    settings: Res<RunSummary>,
+    mut stats: ResMut<RunStats>,
+    mut last: ResMut<LastRun>,
+    mut game_over: EventReader<GameOverEvent>,
+    mut resets: EventReader<ResetGameEvent>,
+    panels: Query<Entity, With<SummaryPanel>>,
+) {
+    let died = game_over.read().count() > 0;
+    let reset = resets.read().count() > 0;
+    if reset && !died {
+        *stats = RunStats::default();
+        return;
+    }
+    if !died {
+        return;
+    }
+    let finished = mem::take(&mut *stats);
+    for panel in panels.iter() {
+        commands.entity(panel).despawn();
+    }
+    if settings.enabled {
+        commands.spawn((
+            Text::new(finished.summary()),
+            TextFont {
+                font_size: 16.,
+                ..Default::default()
+            },
+            TextColor(SUMMARY_COLOR),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(50.),
+                width: Val::Percent(100.),
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            TextLayout::new_with_justify(JustifyText::Center),
+            SummaryPanel,
+            TimedEffect::from_seconds(settings.secs),
+        ));
+    }
+    last.0 = Some(finished);
+}
+
+fn fade_summary(mut commands: Commands, panels: Query<(Entity, &TimedEffect), With<SummaryPanel>>) {
+    for (entity, effect) in panels.iter() {
+        if effect.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EffectPlugin>() {
+            app.add_plugins(EffectPlugin);
+        }
+        app.init_resource::<RunStats>();
+        app.init_resource::<LastRun>();
+        app.init_resource::<RunSummary>();
+        app.add_event::<TickEvent>();
+        app.add_event::<FoodEatenEvent>();
+        app.add_event::<GameOverEvent>();
+        app.add_event::<ResetGameEvent>();
+        app.add_event::<ScoreEvent>();
+        app.add_event::<GameEvent>();
+        app.add_systems(
+            Update,
+            time_run
+                .after(SnakeSet::Movement)
+                .before(SnakeSet::Collision),
+        );
+        // The run is summed up once everything eaten on its last frame has been
+        // counted and the snake has grown
+        app.add_systems(
+            Update,
+            (track_stats, finish_stats, fade_summary)
+                .chain()
+                .after(SnakeSet::Growth),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::{Direction, Position},
+        food::Food,
+        testing::TestGame,
+    };
+
+    #[test]
+    fn test_run_stats_are_summed_up_at_game_over() {
+        let mut game = TestGame::new();
+        game.app.add_plugins(StatsPlugin);
+        for (y, kind) in [
+            (4, FoodKind::Normal),
+            (5, FoodKind::Normal),
+            (7, FoodKind::Golden),
+        ] {
+            game.app
+                .world_mut()
+                .spawn((Food, kind, Position { x: 3, y }));
+        }
+
+        // Up through the food and into the top wall on the seventh move
+        game.turn(Direction::Up).advance(6);
+        assert!(game.app.world().resource::<LastRun>().0.is_none());
+        game.advance(1);
+
+        let world = game.app.world_mut();
+        let last = world.resource::<LastRun>().0.clone().unwrap();
+        assert_eq!(last.score, 5);
+        assert_eq!(last.max_length, 5);
+        assert_eq!(last.moves, 7);
+        assert_eq!(last.eaten_of(FoodKind::Normal), 2);
+        assert_eq!(last.eaten_of(FoodKind::Golden), 1);
+        assert_eq!(last.longest_combo, 3);
+        assert!(last.duration > Duration::ZERO);
+        let panel = world
+            .query_filtered::<&Text, With<SummaryPanel>>()
+            .single(world);
+        assert!(panel.0.contains("Food eaten: 2 normal, 1 golden"));
+
+        // The next run starts from scratch
+        assert_eq!(world.resource::<RunStats>().moves, 0);
+    }
+
+    /// Ends the run as soon as a piece of food is eaten, on the same frame
+    fn poisoned_food(
+        mut eaten: EventReader<FoodEatenEvent>,
+        mut game_over: EventWriter<GameOverEvent>,
+    ) {
+        if eaten.read().count() > 0 {
+            game_over.send(GameOverEvent);
+        }
+    }
+
+    #[test]
+    fn test_food_eaten_on_the_last_frame_counts() {
+        let mut game = TestGame::new();
+        game.app.add_plugins(StatsPlugin);
+        game.app.add_systems(
+            Update,
+            poisoned_food
+                .after(SnakeSet::Collision)
+                .before(SnakeSet::Growth),
+        );
+        game.app
+            .world_mut()
+            .spawn((Food, FoodKind::Golden, Position { x: 3, y: 5 }));
+        let length = game.segments().len();
+
+        game.turn(Direction::Up).advance(2);
+
+        let world = game.app.world();
+        let last = world.resource::<LastRun>().0.clone().unwrap();
+        assert_eq!(last.score, FoodKind::Golden.points());
+        assert_eq!(last.moves, 2);
+        assert_eq!(last.eaten_of(FoodKind::Golden), 1);
+        assert_eq!(last.longest_combo, 1);
+        assert!(last.max_length > length);
+        assert!(last.duration > Duration::ZERO);
+        // Nothing of the last run carries over into the next
+        assert_eq!(*world.resource::<RunStats>(), RunStats::default());
+    }
+}
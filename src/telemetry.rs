@@ -0,0 +1,53 @@
+//! Telemetry module
+//!
+//! This module defines `GameEvent`, a single stream of everything that happens
+//! during play for tools such as dashboards, tutorials or session logs.
+//!
+//! The game systems send a `GameEvent` alongside their own events, so an integration
+//! can follow a whole session with one `EventReader<GameEvent>` rather than
+//! subscribing to each event separately. Within a frame the events arrive in the
+//! order things happen: the snake moves, eats, scores and then grows.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{arena::Position, food::FoodKind};
+
+/// What ended a run
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum DeathCause {
+    /// The head left the arena or ran onto an unplayable tile
+    Wall,
+    /// The head ran into the snake's own body
+    Body,
+    /// A tile effect, such as spikes, killed the snake
+    Tile,
+}
+
+impl DeathCause {
+    /// A short lowercase name for the cause, such as `wall`
+    pub fn name(self) -> &'static str {
+        match self {
+            DeathCause::Wall => "wall",
+            DeathCause::Body => "body",
+            DeathCause::Tile => "tile",
+        }
+    }
+}
+
+/// Something that happened during play
+#[derive(Event, Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    /// The snake took a step, with the head now on `head`
+    Moved { head: Position },
+    /// The snake ate a piece of food
+    Ate { kind: FoodKind },
+    /// A segment was added to the snake
+    Grew,
+    /// The snake died, ending the run
+    Died { cause: DeathCause },
+    /// The goal of the current `GameMode` was reached
+    Won,
+    /// The score of the run changed
+    ScoreChanged { total: u32 },
+}
@@ -0,0 +1,77 @@
+//! Test harness
+//!
+//! `TestGame` runs the snake systems headlessly on a fixed timestep so tests can
+//! steer the snake, advance a number of movement ticks and read back the state,
+//! without assembling `SystemState`s by hand.
+
+use bevy::prelude::*;
+
+use crate::{
+    arena::{ArenaConfig, Direction, Position},
+    score::{Score, ScorePlugin},
+    snake::{MovementTimer, SnakeHead, SnakePlugin, SnakeSegments, SnakeStart},
+};
+
+pub struct TestGame {
+    pub app: App,
+}
+
+impl TestGame {
+    /// A game on the default arena with the starting snake already spawned
+    pub fn new() -> Self {
+        Self::with_start(SnakeStart::default())
+    }
+
+    /// A game on the default arena with the snake spawned from `start`
+    pub fn with_start(start: SnakeStart) -> Self {
+        let mut app = App::new();
+        app.insert_resource(Time::<Virtual>::default());
+        app.insert_resource(start);
+        app.init_resource::<ArenaConfig>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.add_plugins((SnakePlugin, ScorePlugin));
+        app.update();
+        Self { app }
+    }
+
+    /// Points the head in `direction` before the next tick
+    pub fn turn(&mut self, direction: Direction) -> &mut Self {
+        let world = self.app.world_mut();
+        world.query::<&mut SnakeHead>().single_mut(world).direction = direction;
+        self
+    }
+
+    /// Advances the game by exactly `ticks` movement steps, one frame per step
+    pub fn advance(&mut self, ticks: usize) -> &mut Self {
+        for _ in 0..ticks {
+            let interval = self.app.world().resource::<MovementTimer>().interval();
+            self.app
+                .world_mut()
+                .resource_mut::<Time<Virtual>>()
+                .advance_by(interval);
+            self.app.update();
+        }
+        self
+    }
+
+    pub fn head(&mut self) -> Position {
+        let world = self.app.world_mut();
+        *world
+            .query_filtered::<&Position, With<SnakeHead>>()
+            .single(world)
+    }
+
+    /// The positions of every segment, head first
+    pub fn segments(&self) -> Vec<Position> {
+        let world = self.app.world();
+        world
+            .resource::<SnakeSegments>()
+            .iter()
+            .filter_map(|entity| world.get::<Position>(*entity).copied())
+            .collect()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.app.world().resource::<Score>().current
+    }
+}
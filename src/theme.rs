@@ -0,0 +1,434 @@
+//! Theme module
+//!
+//! This module picks the color palette used to draw the game.
+//!
+//! By default the theme follows the preferred color scheme of the system, held in
+//! `PreferredColorScheme`. Detection is left to the game binary and is best effort:
+//! the `--light` and `--dark` command line flags take priority, followed by the
+//! `SLITHER_COLOR_SCHEME` environment variable (`light` or `dark`). When no preference
+//! can be found the dark theme is used.
+//!
+//! Each theme also sets the `PlayerColors` the snakes are drawn in, one palette per
+//! player so that players can tell their snakes apart. A palette of your own can be
+//! kept regardless of the theme with `PlayerColorSetting::Fixed`.
+//!
+//! Every theme has a subtle vertical gradient that can replace the flat clear
+//! color too, drawn on a quad that fills the window behind everything else. Turn it on
+//! with `BackgroundGradient::enabled` or the `--gradient` command line flag.
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    window::PrimaryWindow,
+};
+
+use crate::snake::PlayerId;
+
+/// A light or dark color scheme preference
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    /// Reads `light` or `dark`, ignoring case and surrounding whitespace
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "light" => Some(ColorScheme::Light),
+            "dark" => Some(ColorScheme::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// A color palette preset
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// Every preset, in the order they are cycled through
+    pub const ALL: [Theme; 2] = [Theme::Dark, Theme::Light];
+
+    /// The preset after this one, wrapping back around to the first
+    pub fn next(self) -> Theme {
+        let index = Self::ALL
+            .iter()
+            .position(|theme| *theme == self)
+            .unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn background(self) -> Color {
+        match self {
+            Theme::Dark => Color::srgb(0.04, 0.04, 0.04),
+            Theme::Light => Color::srgb(0.92, 0.92, 0.9),
+        }
+    }
+
+    /// The colors at the bottom and top of the background gradient
+    pub fn gradient(self) -> (Color, Color) {
+        match self {
+            Theme::Dark => (Color::srgb(0.02, 0.02, 0.03), Color::srgb(0.1, 0.1, 0.14)),
+            Theme::Light => (Color::srgb(0.84, 0.85, 0.82), Color::srgb(0.97, 0.97, 0.95)),
+        }
+    }
+
+    /// The snake colors of each player, dark on the light theme and light on the dark
+    pub fn player_colors(self) -> PlayerColors {
+        let snake = |head: Color, body: Color, tail: Color| SnakeColors { head, body, tail };
+        PlayerColors(match self {
+            Theme::Dark => vec![
+                snake(
+                    Color::srgb(0.7, 0.7, 0.7),
+                    Color::srgb(0.3, 0.3, 0.3),
+                    Color::srgb(0.18, 0.18, 0.18),
+                ),
+                snake(
+                    Color::srgb(0.45, 0.85, 0.45),
+                    Color::srgb(0.2, 0.5, 0.2),
+                    Color::srgb(0.12, 0.32, 0.12),
+                ),
+                snake(
+                    Color::srgb(0.95, 0.65, 0.3),
+                    Color::srgb(0.6, 0.35, 0.12),
+                    Color::srgb(0.4, 0.22, 0.08),
+                ),
+                snake(
+                    Color::srgb(0.45, 0.65, 0.95),
+                    Color::srgb(0.2, 0.32, 0.6),
+                    Color::srgb(0.12, 0.2, 0.4),
+                ),
+            ],
+            Theme::Light => vec![
+                snake(
+                    Color::srgb(0.2, 0.2, 0.2),
+                    Color::srgb(0.45, 0.45, 0.45),
+                    Color::srgb(0.6, 0.6, 0.6),
+                ),
+                snake(
+                    Color::srgb(0.1, 0.45, 0.1),
+                    Color::srgb(0.3, 0.62, 0.3),
+                    Color::srgb(0.5, 0.75, 0.5),
+                ),
+                snake(
+                    Color::srgb(0.6, 0.3, 0.05),
+                    Color::srgb(0.82, 0.5, 0.2),
+                    Color::srgb(0.9, 0.68, 0.45),
+                ),
+                snake(
+                    Color::srgb(0.1, 0.25, 0.6),
+                    Color::srgb(0.3, 0.45, 0.8),
+                    Color::srgb(0.52, 0.64, 0.88),
+                ),
+            ],
+        })
+    }
+}
+
+/// The colors a snake is drawn in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SnakeColors {
+    pub head: Color,
+    pub body: Color,
+    /// The last segment of the body
+    pub tail: Color,
+}
+
+/// The snake colors of each player, indexed by `PlayerId`. Players past the end of the
+/// list reuse the colors from the start of it.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct PlayerColors(pub Vec<SnakeColors>);
+
+impl PlayerColors {
+    /// The colors the snake of `player` is drawn in
+    pub fn of(&self, player: PlayerId) -> SnakeColors {
+        if self.0.is_empty() {
+            return Self::default().of(player);
+        }
+        self.0[player.0 as usize % self.0.len()]
+    }
+}
+
+impl Default for PlayerColors {
+    fn default() -> Self {
+        Theme::Dark.player_colors()
+    }
+}
+
+/// Where the snake colors of the players come from
+#[derive(Resource, Clone, PartialEq, Debug, Default)]
+pub enum PlayerColorSetting {
+    /// The palette of the active theme
+    #[default]
+    FromTheme,
+    Fixed(PlayerColors),
+}
+
+/// Chooses the snake colors of the players for a setting given the active theme
+pub fn resolve_player_colors(setting: &PlayerColorSetting, theme: Theme) -> PlayerColors {
+    match setting {
+        PlayerColorSetting::FromTheme => theme.player_colors(),
+        PlayerColorSetting::Fixed(colors) => colors.clone(),
+    }
+}
+
+/// Blends the background gradient at `height`, from 0 at the bottom of the window to
+/// 1 at the top. Heights outside that range are clamped.
+pub fn gradient_color(bottom: Color, top: Color, height: f32) -> Color {
+    let height = if height.is_nan() {
+        0.
+    } else {
+        height.clamp(0., 1.)
+    };
+    bottom.to_srgba().mix(&top.to_srgba(), height).into()
+}
+
+/// Whether the background is drawn as a vertical gradient instead of the flat clear
+/// color. Off by default.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct BackgroundGradient {
+    pub enabled: bool,
+}
+
+/// Draws the gradient behind everything else, including the background grid
+const GRADIENT_DEPTH: f32 = -10.0;
+
+/// Marks the full window quad the gradient is drawn on
+#[derive(Component)]
+struct GradientBackdrop;
+
+/// A unit square with its corners colored from `bottom` to `top`
+fn gradient_mesh(bottom: Color, top: Color) -> Mesh {
+    let positions = vec![
+        [-0.5, -0.5, 0.],
+        [0.5, -0.5, 0.],
+        [0.5, 0.5, 0.],
+        [-0.5, 0.5, 0.],
+    ];
+    let colors: Vec<[f32; 4]> = positions
+        .iter()
+        .map(|[_, y, _]| LinearRgba::from(gradient_color(bottom, top, y + 0.5)).to_f32_array())
+        .collect();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(vec![0, 1, 2, 0, 2, 3]))
+}
+
+/// Rebuilds the gradient for the current theme, or hides it when turned off
+fn apply_gradient(
+    mut commands: Commands,
+    setting: Res<ThemeSetting>,
+    preferred: Res<PreferredColorScheme>,
+    gradient: Res<BackgroundGradient>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<ColorMaterial>>>,
+    backdrops: Query<Entity, With<GradientBackdrop>>,
+) {
+    if !setting.is_changed() && !preferred.is_changed() && !gradient.is_changed() {
+        return;
+    }
+    for backdrop in backdrops.iter() {
+        commands.entity(backdrop).despawn();
+    }
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+    if !gradient.enabled {
+        return;
+    }
+    let (bottom, top) = active_theme(*setting, &preferred).gradient();
+    commands.spawn((
+        Mesh2d(meshes.add(gradient_mesh(bottom, top))),
+        MeshMaterial2d(materials.add(ColorMaterial::default())),
+        Transform::from_xyz(0., 0., GRADIENT_DEPTH),
+        GradientBackdrop,
+    ));
+}
+
+/// Stretches the gradient over the whole window
+fn fit_gradient(
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut backdrops: Query<&mut Transform, With<GradientBackdrop>>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    for mut transform in backdrops.iter_mut() {
+        transform.scale = window.size().extend(1.);
+    }
+}
+
+/// How rounded the corners of the snake are, as a fraction of a tile.
+///
+/// Only the outer corners of the body are rounded, so straight runs and turns still
+/// read as one continuous body. A radius of zero draws plain squares.
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct SegmentCorners {
+    pub radius: f32,
+}
+
+impl SegmentCorners {
+    /// The radius limited to the range a tile can hold, from square to fully round
+    pub fn clamped_radius(&self) -> f32 {
+        if self.radius.is_nan() {
+            return 0.;
+        }
+        self.radius.clamp(0., 0.5)
+    }
+}
+
+impl Default for SegmentCorners {
+    fn default() -> Self {
+        Self { radius: 0.25 }
+    }
+}
+
+/// Which theme to use, either following the system or fixed by the player
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ThemeSetting {
+    #[default]
+    Auto,
+    Fixed(Theme),
+}
+
+/// Chooses the theme for a setting given the detected system color scheme
+pub fn resolve_theme(setting: ThemeSetting, detected: Option<ColorScheme>) -> Theme {
+    match (setting, detected) {
+        (ThemeSetting::Fixed(theme), _) => theme,
+        (ThemeSetting::Auto, Some(ColorScheme::Light)) => Theme::Light,
+        (ThemeSetting::Auto, Some(ColorScheme::Dark) | None) => Theme::Dark,
+    }
+}
+
+/// The color scheme the system prefers, or `None` when no preference was found
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PreferredColorScheme(pub Option<ColorScheme>);
+
+/// The theme currently in use for `setting`
+pub fn active_theme(setting: ThemeSetting, preferred: &PreferredColorScheme) -> Theme {
+    resolve_theme(setting, preferred.0)
+}
+
+fn apply_theme(
+    setting: Res<ThemeSetting>,
+    preferred: Res<PreferredColorScheme>,
+    player_colors: Res<PlayerColorSetting>,
+    mut clear_color: ResMut<ClearColor>,
+    mut colors: ResMut<PlayerColors>,
+) {
+    if setting.is_changed() || preferred.is_changed() || player_colors.is_changed() {
+        let theme = active_theme(*setting, &preferred);
+        clear_color.0 = theme.background();
+        *colors = resolve_player_colors(&player_colors, theme);
+    }
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ThemeSetting>();
+        app.init_resource::<PreferredColorScheme>();
+        app.init_resource::<SegmentCorners>();
+        app.init_resource::<PlayerColorSetting>();
+        app.init_resource::<PlayerColors>();
+        app.init_resource::<BackgroundGradient>();
+        app.insert_resource(ClearColor(Theme::Dark.background()));
+        app.add_systems(Update, (apply_theme, apply_gradient));
+        app.add_systems(PostUpdate, fit_gradient);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_theme_follows_detected_scheme() {
+        assert_eq!(
+            resolve_theme(ThemeSetting::Auto, Some(ColorScheme::Light)),
+            Theme::Light
+        );
+        assert_eq!(
+            resolve_theme(ThemeSetting::Auto, Some(ColorScheme::Dark)),
+            Theme::Dark
+        );
+        assert_eq!(resolve_theme(ThemeSetting::Auto, None), Theme::Dark);
+    }
+
+    #[test]
+    fn test_fixed_theme_overrides_detection() {
+        assert_eq!(
+            resolve_theme(ThemeSetting::Fixed(Theme::Dark), Some(ColorScheme::Light)),
+            Theme::Dark
+        );
+        assert_eq!(
+            resolve_theme(ThemeSetting::Fixed(Theme::Light), None),
+            Theme::Light
+        );
+    }
+
+    #[test]
+    fn test_player_colors_follow_the_theme_unless_fixed() {
+        let first = |colors: PlayerColors| colors.of(PlayerId(0));
+        assert_eq!(
+            first(resolve_player_colors(
+                &PlayerColorSetting::FromTheme,
+                Theme::Light
+            )),
+            first(Theme::Light.player_colors())
+        );
+        assert_ne!(
+            first(Theme::Light.player_colors()),
+            first(Theme::Dark.player_colors())
+        );
+
+        let fixed = PlayerColors(vec![SnakeColors {
+            head: Color::WHITE,
+            body: Color::BLACK,
+            tail: Color::BLACK,
+        }]);
+        let setting = PlayerColorSetting::Fixed(fixed.clone());
+        assert_eq!(resolve_player_colors(&setting, Theme::Light), fixed);
+        // Players past the end of the palette wrap around to its start
+        assert_eq!(fixed.of(PlayerId(3)), fixed.of(PlayerId(0)));
+    }
+
+    #[test]
+    fn test_corner_radius_is_clamped() {
+        let clamped = |radius| SegmentCorners { radius }.clamped_radius();
+        assert_eq!(clamped(0.25), 0.25);
+        assert_eq!(clamped(-1.), 0.);
+        assert_eq!(clamped(3.), 0.5);
+        assert_eq!(clamped(f32::NAN), 0.);
+    }
+
+    #[test]
+    fn test_parse_color_scheme() {
+        assert_eq!(ColorScheme::parse("Light"), Some(ColorScheme::Light));
+        assert_eq!(ColorScheme::parse(" dark\n"), Some(ColorScheme::Dark));
+        assert_eq!(ColorScheme::parse("sepia"), None);
+    }
+
+    #[test]
+    fn test_gradient_blends_from_bottom_to_top() {
+        let bottom = Color::srgb(0., 0.2, 0.4);
+        let top = Color::srgb(1., 0.6, 0.4);
+        assert_eq!(gradient_color(bottom, top, 0.), bottom);
+        assert_eq!(gradient_color(bottom, top, 1.), top);
+        assert_eq!(gradient_color(bottom, top, 0.5), Color::srgb(0.5, 0.4, 0.4));
+        assert_eq!(gradient_color(bottom, top, -2.), bottom);
+        assert_eq!(gradient_color(bottom, top, 3.), top);
+    }
+}
@@ -0,0 +1,72 @@
+//! Tile module
+//!
+//! This module lets floor tiles react to the snake.
+//!
+//! A `TileEffect` is registered for a tile in the `TileEffects` resource and is run
+//! by `movement` every time the snake head enters that tile. Effects can change the
+//! world through `Commands` and can end the run, so custom behaviors need no changes
+//! to the movement code.
+//!
+//! Spikes are the one built-in floor feature, and are registered the same way. Walls
+//! and holes in the arena mask are not tile effects: they are solid like the edge of
+//! the arena, and stay part of the collision checks.
+
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::prelude::*;
+
+use crate::arena::Position;
+
+/// What happens to the snake after entering a tile
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TileOutcome {
+    /// The snake carries on
+    Continue,
+    /// The run ends, just as if the snake had hit a wall
+    Kill,
+}
+
+/// Behavior attached to a floor tile
+pub trait TileEffect: Send + Sync + 'static {
+    /// Called when the snake head enters `tile` on movement tick `tick`
+    fn on_enter(&self, tile: Position, tick: u64, commands: &mut Commands) -> TileOutcome;
+}
+
+/// The effects registered for each tile. A tile holds at most one effect.
+#[derive(Resource, Default, Clone)]
+pub struct TileEffects {
+    effects: HashMap<Position, Arc<dyn TileEffect>>,
+}
+
+impl TileEffects {
+    /// Registers `effect` on `tile`, replacing any effect already there
+    pub fn insert(&mut self, tile: Position, effect: impl TileEffect) {
+        self.effects.insert(tile, Arc::new(effect));
+    }
+
+    /// Registers one shared effect on every tile in `tiles`
+    pub fn insert_shared(
+        &mut self,
+        tiles: impl IntoIterator<Item = Position>,
+        effect: Arc<dyn TileEffect>,
+    ) {
+        for tile in tiles {
+            self.effects.insert(tile, effect.clone());
+        }
+    }
+
+    pub fn remove(&mut self, tile: Position) {
+        self.effects.remove(&tile);
+    }
+
+    pub fn get(&self, tile: Position) -> Option<&dyn TileEffect> {
+        self.effects.get(&tile).map(|effect| effect.as_ref())
+    }
+
+    /// Runs the effect on `tile`, if any
+    pub fn enter(&self, tile: Position, tick: u64, commands: &mut Commands) -> TileOutcome {
+        self.get(tile).map_or(TileOutcome::Continue, |effect| {
+            effect.on_enter(tile, tick, commands)
+        })
+    }
+}